@@ -0,0 +1,178 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::error::SolveError;
+use crate::solution::DynSolution;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Phase {
+    Parse,
+    Part(u32),
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Phase::Parse => write!(f, "parse"),
+            Phase::Part(part) => write!(f, "part_{part}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Measurement {
+    pub day: u32,
+    pub phase: Phase,
+    pub runs: Vec<Duration>,
+}
+
+impl Measurement {
+    pub fn min(&self) -> Duration {
+        self.runs.iter().min().copied().unwrap_or_default()
+    }
+
+    pub fn max(&self) -> Duration {
+        self.runs.iter().max().copied().unwrap_or_default()
+    }
+
+    pub fn median(&self) -> Duration {
+        let mut runs = self.runs.clone();
+        runs.sort();
+
+        match runs.len() {
+            0 => Duration::default(),
+            length if length % 2 == 1 => runs[length / 2],
+            length => (runs[length / 2 - 1] + runs[length / 2]) / 2,
+        }
+    }
+}
+
+// Only `phase` is timed, `prepare` runs untimed before each run.
+fn time<S, T>(
+    runs: usize,
+    mut prepare: impl FnMut() -> Result<S, SolveError>,
+    mut phase: impl FnMut(&S) -> Result<T, SolveError>,
+) -> Result<Vec<Duration>, SolveError> {
+    (0..runs)
+        .map(|_| {
+            let prepared = prepare()?;
+            let start = Instant::now();
+            phase(&prepared)?;
+            Ok(start.elapsed())
+        })
+        .collect()
+}
+
+// Every run solves from a fresh parse, as some inputs keep what a solve works out, so the solve timings exclude parsing but never time a cached result.
+pub fn measure(
+    solution: &dyn DynSolution,
+    input: &str,
+    runs: usize,
+) -> Result<Vec<Measurement>, SolveError> {
+    let day = solution.day();
+    let mut measurements = vec![Measurement {
+        day,
+        phase: Phase::Parse,
+        runs: time(runs, || Ok(()), |_| Ok(solution.parse(input)?))?,
+    }];
+
+    for part in 1..=solution.parts() {
+        measurements.push(Measurement {
+            day,
+            phase: Phase::Part(part),
+            runs: time(
+                runs,
+                || Ok(solution.parse(input)?),
+                |parsed_input| solution.solve(parsed_input, part),
+            )?,
+        });
+    }
+
+    Ok(measurements)
+}
+
+// Tab separated with nanosecond timings, so reports from two commits can be diffed line by line.
+pub fn report(measurements: &[Measurement]) -> String {
+    let mut report = String::from("day\tphase\truns\tmin_ns\tmedian_ns\tmax_ns\n");
+
+    for measurement in measurements {
+        report.push_str(&format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\n",
+            measurement.day,
+            measurement.phase,
+            measurement.runs.len(),
+            measurement.min().as_nanos(),
+            measurement.median().as_nanos(),
+            measurement.max().as_nanos(),
+        ));
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    use crate::day_1::Day1;
+    use crate::utilities::input::puzzle_input;
+
+    fn measurement(runs: &[u64]) -> Measurement {
+        Measurement {
+            day: 1,
+            phase: Phase::Parse,
+            runs: runs.iter().map(|run| Duration::from_nanos(*run)).collect(),
+        }
+    }
+
+    #[rstest]
+    #[case(&[], 0, 0, 0)]
+    #[case(&[5], 5, 5, 5)]
+    #[case(&[9, 1, 4], 1, 4, 9)]
+    #[case(&[8, 2, 4, 6], 2, 5, 8)]
+    fn test_statistics(
+        #[case] runs: &[u64],
+        #[case] min: u64,
+        #[case] median: u64,
+        #[case] max: u64,
+    ) {
+        let measurement = measurement(runs);
+
+        assert_eq!(Duration::from_nanos(min), measurement.min());
+        assert_eq!(Duration::from_nanos(median), measurement.median());
+        assert_eq!(Duration::from_nanos(max), measurement.max());
+    }
+
+    #[test]
+    fn test_measure() {
+        let input = puzzle_input(true, 1, None).read().unwrap();
+        let measurements = measure(&Day1, &input, 3).unwrap();
+
+        let phases: Vec<_> = measurements.iter().map(|m| m.phase).collect();
+        assert_eq!(vec![Phase::Parse, Phase::Part(1), Phase::Part(2)], phases);
+        assert!(measurements.iter().all(|m| m.runs.len() == 3));
+    }
+
+    #[test]
+    fn test_time_prepares_every_run() {
+        let mut prepared = 0;
+        let prepare = || {
+            prepared += 1;
+            Ok(prepared)
+        };
+        let runs = time(4, prepare, |_| Ok(())).unwrap();
+
+        assert_eq!((4, 4), (runs.len(), prepared));
+    }
+
+    #[test]
+    fn test_report() {
+        let measurements = vec![measurement(&[3, 1, 2])];
+
+        assert_eq!(
+            "day\tphase\truns\tmin_ns\tmedian_ns\tmax_ns\n1\tparse\t3\t1\t2\t3\n",
+            report(&measurements)
+        );
+    }
+}
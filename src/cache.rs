@@ -0,0 +1,206 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use rustc_hash::FxHashMap;
+
+use crate::error::ParseError;
+use crate::solution::{DynSolution, ParsedInput};
+
+// The last byte is the version of the file layout.
+const MAGIC: &[u8; 8] = b"AOC2020\x01";
+
+// 64 bit FNV-1a.
+pub fn fingerprint(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Lookup {
+    Memory,
+    Disk,
+    Parsed,
+}
+
+// A day and the fingerprint of its input text.
+type Key = (u32, u64);
+type Slot = Arc<OnceLock<Result<Arc<ParsedInput>, ParseError>>>;
+
+// Parsed inputs by content, so both parts of a day and later runs of the same input share one parse.
+#[derive(Default)]
+pub struct InputCache {
+    slots: Mutex<FxHashMap<Key, Slot>>,
+    directory: Mutex<Option<PathBuf>>,
+    // Fingerprints of the payloads on disk, so unchanged inputs are not written again.
+    stored: Mutex<FxHashMap<Key, u64>>,
+}
+
+impl InputCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn shared() -> &'static Self {
+        static SHARED: OnceLock<InputCache> = OnceLock::new();
+        SHARED.get_or_init(InputCache::new)
+    }
+
+    // Inputs are also kept in this directory, for days that can encode them.
+    pub fn set_directory(&self, directory: Option<PathBuf>) {
+        *self.directory.lock().unwrap() = directory;
+        self.stored.lock().unwrap().clear();
+    }
+
+    // Each input is parsed at most once, even when several threads ask for it together.
+    pub fn parsed(
+        &self,
+        solution: &dyn DynSolution,
+        text: &str,
+    ) -> Result<(Arc<ParsedInput>, Lookup), ParseError> {
+        let key = (solution.day(), fingerprint(text.as_bytes()));
+        let slot = self.slots.lock().unwrap().entry(key).or_default().clone();
+        let mut lookup = Lookup::Memory;
+
+        let input = slot.get_or_init(|| {
+            if let Some(input) = self.load(solution, key) {
+                lookup = Lookup::Disk;
+                return Ok(Arc::new(input));
+            }
+
+            lookup = Lookup::Parsed;
+            solution.parse(text).map(Arc::new)
+        });
+
+        input.clone().map(|input| (input, lookup))
+    }
+
+    fn path(&self, (day, fingerprint): Key) -> Option<PathBuf> {
+        let directory = self.directory.lock().unwrap();
+        Some(
+            directory
+                .as_ref()?
+                .join(format!("day{day}-{fingerprint:016x}.bin")),
+        )
+    }
+
+    fn load(&self, solution: &dyn DynSolution, key: Key) -> Option<ParsedInput> {
+        let bytes = fs::read(self.path(key)?).ok()?;
+        let payload = bytes.strip_prefix(MAGIC)?;
+        let input = solution.decode(payload)?;
+
+        self.stored
+            .lock()
+            .unwrap()
+            .insert(key, fingerprint(payload));
+        Some(input)
+    }
+
+    // Stores the input with whatever it has worked out so far. A failed write only costs the next run a parse.
+    pub fn save(&self, solution: &dyn DynSolution, text: &str, input: &ParsedInput) {
+        let key = (solution.day(), fingerprint(text.as_bytes()));
+        let (Some(path), Some(payload)) = (self.path(key), solution.encode(input)) else {
+            return;
+        };
+
+        let payload_fingerprint = fingerprint(&payload);
+        if self.stored.lock().unwrap().insert(key, payload_fingerprint) == Some(payload_fingerprint)
+        {
+            return;
+        }
+
+        // Readers in other processes only ever see a whole file.
+        let partial = path.with_extension("partial");
+        if fs::write(&partial, [MAGIC.as_slice(), &payload].concat()).is_ok() {
+            let _ = fs::rename(&partial, &path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use std::env;
+    use std::process;
+
+    use crate::registry;
+    use crate::solution::Answer;
+    use crate::utilities::input::puzzle_input;
+
+    // Every test gets its own directory, tests run in parallel.
+    fn directory(name: &str) -> PathBuf {
+        let directory = env::temp_dir().join(format!("advent2020-{}-{name}", process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    #[rstest]
+    #[case("", 0xcbf29ce484222325)]
+    #[case("a", 0xaf63dc4c8601ec8c)]
+    #[case("foobar", 0x85944171f73967e8)]
+    fn test_fingerprint(#[case] text: &str, #[case] expected: u64) {
+        assert_eq!(expected, fingerprint(text.as_bytes()));
+    }
+
+    #[test]
+    fn test_memory() {
+        let cache = InputCache::new();
+        let day_1 = registry::find(1).unwrap();
+        let lookup = |text| cache.parsed(day_1, text).map(|(_, lookup)| lookup);
+
+        assert_eq!(Ok(Lookup::Parsed), lookup("1721\n979"));
+        assert_eq!(Ok(Lookup::Memory), lookup("1721\n979"));
+        assert_eq!(Ok(Lookup::Parsed), lookup("1721\n366"));
+        assert!(lookup("x").is_err());
+    }
+
+    #[test]
+    fn test_disk() {
+        let directory = directory("disk");
+        let day_20 = registry::find(20).unwrap();
+        let text = puzzle_input(true, 20, None).read().unwrap();
+
+        let cache = InputCache::new();
+        cache.set_directory(Some(directory.clone()));
+        let (input, _) = cache.parsed(day_20, &text).unwrap();
+        day_20.solve(&input, 1).unwrap();
+        cache.save(day_20, &text, &input);
+
+        let cache = InputCache::new();
+        cache.set_directory(Some(directory.clone()));
+        let (input, lookup) = cache.parsed(day_20, &text).unwrap();
+
+        assert_eq!(Lookup::Disk, lookup);
+        assert_eq!(Ok(Answer::from(273_i64)), day_20.solve(&input, 2));
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_disk_ignores_bad_files() {
+        let directory = directory("bad");
+        let day_9 = registry::find(9).unwrap();
+        let text = "35\n20\n15";
+        let path = directory.join(format!("day9-{:016x}.bin", fingerprint(text.as_bytes())));
+        fs::write(&path, b"AOC2020\x01truncated").unwrap();
+
+        let cache = InputCache::new();
+        cache.set_directory(Some(directory.clone()));
+        let (input, lookup) = cache.parsed(day_9, text).unwrap();
+        assert_eq!(Lookup::Parsed, lookup);
+
+        // The bad file is replaced by a good one.
+        cache.save(day_9, text, &input);
+        let cache = InputCache::new();
+        cache.set_directory(Some(directory.clone()));
+        assert_eq!(
+            Ok(Lookup::Disk),
+            cache.parsed(day_9, text).map(|(_, lookup)| lookup)
+        );
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,602 @@
+use std::thread;
+use std::time::Duration;
+
+use crate::day_2;
+use crate::generate::Options;
+use crate::ledger;
+use crate::output::Format;
+use crate::registry;
+use crate::render::{self, SIMULATIONS};
+use crate::utilities::image::ImageFormat;
+use crate::utilities::input::{puzzle_input, InputSource};
+
+pub const USAGE: &str = "Usage:
+    Advent2020 run --day <1-25> --part <1|2> (--input <path> | --stdin | --test | --real)
+        [--format <text|json|csv>] [--trace <log|count> | --trace-file <path>] [--cache <dir>]
+    Advent2020 run --all (--test | --real) [--jobs <count>] [--timeout <seconds>]
+        [--format <text|json|csv>] [--cache <dir>] [--answers <path>]
+        where a part that times out keeps running in the background, outside the --jobs
+        limit, until it finishes or the run ends, and a part with no example answer in the
+        --answers ledger is skipped rather than failed under --test
+    Advent2020 bench [--day <1-25>] [--runs <count>] [--test | --real] [--report <path>]
+    Advent2020 verify [--day <1-25>] [--answers <path>]
+    Advent2020 generate --day <1-25> [--seed <number>] [--size <number>]
+        [--invalid <field,field,...>] [--output <path>]
+    Advent2020 repl --day <1-25> (--input <path> | --test | --real)
+    Advent2020 render --day <11|12|17|20|24> (--input <path> | --test | --real) --output <path>
+        [--part <1|2>] [--format <ppm|pbm|svg>] [--scale <pixels>] [--frames]
+    Advent2020 audit (--input <path> | --test | --real) [--size <count>] [--target <number>]
+    Advent2020 passwords (--input <path> | --test | --real) [--policy <policy>]...
+        where a policy is count, positions, regex:<pattern>, min-length:<length>,
+        classes:<lower,upper,digit,symbol> or forbid:<text,...>";
+
+pub const DEFAULT_RUNS: u32 = 5;
+pub const DEFAULT_REPORT: &str = "bench_output.txt";
+pub const DEFAULT_TIMEOUT_SECONDS: u32 = 60;
+
+pub enum InputChoice {
+    Path(String),
+    Stdin,
+    Test,
+    Real,
+}
+
+// Log events to stderr, count them, or log them to a file.
+pub enum Trace {
+    Log,
+    Count,
+    File(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Selection {
+    One { day: u32, part: u32 },
+    All,
+}
+
+pub struct RunArguments {
+    pub selection: Selection,
+    pub input: InputChoice,
+    pub format: Format,
+    pub jobs: usize,
+    pub timeout: Duration,
+    pub trace: Option<Trace>,
+    pub cache: Option<String>,
+    pub answers: String,
+}
+
+pub struct BenchArguments {
+    pub day: Option<u32>,
+    pub runs: u32,
+    pub is_test: bool,
+    pub report: String,
+}
+
+pub fn parse_run_arguments(arguments: &[String]) -> Result<RunArguments, String> {
+    let mut day = None;
+    let mut part = None;
+    let mut all = false;
+    let mut input = None;
+    let mut format = Format::Text;
+    let mut jobs = thread::available_parallelism().map_or(1, |jobs| jobs.get() as u32);
+    let mut timeout = DEFAULT_TIMEOUT_SECONDS;
+    let mut trace = None;
+    let mut cache = None;
+    let mut answers = None;
+
+    let mut arguments = arguments.iter();
+
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--day" => day = Some(parse_flag_value(argument, arguments.next())?),
+            "--part" => part = Some(parse_flag_value(argument, arguments.next())?),
+            "--all" => all = true,
+            "--jobs" => jobs = parse_flag_value(argument, arguments.next())?,
+            "--timeout" => timeout = parse_flag_value(argument, arguments.next())?,
+            "--input" => {
+                let path = arguments
+                    .next()
+                    .ok_or_else(|| format!("{argument} expects a value"))?;
+                input = Some(InputChoice::Path(path.clone()));
+            }
+            "--stdin" => input = Some(InputChoice::Stdin),
+            "--test" => input = Some(InputChoice::Test),
+            "--real" => input = Some(InputChoice::Real),
+            "--format" => {
+                format = arguments
+                    .next()
+                    .ok_or_else(|| format!("{argument} expects a value"))?
+                    .parse()?;
+            }
+            "--trace" => {
+                trace = match arguments.next().map(String::as_str) {
+                    Some("log") => Some(Trace::Log),
+                    Some("count") => Some(Trace::Count),
+                    _ => return Err(format!("{argument} expects log or count")),
+                };
+            }
+            "--trace-file" => {
+                let path = arguments
+                    .next()
+                    .ok_or_else(|| format!("{argument} expects a value"))?;
+                trace = Some(Trace::File(path.clone()));
+            }
+            "--cache" => {
+                let directory = arguments
+                    .next()
+                    .ok_or_else(|| format!("{argument} expects a value"))?;
+                cache = Some(directory.clone());
+            }
+            "--answers" => {
+                let path = arguments
+                    .next()
+                    .ok_or_else(|| format!("{argument} expects a value"))?;
+                answers = Some(path.clone());
+            }
+            other => return Err(format!("Unknown argument {other}")),
+        }
+    }
+
+    if jobs == 0 {
+        return Err(String::from("--jobs expects at least 1 job"));
+    }
+
+    if timeout == 0 {
+        return Err(String::from("--timeout expects at least 1 second"));
+    }
+
+    let selection = if all {
+        if day.is_some() || part.is_some() {
+            return Err(String::from(
+                "--all cannot be combined with --day or --part",
+            ));
+        }
+
+        // Events from parallel solvers would interleave into nonsense.
+        if trace.is_some() {
+            return Err(String::from("--all cannot be traced"));
+        }
+
+        if !matches!(input, None | Some(InputChoice::Test | InputChoice::Real)) {
+            return Err(String::from("--all only reads --test or --real inputs"));
+        }
+
+        Selection::All
+    } else {
+        if answers.is_some() {
+            return Err(String::from("--answers only applies to --all"));
+        }
+
+        let day = day.ok_or("Missing --day or --all")?;
+        let part = part.ok_or("Missing --part")?;
+
+        if !(1..=25).contains(&day) {
+            return Err(format!("There is no day {day}"));
+        }
+
+        if !(1..=2).contains(&part) {
+            return Err(format!("There is no part {part}"));
+        }
+
+        Selection::One { day, part }
+    };
+
+    let input = input.ok_or("Missing one of --input, --stdin, --test or --real")?;
+
+    Ok(RunArguments {
+        selection,
+        input,
+        format,
+        jobs: jobs as usize,
+        timeout: Duration::from_secs(timeout.into()),
+        trace,
+        cache,
+        answers: answers.unwrap_or_else(|| String::from(ledger::DEFAULT_LEDGER)),
+    })
+}
+
+pub fn parse_bench_arguments(arguments: &[String]) -> Result<BenchArguments, String> {
+    let mut day = None;
+    let mut runs = DEFAULT_RUNS;
+    let mut is_test = false;
+    let mut report = String::from(DEFAULT_REPORT);
+
+    let mut arguments = arguments.iter();
+
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--day" => day = Some(parse_flag_value(argument, arguments.next())?),
+            "--runs" => runs = parse_flag_value(argument, arguments.next())?,
+            "--test" => is_test = true,
+            "--real" => is_test = false,
+            "--report" => {
+                report = arguments
+                    .next()
+                    .ok_or_else(|| format!("{argument} expects a value"))?
+                    .clone();
+            }
+            other => return Err(format!("Unknown argument {other}")),
+        }
+    }
+
+    if let Some(day) = day {
+        if !(1..=25).contains(&day) {
+            return Err(format!("There is no day {day}"));
+        }
+    }
+
+    if runs == 0 {
+        return Err(String::from("--runs expects at least 1 run"));
+    }
+
+    Ok(BenchArguments {
+        day,
+        runs,
+        is_test,
+        report,
+    })
+}
+
+pub struct VerifyArguments {
+    pub day: Option<u32>,
+    pub answers: String,
+}
+
+pub fn parse_verify_arguments(arguments: &[String]) -> Result<VerifyArguments, String> {
+    let mut day = None;
+    let mut answers = String::from(ledger::DEFAULT_LEDGER);
+
+    let mut arguments = arguments.iter();
+
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--day" => day = Some(parse_flag_value(argument, arguments.next())?),
+            "--answers" => {
+                answers = arguments
+                    .next()
+                    .ok_or_else(|| format!("{argument} expects a value"))?
+                    .clone();
+            }
+            other => return Err(format!("Unknown argument {other}")),
+        }
+    }
+
+    if let Some(day) = day {
+        if registry::find(day).is_none() {
+            return Err(format!("There is no day {day}"));
+        }
+    }
+
+    Ok(VerifyArguments { day, answers })
+}
+
+pub struct GenerateArguments {
+    pub day: u32,
+    pub options: Options,
+    pub output: Option<String>,
+}
+
+pub fn parse_generate_arguments(arguments: &[String]) -> Result<GenerateArguments, String> {
+    let mut day = None;
+    let mut options = Options::default();
+    let mut output = None;
+
+    let mut arguments = arguments.iter();
+
+    while let Some(argument) = arguments.next() {
+        let mut value = || {
+            arguments
+                .next()
+                .ok_or_else(|| format!("{argument} expects a value"))
+        };
+
+        match argument.as_str() {
+            "--day" => day = Some(parse_flag_value(argument, Some(value()?))?),
+            "--seed" => {
+                let seed = value()?;
+                options.seed = seed
+                    .parse()
+                    .map_err(|_| format!("{argument} expects a number, got {seed}"))?;
+            }
+            "--size" => {
+                let size = value()?;
+                options.size = Some(
+                    size.parse()
+                        .map_err(|_| format!("{argument} expects a number, got {size}"))?,
+                );
+            }
+            "--invalid" => {
+                options.invalid_fields = value()?.split(',').map(String::from).collect();
+            }
+            "--output" => output = Some(value()?.clone()),
+            other => return Err(format!("Unknown argument {other}")),
+        }
+    }
+
+    Ok(GenerateArguments {
+        day: day.ok_or("Missing --day")?,
+        options,
+        output,
+    })
+}
+
+pub struct ReplArguments {
+    pub day: u32,
+    pub source: InputSource,
+}
+
+pub fn parse_repl_arguments(arguments: &[String]) -> Result<ReplArguments, String> {
+    let mut day = None;
+    let mut input = None;
+
+    let mut arguments = arguments.iter();
+
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--day" => day = Some(parse_flag_value(argument, arguments.next())?),
+            "--input" => {
+                let path = arguments
+                    .next()
+                    .ok_or_else(|| format!("{argument} expects a value"))?;
+                input = Some(InputChoice::Path(path.clone()));
+            }
+            "--test" => input = Some(InputChoice::Test),
+            "--real" => input = Some(InputChoice::Real),
+            other => return Err(format!("Unknown argument {other}")),
+        }
+    }
+
+    let day = day.ok_or("Missing --day")?;
+    if !(1..=25).contains(&day) {
+        return Err(format!("There is no day {day}"));
+    }
+
+    // Commands arrive on stdin, so the input has to come from somewhere else.
+    let source = match input.ok_or("Missing one of --input, --test or --real")? {
+        InputChoice::Path(path) => InputSource::Path(path),
+        InputChoice::Test => puzzle_input(true, day, None),
+        InputChoice::Real => puzzle_input(false, day, None),
+        InputChoice::Stdin => unreachable!("--stdin is not accepted by repl"),
+    };
+
+    Ok(ReplArguments { day, source })
+}
+
+pub struct RenderArguments {
+    pub day: u32,
+    pub source: InputSource,
+    pub options: render::Options,
+    pub format: ImageFormat,
+    pub output: String,
+}
+
+pub fn parse_render_arguments(arguments: &[String]) -> Result<RenderArguments, String> {
+    let mut day = None;
+    let mut input = None;
+    let mut options = render::Options::default();
+    let mut format = None;
+    let mut output = None;
+
+    let mut arguments = arguments.iter();
+
+    while let Some(argument) = arguments.next() {
+        let mut value = || {
+            arguments
+                .next()
+                .ok_or_else(|| format!("{argument} expects a value"))
+        };
+
+        match argument.as_str() {
+            "--day" => day = Some(parse_flag_value(argument, Some(value()?))?),
+            "--part" => options.part = parse_flag_value(argument, Some(value()?))?,
+            "--input" => input = Some(InputChoice::Path(value()?.clone())),
+            "--test" => input = Some(InputChoice::Test),
+            "--real" => input = Some(InputChoice::Real),
+            "--format" => format = Some(value()?.parse()?),
+            "--scale" => {
+                let scale = value()?;
+                options.scale = Some(
+                    scale
+                        .parse()
+                        .ok()
+                        .filter(|scale: &f64| *scale > 0.0)
+                        .ok_or_else(|| {
+                            format!("{argument} expects a positive number, got {scale}")
+                        })?,
+                );
+            }
+            "--frames" => options.frames = true,
+            "--output" => output = Some(value()?.clone()),
+            other => return Err(format!("Unknown argument {other}")),
+        }
+    }
+
+    let day = day.ok_or("Missing --day")?;
+    if !render::DAYS.contains(&day) {
+        return Err(format!("Day {day} has nothing to draw"));
+    }
+    if options.frames && !SIMULATIONS.contains(&day) {
+        return Err(format!(
+            "Day {day} is not a simulation, so it has no frames"
+        ));
+    }
+
+    let source = match input.ok_or("Missing one of --input, --test or --real")? {
+        InputChoice::Path(path) => InputSource::Path(path),
+        InputChoice::Test => puzzle_input(true, day, None),
+        InputChoice::Real => puzzle_input(false, day, None),
+        InputChoice::Stdin => unreachable!("--stdin is not accepted by render"),
+    };
+
+    let output: String = output.ok_or("Missing --output")?;
+    // Without --format the extension of the output decides.
+    let format = match format {
+        Some(format) => format,
+        None => output
+            .rsplit_once('.')
+            .map_or("", |(_, extension)| extension)
+            .parse()?,
+    };
+
+    Ok(RenderArguments {
+        day,
+        source,
+        options,
+        format,
+        output,
+    })
+}
+
+pub struct AuditArguments {
+    pub source: InputSource,
+    pub size: usize,
+    pub target: i64,
+}
+
+pub fn parse_audit_arguments(arguments: &[String]) -> Result<AuditArguments, String> {
+    let mut input = None;
+    let mut size = 2;
+    let mut target = 2020;
+
+    let mut arguments = arguments.iter();
+
+    while let Some(argument) = arguments.next() {
+        let mut value = || {
+            arguments
+                .next()
+                .ok_or_else(|| format!("{argument} expects a value"))
+        };
+
+        match argument.as_str() {
+            "--input" => input = Some(InputChoice::Path(value()?.clone())),
+            "--test" => input = Some(InputChoice::Test),
+            "--real" => input = Some(InputChoice::Real),
+            "--size" => size = parse_flag_value(argument, Some(value()?))? as usize,
+            "--target" => {
+                let value = value()?;
+                target = value
+                    .parse()
+                    .map_err(|_| format!("{argument} expects a number, got {value}"))?;
+            }
+            other => return Err(format!("Unknown argument {other}")),
+        }
+    }
+
+    let source = match input.ok_or("Missing one of --input, --test or --real")? {
+        InputChoice::Path(path) => InputSource::Path(path),
+        InputChoice::Test => puzzle_input(true, 1, None),
+        InputChoice::Real => puzzle_input(false, 1, None),
+        InputChoice::Stdin => unreachable!("--stdin is not accepted by audit"),
+    };
+
+    Ok(AuditArguments {
+        source,
+        size,
+        target,
+    })
+}
+
+pub struct PasswordsArguments {
+    pub source: InputSource,
+    pub policies: Vec<Box<dyn day_2::Policy>>,
+}
+
+// Without --policy the passwords are held to the count policy of part 1.
+pub fn parse_passwords_arguments(arguments: &[String]) -> Result<PasswordsArguments, String> {
+    let mut input = None;
+    let mut policies = vec![];
+
+    let mut arguments = arguments.iter();
+
+    while let Some(argument) = arguments.next() {
+        let mut value = || {
+            arguments
+                .next()
+                .ok_or_else(|| format!("{argument} expects a value"))
+        };
+
+        match argument.as_str() {
+            "--input" => input = Some(InputChoice::Path(value()?.clone())),
+            "--test" => input = Some(InputChoice::Test),
+            "--real" => input = Some(InputChoice::Real),
+            "--policy" => policies.push(day_2::parse_policy(value()?)?),
+            other => return Err(format!("Unknown argument {other}")),
+        }
+    }
+
+    if policies.is_empty() {
+        policies.push(Box::new(day_2::OccurrenceCount));
+    }
+
+    let source = match input.ok_or("Missing one of --input, --test or --real")? {
+        InputChoice::Path(path) => InputSource::Path(path),
+        InputChoice::Test => puzzle_input(true, 2, None),
+        InputChoice::Real => puzzle_input(false, 2, None),
+        InputChoice::Stdin => unreachable!("--stdin is not accepted by passwords"),
+    };
+
+    Ok(PasswordsArguments { source, policies })
+}
+
+fn parse_flag_value(flag: &str, value: Option<&String>) -> Result<u32, String> {
+    let value = value.ok_or_else(|| format!("{flag} expects a value"))?;
+    value
+        .parse::<u32>()
+        .map_err(|_| format!("{flag} expects a number, got {value}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn arguments(text: &str) -> Vec<String> {
+        text.split_whitespace().map(String::from).collect()
+    }
+
+    #[rstest]
+    #[case(
+        "--all --day 1 --test",
+        "--all cannot be combined with --day or --part"
+    )]
+    #[case("--all --test --trace count", "--all cannot be traced")]
+    #[case("--all --stdin", "--all only reads --test or --real inputs")]
+    #[case("--day 26 --part 1 --test", "There is no day 26")]
+    #[case("--day 1 --part 3 --test", "There is no part 3")]
+    #[case(
+        "--day 1 --part 1",
+        "Missing one of --input, --stdin, --test or --real"
+    )]
+    #[case("--day 1 --part 1 --test --jobs 0", "--jobs expects at least 1 job")]
+    #[case("--day x", "--day expects a number, got x")]
+    #[case(
+        "--day 1 --part 1 --test --answers answers.toml",
+        "--answers only applies to --all"
+    )]
+    fn test_parse_run_arguments_errors(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(
+            parse_run_arguments(&arguments(input)).err().as_deref(),
+            Some(expected)
+        );
+    }
+
+    #[rstest]
+    #[case("--all --real", Selection::All)]
+    #[case("--day 9 --part 2 --test", Selection::One { day: 9, part: 2 })]
+    fn test_parse_run_arguments(#[case] input: &str, #[case] expected: Selection) {
+        let parsed = parse_run_arguments(&arguments(input)).unwrap();
+
+        assert_eq!(parsed.selection, expected);
+    }
+
+    #[rstest]
+    #[case("--day 40", Some("There is no day 40"))]
+    #[case("--day 4 --answers answers.toml", None)]
+    #[case("--verbose", Some("Unknown argument --verbose"))]
+    fn test_parse_verify_arguments(#[case] input: &str, #[case] expected: Option<&str>) {
+        assert_eq!(
+            parse_verify_arguments(&arguments(input)).err().as_deref(),
+            expected
+        );
+    }
+}
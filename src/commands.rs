@@ -0,0 +1,469 @@
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufWriter, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Instant;
+
+use crate::bench::{self, Measurement};
+use crate::cache::InputCache;
+use crate::cli::{
+    AuditArguments, BenchArguments, GenerateArguments, InputChoice, PasswordsArguments,
+    RenderArguments, ReplArguments, RunArguments, Selection, Trace, VerifyArguments,
+};
+use crate::error::SolveError;
+use crate::generate;
+use crate::ledger::{self, Verdict};
+use crate::observer::{Counter, Observer, TextLogger};
+use crate::output::{self, Format, Outcome};
+use crate::registry;
+use crate::render;
+use crate::repl::{Repl, Reply};
+use crate::runner::{self, Job};
+use crate::solution::DynSolution;
+use crate::utilities::input::{puzzle_input, InputSource};
+use crate::{day_1, day_2};
+
+pub fn run(arguments: RunArguments) -> ExitCode {
+    // Without a directory parsed inputs are still shared between the parts, but only in memory.
+    if let Some(directory) = &arguments.cache {
+        if let Err(error) = fs::create_dir_all(directory) {
+            eprintln!("Cannot create {directory}: {error}");
+            return ExitCode::FAILURE;
+        }
+        InputCache::shared().set_directory(Some(PathBuf::from(directory)));
+    }
+
+    let (day, part) = match arguments.selection {
+        Selection::One { day, part } => (day, part),
+        Selection::All => return run_all(arguments),
+    };
+
+    let Some(solution) = registry::find(day) else {
+        eprintln!("Day {day} is not registered");
+        return ExitCode::FAILURE;
+    };
+
+    if part > solution.parts() {
+        eprintln!("Day {day} has no part {part}");
+        return ExitCode::FAILURE;
+    }
+
+    let source = match arguments.input {
+        InputChoice::Path(path) => InputSource::Path(path),
+        InputChoice::Stdin => InputSource::Stdin,
+        InputChoice::Test => puzzle_input(true, day, None),
+        InputChoice::Real => puzzle_input(false, day, None),
+    };
+
+    let job = Job {
+        solution,
+        part,
+        source,
+    };
+    let outcome = match arguments.trace {
+        None => runner::run(&job),
+        Some(Trace::Log) => runner::run_observed(&job, TextLogger::new(io::stderr())).0,
+        Some(Trace::Count) => {
+            let (outcome, counter) = runner::run_observed(&job, Counter::default());
+            eprint!("{}", counter.summary().unwrap_or_default());
+            outcome
+        }
+        Some(Trace::File(path)) => match File::create(&path) {
+            Ok(file) => runner::run_observed(&job, TextLogger::new(BufWriter::new(file))).0,
+            Err(error) => {
+                eprintln!("Cannot write {path}: {error}");
+                return ExitCode::FAILURE;
+            }
+        },
+    };
+    let succeeded = outcome.result.is_ok();
+    let format = arguments.format;
+    let rendered = output::render(&[outcome], format);
+
+    // Plain text keeps diagnostics on stderr, structured formats carry them in the record.
+    if format == Format::Text && !succeeded {
+        eprint!("{rendered}");
+    } else {
+        print!("{rendered}");
+    }
+
+    if succeeded {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn run_all(arguments: RunArguments) -> ExitCode {
+    let is_test = matches!(arguments.input, InputChoice::Test);
+    let jobs = runner::all_jobs(&registry::DAYS, is_test);
+
+    // An example that has no answer for a part cannot fail it, so those parts are only skipped.
+    let example_parts = if is_test {
+        let source = InputSource::Path(arguments.answers.clone());
+        match source.read().and_then(|text| {
+            ledger::parse_ledger(&text)
+                .map_err(|error| SolveError::Parse(error.with_file(source.name())))
+        }) {
+            Ok(expectations) => Some(ledger::example_parts(&expectations)),
+            Err(error) => {
+                eprintln!("{error}");
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        None
+    };
+
+    let start = Instant::now();
+    let outcomes = runner::run_all(jobs, arguments.jobs, arguments.timeout);
+    let elapsed = start.elapsed();
+
+    let skipped: Vec<bool> = outcomes
+        .iter()
+        .map(|outcome| {
+            outcome.result.is_err()
+                && example_parts
+                    .as_ref()
+                    .is_some_and(|parts| !parts.contains(&(outcome.day, outcome.part)))
+        })
+        .collect();
+
+    let failed = outcomes
+        .iter()
+        .zip(&skipped)
+        .filter(|(outcome, &skipped)| outcome.result.is_err() && !skipped)
+        .count();
+    let skipped_count = skipped.iter().filter(|&&skipped| skipped).count();
+
+    if arguments.format == Format::Text {
+        print_summary(&outcomes, &skipped);
+        println!(
+            "{} solved, {skipped_count} skipped, {failed} failed in {elapsed:.2?}",
+            outcomes.len() - failed - skipped_count
+        );
+    } else {
+        print!("{}", output::render(&outcomes, arguments.format));
+    }
+
+    if failed == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn print_summary(outcomes: &[Outcome], skipped: &[bool]) {
+    println!("{:<5}{:<6}{:>12}  Answer", "Day", "Part", "Time");
+
+    for (outcome, &skipped) in outcomes.iter().zip(skipped) {
+        let answer = match &outcome.result {
+            Ok(answer) => answer.to_string(),
+            Err(_) if skipped => String::from("skipped, no example answer"),
+            Err(error) => format!(
+                "FAILED: {}",
+                error.replace('\n', "\n                         ")
+            ),
+        };
+
+        println!(
+            "{:<5}{:<6}{:>12}  {answer}",
+            outcome.day,
+            outcome.part,
+            format!("{:.2?}", outcome.elapsed),
+        );
+    }
+}
+
+fn measure_day(
+    solution: &dyn DynSolution,
+    is_test: bool,
+    runs: u32,
+) -> Result<Vec<Measurement>, String> {
+    let source = puzzle_input(is_test, solution.day(), None);
+    let input = source.read().map_err(|error| error.to_string())?;
+
+    match panic::catch_unwind(AssertUnwindSafe(|| {
+        bench::measure(solution, &input, runs as usize)
+    })) {
+        Ok(measurements) => {
+            measurements.map_err(|error| error.with_file(source.name()).to_string())
+        }
+        Err(_) => Err(String::from("panicked")),
+    }
+}
+
+pub fn bench(arguments: BenchArguments) -> ExitCode {
+    let solutions: Vec<&dyn DynSolution> = match arguments.day {
+        Some(day) => registry::find(day).into_iter().collect(),
+        None => registry::DAYS.to_vec(),
+    };
+
+    let mut measurements = vec![];
+    let mut all_measured = true;
+
+    println!(
+        "{:<5}{:<8}{:>6}{:>14}{:>14}{:>14}",
+        "Day", "Phase", "Runs", "Min", "Median", "Max"
+    );
+
+    for solution in solutions {
+        match measure_day(solution, arguments.is_test, arguments.runs) {
+            Ok(day_measurements) => {
+                for measurement in day_measurements.iter() {
+                    println!(
+                        "{:<5}{:<8}{:>6}{:>14}{:>14}{:>14}",
+                        measurement.day,
+                        measurement.phase.to_string(),
+                        measurement.runs.len(),
+                        format!("{:.2?}", measurement.min()),
+                        format!("{:.2?}", measurement.median()),
+                        format!("{:.2?}", measurement.max()),
+                    );
+                }
+
+                measurements.extend(day_measurements);
+            }
+            Err(message) => {
+                eprintln!("Day {} could not be measured: {message}", solution.day());
+                all_measured = false;
+            }
+        }
+    }
+
+    if let Err(error) = fs::write(&arguments.report, bench::report(&measurements)) {
+        eprintln!("Cannot write {}: {error}", arguments.report);
+        return ExitCode::FAILURE;
+    }
+
+    println!("Report written to {}", arguments.report);
+
+    if all_measured {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+pub fn verify(arguments: VerifyArguments) -> ExitCode {
+    let source = InputSource::Path(arguments.answers);
+    let expectations = match source.read().and_then(|text| {
+        ledger::parse_ledger(&text)
+            .map_err(|error| SolveError::Parse(error.with_file(source.name())))
+    }) {
+        Ok(expectations) => expectations,
+        Err(error) => {
+            eprintln!("{error}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let expectations: Vec<_> = expectations
+        .into_iter()
+        .filter(|expectation| arguments.day.is_none_or(|day| expectation.day == day))
+        .collect();
+
+    let (mut passed, mut failed, mut missing) = (0, 0, 0);
+
+    println!(
+        "{:<5}{:<6}{:<14}{:<9}Details",
+        "Day", "Part", "Input", "Result"
+    );
+
+    for check in ledger::verify(&expectations) {
+        let expectation = &check.expectation;
+        let (result, details) = match check.verdict {
+            Verdict::Pass => {
+                passed += 1;
+                ("pass", String::new())
+            }
+            Verdict::Fail { actual } => {
+                failed += 1;
+                (
+                    "FAIL",
+                    format!("expected {}, got {actual}", expectation.answer),
+                )
+            }
+            Verdict::MissingInput(message) => {
+                missing += 1;
+                ("missing", message)
+            }
+            Verdict::Error(message) => {
+                failed += 1;
+                (
+                    "ERROR",
+                    message.replace('\n', "\n                                  "),
+                )
+            }
+        };
+
+        let row = format!(
+            "{:<5}{:<6}{:<14}{:<9}{details}",
+            expectation.day, expectation.part, expectation.input, result
+        );
+        println!("{}", row.trim_end());
+    }
+
+    println!("{passed} passed, {failed} failed, {missing} without input");
+
+    if failed == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+// The input goes to --output or stdout, and the known answers follow as a ledger table.
+pub fn generate(arguments: GenerateArguments) -> ExitCode {
+    let generated = match generate::generate(arguments.day, &arguments.options) {
+        Ok(generated) => generated,
+        Err(message) => {
+            eprintln!("{message}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match arguments.output {
+        Some(path) => {
+            if let Err(error) = fs::write(&path, &generated.input) {
+                eprintln!("Cannot write {path}: {error}");
+                return ExitCode::FAILURE;
+            }
+
+            print!(
+                "{}",
+                ledger::table(arguments.day, &path, &generated.answers)
+            );
+        }
+        None => {
+            print!("{}", generated.input);
+            eprint!("{}", ledger::table(arguments.day, "-", &generated.answers));
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+pub fn repl(arguments: ReplArguments) -> ExitCode {
+    let Some(solution) = registry::find(arguments.day) else {
+        eprintln!("Day {} is not registered", arguments.day);
+        return ExitCode::FAILURE;
+    };
+
+    let source = arguments.source;
+    let mut repl = match source.read().and_then(|text| {
+        Repl::new(solution, &text)
+            .map_err(|error| SolveError::Parse(error.with_file(source.name())))
+    }) {
+        Ok(repl) => repl,
+        Err(error) => {
+            eprintln!("{error}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    println!("Loaded {}, type help for the commands", source.name());
+
+    let mut lines = io::stdin().lock().lines();
+    loop {
+        print!("{}", repl.prompt());
+        let _ = io::stdout().flush();
+
+        let Some(Ok(line)) = lines.next() else {
+            println!();
+            return ExitCode::SUCCESS;
+        };
+
+        match repl.execute(&line) {
+            Ok(Reply::Output(output)) if output.is_empty() => {}
+            Ok(Reply::Output(output)) => println!("{output}"),
+            Ok(Reply::Quit) => return ExitCode::SUCCESS,
+            Err(message) => eprintln!("{message}"),
+        }
+    }
+}
+
+// With --frames each generation gets its own numbered file next to --output.
+pub fn render(arguments: RenderArguments) -> ExitCode {
+    let source = arguments.source;
+    let scenes = match source.read().and_then(|text| {
+        render::render(arguments.day, &text, &arguments.options)
+            .map_err(|error| error.with_file(source.name()))
+    }) {
+        Ok(scenes) => scenes,
+        Err(error) => {
+            eprintln!("{error}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    for (frame, scene) in scenes.iter().enumerate() {
+        let path = if arguments.options.frames {
+            render::frame_path(&arguments.output, frame)
+        } else {
+            arguments.output.clone()
+        };
+
+        if let Err(error) = fs::write(&path, arguments.format.encode(scene)) {
+            eprintln!("Cannot write {path}: {error}");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    println!("Wrote {} image(s) for day {}", scenes.len(), arguments.day);
+    ExitCode::SUCCESS
+}
+
+// Audits a day 1 expense report, succeeding only when exactly one combination reaches the target.
+pub fn audit(arguments: AuditArguments) -> ExitCode {
+    let source = arguments.source;
+    let numbers = match source.read().and_then(|text| {
+        day_1::parse_data(&text).map_err(|error| SolveError::Parse(error.with_file(source.name())))
+    }) {
+        Ok(numbers) => numbers,
+        Err(error) => {
+            eprintln!("{error}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let audit = day_1::audit(&numbers, arguments.size, arguments.target);
+    print!("{}", audit.table());
+
+    if audit.is_unique() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+// A verdict for every password of a day 2 database that can be read, after a report on the lines that cannot.
+pub fn passwords(arguments: PasswordsArguments) -> ExitCode {
+    let source = arguments.source;
+    let mut lint = match source.read() {
+        Ok(text) => day_2::lint(&text),
+        Err(error) => {
+            eprintln!("{error}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    lint.problems = lint
+        .problems
+        .into_iter()
+        .map(|problem| problem.with_file(source.name()))
+        .collect();
+    eprint!("{}", lint.report());
+
+    print!(
+        "{}",
+        day_2::report(&day_2::verdicts(&lint.entries, &arguments.policies))
+    );
+
+    if lint.problems.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
@@ -1,56 +1,615 @@
 use itertools::Itertools;
+use rustc_hash::FxHashSet;
 
-use crate::utilities::file_utilities::read_lines;
+use crate::error::{parse_lines, OrOverflow, ParseError, SolveError};
+use crate::generate::Generated;
+use crate::solution::{Answer, Solution};
+use crate::utilities::binary::{from_bytes, to_bytes};
+use crate::utilities::grammar::{number, parse_all, ws};
+use crate::utilities::random::Rng;
 
-fn parse_line_to_int(line: String) -> i32 {
-    line.parse::<i32>().unwrap()
+const TARGET: i64 = 2020;
+
+fn parse_line_to_int(line: &str) -> Result<i64, ParseError> {
+    parse_all(line, ws(number))
 }
 
-fn parse_data(file_path: String) -> Vec<i32> {
-    read_lines(file_path)
-        .into_iter()
-        .map(parse_line_to_int)
-        .collect()
+pub fn parse_data(input: &str) -> Result<Vec<i64>, ParseError> {
+    parse_lines(input, parse_line_to_int)
+}
+
+// Entries that sum to the target, in the order they appear in the report.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Combination {
+    pub indices: Vec<usize>,
+    pub entries: Vec<i64>,
+}
+
+impl Combination {
+    pub fn product(&self) -> Result<i64, SolveError> {
+        self.entries
+            .iter()
+            .try_fold(1_i64, |product, entry| product.checked_mul(*entry))
+            .or_overflow("multiplying the entries")
+    }
 }
 
-fn find_product_of_combination_with_given_sum(numbers: Vec<i32>, combination_size: usize) -> i32 {
-    for combination in numbers.into_iter().combinations(combination_size) {
-        if combination.iter().cloned().sum::<i32>() == 2020 {
-            return combination.into_iter().product::<i32>();
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct KSumOptions {
+    // Every combination rather than the first one found.
+    pub all: bool,
+    // Whether an entry may be used more than once in a combination.
+    pub reuse: bool,
+}
+
+// Positions into the entries sorted by value, with their original indices kept alongside.
+struct KSum<'a> {
+    sorted: &'a [(i64, usize)],
+    options: KSumOptions,
+    chosen: Vec<usize>,
+    found: Vec<Vec<usize>>,
+}
+
+impl KSum<'_> {
+    fn done(&self) -> bool {
+        !self.options.all && !self.found.is_empty()
+    }
+
+    fn found(&mut self, last: &[usize]) {
+        self.found
+            .push(self.chosen.iter().chain(last).copied().collect());
+    }
+
+    // The sums are taken in i128, so no choice of i64 entries overflows them.
+    fn search(&mut self, start: usize, k: usize, target: i128) {
+        match k {
+            0 if target == 0 => self.found(&[]),
+            0 => {}
+            1 => {
+                for position in start..self.sorted.len() {
+                    if self.sorted[position].0 as i128 == target {
+                        self.found(&[position]);
+                        if self.done() {
+                            return;
+                        }
+                    }
+                }
+            }
+            2 => self.two_sum(start, target),
+            _ => {
+                for position in start..self.sorted.len() {
+                    let next = if self.options.reuse {
+                        position
+                    } else {
+                        position + 1
+                    };
+
+                    self.chosen.push(position);
+                    self.search(next, k - 1, target - self.sorted[position].0 as i128);
+                    self.chosen.pop();
+
+                    if self.done() {
+                        return;
+                    }
+                }
+            }
         }
     }
 
-    -1
+    // Two pointers closing in from both ends. Runs of equal values are paired up all at once.
+    fn two_sum(&mut self, start: usize, target: i128) {
+        let value = |position: usize| self.sorted[position].0;
+        let reuse = self.options.reuse;
+        let (mut low, mut high) = (start, self.sorted.len());
+
+        while high > 0 && (low < high - 1 || (reuse && low == high - 1)) {
+            let top = high - 1;
+            let sum = value(low) as i128 + value(top) as i128;
+
+            if sum < target {
+                low += 1;
+                continue;
+            }
+            if sum > target {
+                high -= 1;
+                continue;
+            }
+
+            if !self.options.all {
+                self.found(&[low, top]);
+                return;
+            }
+
+            if value(low) == value(top) {
+                for first in low..=top {
+                    let second_start = if reuse { first } else { first + 1 };
+                    for second in second_start..=top {
+                        self.found(&[first, second]);
+                    }
+                }
+                return;
+            }
+
+            let low_end = (low..top).find(|&position| value(position) != value(low));
+            let high_start = (low..=top)
+                .rev()
+                .find(|&position| value(position) != value(top));
+            let (low_end, high_start) = (low_end.unwrap_or(top), high_start.unwrap_or(low) + 1);
+
+            for first in low..low_end {
+                for second in high_start..=top {
+                    self.found(&[first, second]);
+                }
+            }
+            (low, high) = (low_end, high_start);
+        }
+    }
+}
+
+// Sorting first makes the innermost two entries a two pointer walk, so k entries take O(n^(k-1)).
+pub fn k_sums(numbers: &[i64], k: usize, target: i64, options: KSumOptions) -> Vec<Combination> {
+    let mut sorted: Vec<(i64, usize)> = numbers
+        .iter()
+        .enumerate()
+        .map(|(index, number)| (*number, index))
+        .collect();
+    sorted.sort();
+
+    let mut k_sum = KSum {
+        sorted: &sorted,
+        options,
+        chosen: vec![],
+        found: vec![],
+    };
+    k_sum.search(0, k, target.into());
+
+    k_sum
+        .found
+        .into_iter()
+        .map(|positions| {
+            let indices: Vec<usize> = positions
+                .iter()
+                .map(|position| sorted[*position].1)
+                .sorted()
+                .collect();
+            let entries = indices.iter().map(|index| numbers[*index]).collect();
+            Combination { indices, entries }
+        })
+        .collect()
+}
+
+pub fn find_k_sum(numbers: &[i64], k: usize, target: i64) -> Option<Vec<i64>> {
+    k_sums(numbers, k, target, KSumOptions::default())
+        .into_iter()
+        .next()
+        .map(|combination| combination.entries)
+}
+
+fn find_product_of_combination_with_given_sum(
+    numbers: &[i64],
+    combination_size: usize,
+) -> Result<i64, SolveError> {
+    let combination = k_sums(numbers, combination_size, TARGET, KSumOptions::default())
+        .into_iter()
+        .next();
+
+    match combination {
+        Some(combination) => combination.product(),
+        None => Err(SolveError::NoSolution(format!(
+            "No {combination_size} entries sum to {TARGET}"
+        ))),
+    }
 }
 
-pub fn part_1(file_path: String) -> i32 {
-    let numbers = parse_data(file_path);
+pub fn part_1(numbers: &[i64]) -> Result<i64, SolveError> {
     find_product_of_combination_with_given_sum(numbers, 2)
 }
 
-pub fn part_2(file_path: String) -> i32 {
-    let numbers = parse_data(file_path);
+pub fn part_2(numbers: &[i64]) -> Result<i64, SolveError> {
     find_product_of_combination_with_given_sum(numbers, 3)
 }
 
+// Everything that reaches the target, not only the first combination found.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Audit {
+    pub target: i64,
+    pub combination_size: usize,
+    pub entry_count: usize,
+    pub combinations: Vec<Combination>,
+    // Values entered more than once, with the indices of every copy.
+    pub duplicates: Vec<(i64, Vec<usize>)>,
+}
+
+pub fn audit(numbers: &[i64], combination_size: usize, target: i64) -> Audit {
+    let options = KSumOptions {
+        all: true,
+        reuse: false,
+    };
+    let mut combinations = k_sums(numbers, combination_size, target, options);
+    combinations.sort_by(|first, second| first.indices.cmp(&second.indices));
+
+    let duplicates = (0..numbers.len())
+        .into_group_map_by(|index| numbers[*index])
+        .into_iter()
+        .filter(|(_, indices)| indices.len() > 1)
+        .sorted()
+        .collect();
+
+    Audit {
+        target,
+        combination_size,
+        entry_count: numbers.len(),
+        combinations,
+        duplicates,
+    }
+}
+
+impl Audit {
+    pub fn is_unique(&self) -> bool {
+        self.combinations.len() == 1
+    }
+
+    // Indices of the entries used by more than one combination, with the combinations using them.
+    pub fn shared_entries(&self) -> Vec<(usize, Vec<usize>)> {
+        self.combinations
+            .iter()
+            .enumerate()
+            .flat_map(|(number, combination)| {
+                combination
+                    .indices
+                    .iter()
+                    .map(move |index| (*index, number))
+            })
+            .into_group_map()
+            .into_iter()
+            .filter(|(_, combinations)| combinations.len() > 1)
+            .sorted()
+            .collect()
+    }
+
+    // Lines are counted from 1 like in the report, and shared entries are marked with a *.
+    pub fn table(&self) -> String {
+        let verdict = match self.combinations.len() {
+            0 => "no answer",
+            1 => "unique answer",
+            _ => "ambiguous answer",
+        };
+        let mut table = format!(
+            "{} combination(s) of {} out of {} entries sum to {}: {verdict}\n",
+            self.combinations.len(),
+            self.combination_size,
+            self.entry_count,
+            self.target
+        );
+
+        let shared: Vec<usize> = self
+            .shared_entries()
+            .into_iter()
+            .map(|(index, _)| index)
+            .collect();
+        let join = |cells: Vec<String>| cells.join(", ");
+        let mut rows = vec![[
+            String::from("#"),
+            String::from("lines"),
+            String::from("entries"),
+            String::from("product"),
+        ]];
+
+        for (number, combination) in self.combinations.iter().enumerate() {
+            let lines = combination
+                .indices
+                .iter()
+                .map(|index| (index + 1).to_string());
+            let entries =
+                combination
+                    .indices
+                    .iter()
+                    .zip(&combination.entries)
+                    .map(|(index, entry)| {
+                        if shared.contains(index) {
+                            format!("{entry}*")
+                        } else {
+                            entry.to_string()
+                        }
+                    });
+            let product = combination
+                .product()
+                .map_or_else(|_| String::from("overflow"), |product| product.to_string());
+
+            rows.push([
+                (number + 1).to_string(),
+                join(lines.collect()),
+                join(entries.collect()),
+                product,
+            ]);
+        }
+
+        if rows.len() > 1 {
+            let widths: Vec<usize> = (0..4)
+                .map(|column| rows.iter().map(|row| row[column].len()).max().unwrap_or(0))
+                .collect();
+
+            for row in rows {
+                let cells: Vec<String> = row
+                    .iter()
+                    .zip(&widths)
+                    .map(|(cell, width)| format!("{cell:<width$}"))
+                    .collect();
+                table.push_str(cells.join("  ").trim_end());
+                table.push('\n');
+            }
+        }
+
+        if !shared.is_empty() {
+            table.push_str("* is in more than one combination\n");
+        }
+
+        for (value, indices) in self.duplicates.iter() {
+            let lines = indices.iter().map(|index| (index + 1).to_string());
+            table.push_str(&format!(
+                "Duplicate entry {value} on lines {}\n",
+                join(lines.collect())
+            ));
+        }
+
+        table
+    }
+}
+
+// How many new pairs and triples summing to the target adding `value` to the entries would create.
+fn sums_with(entries: &FxHashSet<i64>, value: i64) -> (usize, usize) {
+    let pairs = usize::from(entries.contains(&(TARGET - value)));
+    let triples = entries
+        .iter()
+        .filter(|&&entry| {
+            let other = TARGET - value - entry;
+            entry < other && entries.contains(&other)
+        })
+        .count();
+
+    (pairs, triples)
+}
+
+// Exactly one pair and one triple sum to the target. The filler entries are all above half of it, like the real inputs, so no two of them add up.
+pub fn generate(rng: &mut Rng, size: usize) -> Result<Generated, String> {
+    if !(5..=900).contains(&size) {
+        return Err(String::from("Day 1 needs between 5 and 900 entries"));
+    }
+
+    let (mut entries, pair, triple) = loop {
+        let low = rng.range(1..=TARGET / 2 - 1);
+        let pair = [low, TARGET - low];
+
+        let first = rng.range(1..=(TARGET - 3) / 3);
+        let second = rng.range(first + 1..=(TARGET - 1 - first) / 2);
+        let triple = [first, second, TARGET - first - second];
+
+        // Each planted entry may only complete its own pair or triple.
+        let expected = [(0, 0), (1, 0), (0, 0), (0, 0), (0, 1)];
+        let mut entries = FxHashSet::default();
+        let fits = pair
+            .iter()
+            .chain(triple.iter())
+            .zip(expected)
+            .all(|(&value, expected)| {
+                let fits = !entries.contains(&value) && sums_with(&entries, value) == expected;
+                entries.insert(value);
+                fits
+            });
+
+        if fits {
+            break (entries, pair, triple);
+        }
+    };
+
+    while entries.len() < size {
+        let value = rng.range(TARGET / 2 + 1..=TARGET - 1);
+
+        if !entries.contains(&value) && sums_with(&entries, value) == (0, 0) {
+            entries.insert(value);
+        }
+    }
+
+    let mut entries: Vec<i64> = entries.into_iter().collect();
+    entries.sort();
+    rng.shuffle(&mut entries);
+
+    Ok(Generated::new(
+        entries.iter().map(|entry| format!("{entry}\n")).collect(),
+        Some(Answer::from(pair.iter().product::<i64>())),
+        Some(Answer::from(triple.iter().product::<i64>())),
+    ))
+}
+
+pub struct Day1;
+
+impl Solution for Day1 {
+    const DAY: u32 = 1;
+
+    type Input = Vec<i64>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_data(input)
+    }
+
+    fn part_1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_1(input)?.into())
+    }
+
+    fn part_2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_2(input)?.into())
+    }
+
+    fn encode(input: &Self::Input) -> Option<Vec<u8>> {
+        Some(to_bytes(input))
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self::Input> {
+        from_bytes(bytes)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use rstest::rstest;
 
-    use crate::utilities::file_utilities::get_file_path;
+    use crate::utilities::input::puzzle_input;
+    use crate::utilities::property::{agree, shrink_vec, Property};
+
+    fn k_sums_naive(numbers: &[i64], k: usize, target: i64, reuse: bool) -> Vec<Vec<usize>> {
+        let indices = 0..numbers.len();
+        let combinations: Vec<Vec<usize>> = if reuse {
+            indices.combinations_with_replacement(k).collect()
+        } else {
+            indices.combinations(k).collect()
+        };
+
+        combinations
+            .into_iter()
+            .filter(|indices| indices.iter().map(|index| numbers[*index]).sum::<i64>() == target)
+            .collect()
+    }
+
+    fn all_indices(numbers: &[i64], k: usize, target: i64, reuse: bool) -> Vec<Vec<usize>> {
+        let options = KSumOptions { all: true, reuse };
+        k_sums(numbers, k, target, options)
+            .into_iter()
+            .map(|combination| combination.indices)
+            .sorted()
+            .collect()
+    }
+
+    #[rstest]
+    #[case(vec![1721, 979, 366, 299, 675, 1456], 2, 2020, Some(vec![1721, 299]))]
+    #[case(vec![1721, 979, 366, 299, 675, 1456], 3, 2020, Some(vec![979, 366, 675]))]
+    #[case(vec![1721, 979, 366, 299, 675, 1456, 1001], 4, 3021, Some(vec![979, 366, 675, 1001]))]
+    #[case(vec![5, -3, 12, 8], 1, 12, Some(vec![12]))]
+    #[case(vec![5, -3, 12, 8], 2, 2, Some(vec![5, -3]))]
+    #[case(vec![1010, 7], 2, 2020, None)]
+    #[case(vec![], 0, 0, Some(vec![]))]
+    fn test_find_k_sum(
+        #[case] numbers: Vec<i64>,
+        #[case] k: usize,
+        #[case] target: i64,
+        #[case] expected: Option<Vec<i64>>,
+    ) {
+        assert_eq!(expected, find_k_sum(&numbers, k, target));
+    }
+
+    #[test]
+    fn test_k_sums_indices() {
+        let numbers = [675, 1721, 979, 299, 366];
+        let expected = vec![Combination {
+            indices: vec![0, 2, 4],
+            entries: vec![675, 979, 366],
+        }];
+
+        assert_eq!(expected, k_sums(&numbers, 3, 2020, KSumOptions::default()));
+    }
+
+    #[rstest]
+    #[case(vec![1010, 1010, 1010], false, vec![vec![0, 1], vec![0, 2], vec![1, 2]])]
+    #[case(vec![1010, 1721, 299], true, vec![vec![0, 0], vec![1, 2]])]
+    #[case(vec![1000, 1000, 1020, 1020], false, vec![vec![0, 2], vec![0, 3], vec![1, 2], vec![1, 3]])]
+    fn test_k_sums_all(
+        #[case] numbers: Vec<i64>,
+        #[case] reuse: bool,
+        #[case] expected: Vec<Vec<usize>>,
+    ) {
+        assert_eq!(expected, all_indices(&numbers, 2, 2020, reuse));
+    }
+
+    #[test]
+    fn test_audit() {
+        let numbers = [1721, 979, 1010, 299, 1041, 1010, 1721];
+        let audit = audit(&numbers, 2, 2020);
+
+        assert!(!audit.is_unique());
+        assert_eq!(
+            vec![(1010, vec![2, 5]), (1721, vec![0, 6])],
+            audit.duplicates
+        );
+        assert_eq!(vec![(3, vec![0, 3])], audit.shared_entries());
+        assert_eq!(
+            "4 combination(s) of 2 out of 7 entries sum to 2020: ambiguous answer\n\
+             #  lines  entries     product\n\
+             1  1, 4   1721, 299*  514579\n\
+             2  2, 5   979, 1041   1019139\n\
+             3  3, 6   1010, 1010  1020100\n\
+             4  4, 7   299*, 1721  514579\n\
+             * is in more than one combination\n\
+             Duplicate entry 1010 on lines 3, 6\n\
+             Duplicate entry 1721 on lines 1, 7\n",
+            audit.table()
+        );
+    }
+
+    #[rstest]
+    #[case(vec![1721, 299, 5], "1 combination(s) of 2 out of 3 entries sum to 2020: unique answer\n#  lines  entries    product\n1  1, 2   1721, 299  514579\n")]
+    #[case(vec![1, 2], "0 combination(s) of 2 out of 2 entries sum to 2020: no answer\n")]
+    fn test_audit_table(#[case] numbers: Vec<i64>, #[case] expected: &str) {
+        assert_eq!(expected, audit(&numbers, 2, 2020).table());
+    }
+
+    #[test]
+    fn test_k_sums_match_naive() {
+        Property::new().check(
+            |rng| {
+                let numbers: Vec<i64> = (0..rng.range(0..=9)).map(|_| rng.range(-5..=10)).collect();
+                (
+                    numbers,
+                    rng.range(0..=4) as usize,
+                    rng.range(-5..=20),
+                    rng.chance(1, 2),
+                )
+            },
+            |(numbers, k, target, reuse)| {
+                shrink_vec(numbers)
+                    .into_iter()
+                    .map(|numbers| (numbers, *k, *target, *reuse))
+                    .collect()
+            },
+            |(numbers, k, target, reuse)| {
+                agree(
+                    all_indices(numbers, *k, *target, *reuse),
+                    k_sums_naive(numbers, *k, *target, *reuse),
+                )
+            },
+        );
+    }
 
     #[rstest]
     #[case(true, 514579)]
     #[case(false, 1010299)]
-    fn test_part_1(#[case] is_test: bool, #[case] expected: i32) {
-        assert_eq!(expected, part_1(get_file_path(is_test, 1, None)));
+    fn test_part_1(#[case] is_test: bool, #[case] expected: i64) {
+        let numbers = parse_data(&puzzle_input(is_test, 1, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_1(&numbers).unwrap());
     }
 
     #[rstest]
     #[case(true, 241861950)]
     #[case(false, 42140160)]
-    fn test_part_2(#[case] is_test: bool, #[case] expected: i32) {
-        assert_eq!(expected, part_2(get_file_path(is_test, 1, None)));
+    fn test_part_2(#[case] is_test: bool, #[case] expected: i64) {
+        let numbers = parse_data(&puzzle_input(is_test, 1, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_2(&numbers).unwrap());
+    }
+
+    #[test]
+    fn test_overflow() {
+        let numbers = vec![4000000000000, -3999999997980, 2000000000, -2000000000, 2020];
+
+        assert_eq!(
+            Err(SolveError::Overflow(String::from(
+                "multiplying the entries"
+            ))),
+            part_1(&numbers)
+        );
+        assert_eq!(
+            Err(SolveError::Overflow(String::from(
+                "multiplying the entries"
+            ))),
+            part_2(&numbers)
+        );
     }
 }
@@ -1,65 +1,145 @@
-use crate::utilities::file_utilities::read_lines;
+use crate::error::{parse_lines, OrOverflow, ParseError, SolveError};
+use crate::generate::Generated;
+use crate::solution::{Answer, Solution};
+use crate::utilities::binary::{from_bytes, to_bytes};
+use crate::utilities::grammar::{number, parse_all, ws};
+use crate::utilities::random::Rng;
 use itertools::Itertools;
+use num::BigUint;
 use std::collections::{HashMap, HashSet};
 use std::str;
 
-fn parse_line(line: &str) -> usize {
-    line.parse::<usize>().unwrap()
+fn parse_line(line: &str) -> Result<usize, ParseError> {
+    parse_all(line, ws(number))
 }
 
-fn parse_data(file_path: String) -> Vec<usize> {
-    let mut adaptors: Vec<usize> = read_lines(file_path)
-        .into_iter()
-        .map(|line| parse_line(line.as_str()))
-        .collect();
+pub fn parse_data(input: &str) -> Result<Vec<usize>, ParseError> {
+    let mut adaptors: Vec<usize> = parse_lines(input, parse_line)?;
 
     adaptors.insert(0, 0); // Initial outlet
-    adaptors.push(adaptors.iter().max().unwrap() + 3); // Final adapter
+    adaptors.push(adaptors.iter().max().unwrap_or(&0) + 3); // Final adapter
     adaptors.sort();
 
-    adaptors
+    Ok(adaptors)
 }
 
-pub fn part_1(file_path: String) -> usize {
-    let sorted_adaptors = parse_data(file_path);
-
-    let mut one_diff = 0;
+pub fn part_1(sorted_adaptors: &[usize]) -> Result<usize, SolveError> {
+    let mut one_diff: usize = 0;
     let mut _two_diff = 0;
     let mut three_diff = 0;
 
-    for (first, second) in sorted_adaptors.into_iter().tuple_windows() {
+    for (first, second) in sorted_adaptors.iter().tuple_windows() {
         match second - first {
             1 => one_diff += 1,
             2 => _two_diff += 1,
             3 => three_diff += 1,
-            _ => panic!(),
+            _ => {
+                return Err(SolveError::NoSolution(format!(
+                    "Adaptors {first} and {second} cannot be chained"
+                )))
+            }
         }
     }
 
-    one_diff * three_diff
+    one_diff
+        .checked_mul(three_diff)
+        .or_overflow("multiplying the differences")
 }
 
-pub fn part_2(file_path: String) -> usize {
-    let sorted_adaptors = parse_data(file_path);
+pub fn part_2(sorted_adaptors: &[usize]) -> Result<BigUint, SolveError> {
     let sorted_adaptors_hashset: HashSet<usize> =
         HashSet::from_iter(sorted_adaptors.iter().cloned());
-    let target = sorted_adaptors.iter().max().unwrap();
+    let target = sorted_adaptors.iter().max().unwrap_or(&0);
 
-    let mut adaptor_path_count: HashMap<usize, usize> = vec![(0, 1)].into_iter().collect();
+    let mut adaptor_path_count: HashMap<usize, BigUint> =
+        vec![(0, BigUint::from(1_u32))].into_iter().collect();
 
     for adaptor in sorted_adaptors.iter() {
-        let path_count_here = adaptor_path_count[adaptor];
+        let path_count_here = adaptor_path_count.get(adaptor).cloned().unwrap_or_default();
 
         for delta in 1..=3 {
             let next_adaptor = *adaptor + delta;
             if !sorted_adaptors_hashset.contains(&next_adaptor) {
                 continue;
             }
-            *adaptor_path_count.entry(next_adaptor).or_insert(0) += path_count_here;
+            *adaptor_path_count.entry(next_adaptor).or_default() += &path_count_here;
         }
     }
 
-    adaptor_path_count[target]
+    Ok(adaptor_path_count.remove(target).unwrap_or_default())
+}
+
+// The adapters step up by one or three jolts, like the real inputs.
+pub fn generate(rng: &mut Rng, size: usize) -> Result<Generated, String> {
+    let mut adapters = vec![];
+    let mut joltage = 0;
+
+    for _ in 0..size {
+        joltage += if rng.chance(7, 10) { 1 } else { 3 };
+        adapters.push(joltage);
+    }
+
+    let chain: Vec<usize> = [0]
+        .into_iter()
+        .chain(adapters.iter().copied())
+        .chain([joltage + 3])
+        .collect();
+    let ones = chain
+        .windows(2)
+        .filter(|pair| pair[1] - pair[0] == 1)
+        .count();
+    let threes = chain
+        .windows(2)
+        .filter(|pair| pair[1] - pair[0] == 3)
+        .count();
+
+    let mut arrangements: Vec<BigUint> = vec![BigUint::from(1_u32)];
+    for index in 1..chain.len() {
+        let here = (index.saturating_sub(3)..index)
+            .filter(|previous| chain[index] - chain[*previous] <= 3)
+            .map(|previous| &arrangements[previous])
+            .sum();
+        arrangements.push(here);
+    }
+
+    rng.shuffle(&mut adapters);
+
+    Ok(Generated::new(
+        adapters
+            .iter()
+            .map(|adapter| format!("{adapter}\n"))
+            .collect(),
+        Some(Answer::from(ones * threes)),
+        Some(Answer::from(arrangements.pop().unwrap_or_default())),
+    ))
+}
+
+pub struct Day10;
+
+impl Solution for Day10 {
+    const DAY: u32 = 10;
+
+    type Input = Vec<usize>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_data(input)
+    }
+
+    fn part_1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_1(input)?.into())
+    }
+
+    fn part_2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_2(input)?.into())
+    }
+
+    fn encode(input: &Self::Input) -> Option<Vec<u8>> {
+        Some(to_bytes(input))
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self::Input> {
+        from_bytes(bytes)
+    }
 }
 
 #[cfg(test)]
@@ -67,19 +147,68 @@ mod tests {
     use super::*;
     use rstest::rstest;
 
-    use crate::utilities::file_utilities::get_file_path;
+    use crate::utilities::input::puzzle_input;
+    use crate::utilities::property::{agree, shrink_vec, Property};
+
+    // Every chain from the outlet to the device, walked one by one.
+    fn count_paths_naive(sorted_adaptors: &[usize], from: usize) -> usize {
+        if from == sorted_adaptors.len() - 1 {
+            return 1;
+        }
+
+        (from + 1..sorted_adaptors.len())
+            .take_while(|next| sorted_adaptors[*next] - sorted_adaptors[from] <= 3)
+            .map(|next| count_paths_naive(sorted_adaptors, next))
+            .sum()
+    }
+
+    fn random_adaptors(rng: &mut Rng) -> Vec<usize> {
+        let count = rng.range(0..=12) as usize;
+        rng.distinct(1..=30, count)
+            .into_iter()
+            .map(|adaptor| adaptor as usize)
+            .collect()
+    }
+
+    #[test]
+    fn test_part_2_matches_naive() {
+        Property::new().check(
+            random_adaptors,
+            |adaptors| shrink_vec(adaptors),
+            |adaptors| {
+                let sorted_adaptors = parse_data(&adaptors.iter().join("\n")).unwrap();
+                agree(
+                    part_2(&sorted_adaptors).unwrap(),
+                    BigUint::from(count_paths_naive(&sorted_adaptors, 0)),
+                )
+            },
+        );
+    }
 
     #[rstest]
     #[case(true, 35)]
     #[case(false, 1914)]
     fn test_part_1(#[case] is_test: bool, #[case] expected: usize) {
-        assert_eq!(expected, part_1(get_file_path(is_test, 10, None)));
+        let sorted_adaptors = parse_data(&puzzle_input(is_test, 10, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_1(&sorted_adaptors).unwrap());
     }
 
     #[rstest]
     #[case(true, 8)]
     #[case(false, 9256148959232)]
-    fn test_part_2(#[case] is_test: bool, #[case] expected: usize) {
-        assert_eq!(expected, part_2(get_file_path(is_test, 10, None)));
+    fn test_part_2(#[case] is_test: bool, #[case] expected: u64) {
+        let sorted_adaptors = parse_data(&puzzle_input(is_test, 10, None).read().unwrap()).unwrap();
+        assert_eq!(BigUint::from(expected), part_2(&sorted_adaptors).unwrap());
+    }
+
+    #[test]
+    fn test_part_2_large() {
+        // With every joltage from 1 to 100 the counts follow the tribonacci numbers, well past u64.
+        let sorted_adaptors = parse_data(&(1..=100).join("\n")).unwrap();
+        let (_, _, expected) =
+            (1..100).fold((0_u128, 1_u128, 1_u128), |(a, b, c), _| (b, c, a + b + c));
+
+        assert!(expected > u64::MAX as u128);
+        assert_eq!(BigUint::from(expected), part_2(&sorted_adaptors).unwrap());
     }
 }
@@ -1,137 +1,217 @@
-use itertools::Itertools;
 use rustc_hash::{FxHashMap, FxHashSet};
 
-use crate::utilities::file_utilities::read_lines;
+use crate::error::{ParseError, SolveError};
+use crate::generate::Generated;
+use crate::solution::{Answer, Solution};
+use crate::utilities::automaton::{Automaton, Rule};
+use crate::utilities::binary::{from_bytes, to_bytes, Binary};
+use crate::utilities::grid::{Grid, Position, ALL_DIRECTIONS};
+use crate::utilities::image::{Color, Scene};
+use crate::utilities::random::Rng;
 
-fn get_all_seats(inputs: &Vec<String>) -> (FxHashSet<(isize, isize)>, isize, isize) {
-    let height = inputs.len();
-    let width = inputs[0].len();
-    let mut seats = FxHashSet::default();
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Spot {
+    Floor,
+    EmptySeat,
+    OccupiedSeat,
+}
+
+pub fn parse_data(input: &str) -> Result<Grid<Spot>, ParseError> {
+    Grid::parse(input, |c| match c {
+        '.' => Some(Spot::Floor),
+        'L' => Some(Spot::EmptySeat),
+        '#' => Some(Spot::OccupiedSeat),
+        _ => None,
+    })
+}
+
+impl Binary for Spot {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        let code: u8 = match self {
+            Spot::Floor => 0,
+            Spot::EmptySeat => 1,
+            Spot::OccupiedSeat => 2,
+        };
+        code.encode(bytes);
+    }
 
-    for (row, column) in (0..height).cartesian_product(0..width) {
-        if inputs[row][column..=column].eq("L") {
-            seats.insert((row as isize, column as isize));
+    fn decode(bytes: &mut &[u8]) -> Option<Self> {
+        match u8::decode(bytes)? {
+            0 => Some(Spot::Floor),
+            1 => Some(Spot::EmptySeat),
+            2 => Some(Spot::OccupiedSeat),
+            _ => None,
         }
     }
+}
+
+fn is_seat(map: &Grid<Spot>, position: &Position) -> bool {
+    map[*position] != Spot::Floor
+}
 
-    (seats, height as isize, width as isize)
+type VisibleSeats = FxHashMap<Position, Vec<Position>>;
+
+fn seating<'a>(
+    map: &Grid<Spot>,
+    visible_seats: &'a VisibleSeats,
+    seat_limit_for_empty: usize,
+) -> Automaton<'a, Position> {
+    let occupied = map
+        .iter()
+        .filter(|(_, spot)| **spot == Spot::OccupiedSeat)
+        .map(|(position, _)| position);
+
+    // Empty seats fill up when no visible seat is taken, so only the seats themselves can ever be occupied.
+    Automaton::new(
+        occupied,
+        |seat| visible_seats[seat].clone(),
+        Rule {
+            birth: 0..=0,
+            survival: 0..=seat_limit_for_empty - 1,
+        },
+    )
+    .bounded(visible_seats.keys().copied())
 }
 
 fn solve(
-    seats: &FxHashSet<(isize, isize)>,
-    visible_seats: &FxHashMap<(isize, isize), Vec<(isize, isize)>>,
+    map: &Grid<Spot>,
+    visible_seats: &VisibleSeats,
     seat_limit_for_empty: usize,
-) -> usize {
-    let mut filled_seats: FxHashSet<(isize, isize)> = FxHashSet::default();
+) -> Result<usize, SolveError> {
+    let mut automaton = seating(map, visible_seats, seat_limit_for_empty);
+    automaton.run_to_fixed_point()?;
+    Ok(automaton.population())
+}
 
-    loop {
-        let mut new_filled_seats: FxHashSet<(isize, isize)> = FxHashSet::default();
-        let mut new_emptied_seats: FxHashSet<(isize, isize)> = FxHashSet::default();
+fn adjacent_seats(map: &Grid<Spot>) -> VisibleSeats {
+    map.positions()
+        .filter(|seat| is_seat(map, seat))
+        .map(|seat| {
+            let neighbours = map
+                .neighbours_8(seat)
+                .filter(|neighbour| is_seat(map, neighbour))
+                .collect();
 
-        for seat in seats.iter() {
-            let visible_occupied = visible_seats
-                .get(seat)
-                .unwrap()
+            (seat, neighbours)
+        })
+        .collect()
+}
+
+fn first_visible_seats(map: &Grid<Spot>) -> VisibleSeats {
+    map.positions()
+        .filter(|seat| is_seat(map, seat))
+        .map(|seat| {
+            // Only the first seat in each direction can be seen.
+            let neighbours = ALL_DIRECTIONS
                 .iter()
-                .filter(|spot| filled_seats.contains(spot))
-                .count();
-
-            if visible_occupied == 0 && !filled_seats.contains(seat) {
-                new_filled_seats.insert(*seat);
-            } else if visible_occupied >= seat_limit_for_empty && filled_seats.contains(seat) {
-                new_emptied_seats.insert(*seat);
-            }
-        }
+                .filter_map(|direction| {
+                    map.ray(seat, *direction)
+                        .find(|position| is_seat(map, position))
+                })
+                .collect();
+
+            (seat, neighbours)
+        })
+        .collect()
+}
+
+pub fn part_1(map: &Grid<Spot>) -> Result<usize, SolveError> {
+    solve(map, &adjacent_seats(map), 4)
+}
 
-        if new_filled_seats.is_empty() && new_emptied_seats.is_empty() {
-            break;
+pub fn part_2(map: &Grid<Spot>) -> Result<usize, SolveError> {
+    solve(map, &first_visible_seats(map), 5)
+}
+
+const MAX_LAYOUTS: usize = 100;
+
+const EMPTY_SEAT: Color = Color(170, 200, 170);
+const OCCUPIED_SEAT: Color = Color(30, 90, 30);
+
+fn draw_seating(map: &Grid<Spot>, occupied: &FxHashSet<Position>) -> Scene {
+    let mut scene = Scene::new(map.width() as f64, map.height() as f64, Color::WHITE);
+
+    for ((row, column), spot) in map.iter() {
+        match (spot, occupied.contains(&(row, column))) {
+            (Spot::Floor, _) => {}
+            (_, true) => scene.cell(row, column, OCCUPIED_SEAT),
+            (_, false) => scene.cell(row, column, EMPTY_SEAT),
         }
+    }
+
+    scene
+}
+
+// The seating once it settles, or every round on the way there.
+pub fn render(map: &Grid<Spot>, part: u32, frames: bool) -> Result<Vec<Scene>, SolveError> {
+    let (visible_seats, seat_limit_for_empty) = match part {
+        1 => (adjacent_seats(map), 4),
+        _ => (first_visible_seats(map), 5),
+    };
+    let mut automaton = seating(map, &visible_seats, seat_limit_for_empty);
+
+    if frames {
+        automaton.frames_to_fixed_point(|occupied| draw_seating(map, occupied))
+    } else {
+        automaton.run_to_fixed_point()?;
+        Ok(vec![draw_seating(map, automaton.active())])
+    }
+}
 
-        filled_seats = filled_seats
-            .difference(&new_emptied_seats)
-            .cloned()
+// Some random layouts cycle forever, so each one is simulated and only a layout that settles in both parts is kept.
+pub fn generate(rng: &mut Rng, size: usize) -> Result<Generated, String> {
+    for _ in 0..MAX_LAYOUTS {
+        let input: String = (0..size)
+            .map(|_| {
+                let mut row: String = (0..size)
+                    .map(|_| if rng.chance(4, 5) { 'L' } else { '.' })
+                    .collect();
+                row.push('\n');
+                row
+            })
             .collect();
-        filled_seats = filled_seats.union(&new_filled_seats).cloned().collect();
+
+        let map = parse_data(&input).map_err(|error| error.to_string())?;
+        if let (Ok(part_1), Ok(part_2)) = (part_1(&map), part_2(&map)) {
+            return Ok(Generated::new(
+                input,
+                Some(Answer::from(part_1)),
+                Some(Answer::from(part_2)),
+            ));
+        }
     }
 
-    filled_seats.len()
+    Err(format!(
+        "No layout of size {size} settled in {MAX_LAYOUTS} attempts"
+    ))
 }
 
-pub fn part_1(file_path: String) -> usize {
-    let map: Vec<String> = read_lines(file_path);
-    let (seats, height, width) = get_all_seats(&map);
-    let visible_seats: FxHashMap<(isize, isize), Vec<(isize, isize)>> = seats
-        .iter()
-        .map(|seat| {
-            let (row, column) = *seat;
-
-            (
-                *seat,
-                (-1..=1)
-                    .cartesian_product(-1..=1)
-                    .filter_map(|(delta_row, delta_column)| {
-                        if delta_row == 0 && delta_column == 0 {
-                            return None;
-                        }
-
-                        let new_row = row + delta_row;
-                        let new_column = column + delta_column;
-
-                        if (0..height).contains(&new_row)
-                            && (0..width).contains(&new_column)
-                            && seats.contains(&(new_row, new_column))
-                        {
-                            return Some((new_row, new_column));
-                        }
-
-                        None
-                    })
-                    .collect::<Vec<(isize, isize)>>(),
-            )
-        })
-        .collect();
+pub struct Day11;
 
-    solve(&seats, &visible_seats, 4)
-}
+impl Solution for Day11 {
+    const DAY: u32 = 11;
 
-pub fn part_2(file_path: String) -> usize {
-    let map: Vec<String> = read_lines(file_path);
-    let (seats, height, width) = get_all_seats(&map);
-    let visible_seats: FxHashMap<(isize, isize), Vec<(isize, isize)>> = seats
-        .iter()
-        .map(|seat| {
-            let (row, column) = *seat;
-
-            (
-                *seat,
-                (-1..=1)
-                    .cartesian_product(-1..=1)
-                    .filter_map(|(delta_row, delta_column)| {
-                        if delta_row == 0 && delta_column == 0 {
-                            return None;
-                        }
-
-                        let mut new_row = row + delta_row;
-                        let mut new_column = column + delta_column;
-
-                        while (0..height).contains(&new_row) && (0..width).contains(&new_column) {
-                            // Stop at first.
-                            if seats.contains(&(new_row, new_column)) {
-                                return Some((new_row, new_column));
-                            }
-
-                            new_row += delta_row;
-                            new_column += delta_column;
-                        }
-
-                        None
-                    })
-                    .collect::<Vec<(isize, isize)>>(),
-            )
-        })
-        .collect();
+    type Input = Grid<Spot>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_data(input)
+    }
 
-    solve(&seats, &visible_seats, 5)
+    fn part_1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_1(input)?.into())
+    }
+
+    fn part_2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_2(input)?.into())
+    }
+
+    fn encode(input: &Self::Input) -> Option<Vec<u8>> {
+        Some(to_bytes(input))
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self::Input> {
+        from_bytes(bytes)
+    }
 }
 
 #[cfg(test)]
@@ -139,19 +219,53 @@ mod tests {
     use super::*;
     use rstest::rstest;
 
-    use crate::utilities::file_utilities::get_file_path;
+    use crate::utilities::input::puzzle_input;
+    use crate::utilities::random::DEFAULT_SEED;
 
     #[rstest]
     #[case(true, 37)]
     #[case(false, 2481)]
     fn test_part_1(#[case] is_test: bool, #[case] expected: usize) {
-        assert_eq!(expected, part_1(get_file_path(is_test, 11, None)));
+        let map = parse_data(&puzzle_input(is_test, 11, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_1(&map).unwrap());
     }
 
     #[rstest]
     #[case(true, 26)]
     #[case(false, 2227)]
     fn test_part_2(#[case] is_test: bool, #[case] expected: usize) {
-        assert_eq!(expected, part_2(get_file_path(is_test, 11, None)));
+        let map = parse_data(&puzzle_input(is_test, 11, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_2(&map).unwrap());
+    }
+
+    // The default seed and size once gave a layout that cycles forever.
+    #[rstest]
+    #[case(DEFAULT_SEED, 90)]
+    #[case(1, 20)]
+    fn test_generate(#[case] seed: u64, #[case] size: usize) {
+        let generated = generate(&mut Rng::new(seed), size).unwrap();
+        let map = parse_data(&generated.input).unwrap();
+
+        assert_eq!(
+            generated.answers,
+            [
+                Some(Answer::from(part_1(&map).unwrap())),
+                Some(Answer::from(part_2(&map).unwrap()))
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case(1, 6)]
+    #[case(2, 7)]
+    fn test_render_frames(#[case] part: u32, #[case] expected_frames: usize) {
+        let map = parse_data(&puzzle_input(true, 11, None).read().unwrap()).unwrap();
+        let scenes = render(&map, part, true).unwrap();
+
+        assert_eq!(expected_frames, scenes.len());
+        assert_eq!(
+            render(&map, part, false).unwrap()[0],
+            scenes[expected_frames - 1]
+        );
     }
 }
@@ -1,9 +1,21 @@
 use std::str;
 
-use crate::utilities::file_utilities::read_lines;
+use itertools::Itertools;
+use nom::branch::alt;
+use nom::character::complete::char;
+use nom::combinator::map;
+use nom::error::context;
+use nom::sequence::preceded;
+
+use crate::error::{parse_lines, OrOverflow, ParseError, SolveError};
+use crate::generate::Generated;
+use crate::solution::{Answer, Solution};
+use crate::utilities::grammar::{number, parse_all, ws, GResult};
+use crate::utilities::image::{Color, Scene, Shape};
+use crate::utilities::random::Rng;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
-enum Direction {
+pub enum Direction {
     North,
     South,
     East,
@@ -11,120 +23,294 @@ enum Direction {
 }
 
 #[derive(Clone, Debug)]
-enum Instruction {
+pub enum Instruction {
     Direction(Direction, i32),
     Left(i32),
     Right(i32),
     Forward(i32),
 }
 
-fn parse_line(line: &str) -> Instruction {
-    let (type_str, value_str) = line.split_at(1);
-    let value = value_str.parse::<i32>().unwrap();
-    match type_str {
-        "N" => Instruction::Direction(Direction::North, value),
-        "S" => Instruction::Direction(Direction::South, value),
-        "E" => Instruction::Direction(Direction::East, value),
-        "W" => Instruction::Direction(Direction::West, value),
-        "L" => Instruction::Left(value),
-        "R" => Instruction::Right(value),
-        "F" => Instruction::Forward(value),
-        _ => panic!(),
-    }
+// F10
+fn instruction(input: &str) -> GResult<'_, Instruction> {
+    let action = |action, instruction: fn(i32) -> Instruction| {
+        map(preceded(ws(char(action)), ws(number)), instruction)
+    };
+
+    context(
+        "one of N, S, E, W, L, R or F",
+        alt((
+            action('N', |value| Instruction::Direction(Direction::North, value)),
+            action('S', |value| Instruction::Direction(Direction::South, value)),
+            action('E', |value| Instruction::Direction(Direction::East, value)),
+            action('W', |value| Instruction::Direction(Direction::West, value)),
+            action('L', Instruction::Left),
+            action('R', Instruction::Right),
+            action('F', Instruction::Forward),
+        )),
+    )(input)
+}
+
+fn parse_line(line: &str) -> Result<Instruction, ParseError> {
+    parse_all(line, instruction)
 }
 
-fn parse_data(file_path: String) -> Vec<Instruction> {
-    read_lines(file_path)
-        .into_iter()
-        .map(|line| parse_line(line.as_str()))
-        .collect()
+pub fn parse_data(input: &str) -> Result<Vec<Instruction>, ParseError> {
+    parse_lines(input, parse_line)
 }
 
 fn simulate_move_in_direction(
     state: &(i32, i32),
     direction: &Direction,
     amount: i32,
-) -> (i32, i32) {
+) -> Result<(i32, i32), SolveError> {
     match direction {
-        Direction::North => (state.0, state.1 + amount),
-        Direction::South => (state.0, state.1 - amount),
-        Direction::East => (state.0 + amount, state.1),
-        Direction::West => (state.0 - amount, state.1),
+        Direction::North => state.1.checked_add(amount).map(|north| (state.0, north)),
+        Direction::South => state.1.checked_sub(amount).map(|north| (state.0, north)),
+        Direction::East => state.0.checked_add(amount).map(|east| (east, state.1)),
+        Direction::West => state.0.checked_sub(amount).map(|east| (east, state.1)),
     }
+    .or_overflow("moving")
 }
 
-pub fn part_1(file_path: String) -> i32 {
-    let instructions = parse_data(file_path);
+fn manhattan_distance((east, north): (i32, i32)) -> Result<i32, SolveError> {
+    east.checked_abs()
+        .zip(north.checked_abs())
+        .and_then(|(east, north)| east.checked_add(north))
+        .or_overflow("measuring the distance")
+}
+
+// The headings in the order a ship turning one way meets them.
+const LEFT_TURNS: [Direction; 4] = [
+    Direction::East,
+    Direction::North,
+    Direction::West,
+    Direction::South,
+];
+const RIGHT_TURNS: [Direction; 4] = [
+    Direction::East,
+    Direction::South,
+    Direction::West,
+    Direction::North,
+];
+
+fn turn(direction: Direction, turns: &[Direction; 4], amount: i32) -> Direction {
+    let old_index = turns.iter().position(|&d| d == direction).unwrap_or(0);
+    let new_index = (((amount as f64 / 90.0).round() as usize) + old_index) % 4;
+
+    turns[new_index]
+}
+
+// Every point the ship visits, starting from the origin.
+fn sail_by_heading(instructions: &[Instruction]) -> Result<Vec<(i32, i32)>, SolveError> {
     let mut ship_direction = Direction::East;
     let mut ship_point = (0, 0);
+    let mut path = vec![ship_point];
 
     for instruction in instructions.iter() {
         match instruction {
             Instruction::Direction(direction, amount) => {
-                ship_point = simulate_move_in_direction(&ship_point, direction, *amount);
+                ship_point = simulate_move_in_direction(&ship_point, direction, *amount)?;
             }
             Instruction::Forward(amount) => {
-                ship_point = simulate_move_in_direction(&ship_point, &ship_direction, *amount);
+                ship_point = simulate_move_in_direction(&ship_point, &ship_direction, *amount)?;
             }
-            Instruction::Left(amount) | Instruction::Right(amount) => {
-                let rotations = match instruction {
-                    Instruction::Left(_) => vec![
-                        Direction::East,
-                        Direction::North,
-                        Direction::West,
-                        Direction::South,
-                    ],
-                    Instruction::Right(_) => vec![
-                        Direction::East,
-                        Direction::South,
-                        Direction::West,
-                        Direction::North,
-                    ],
-                    _ => panic!(),
-                };
-
-                let old_index = rotations.iter().position(|&d| d == ship_direction).unwrap();
-                let new_index = (((*amount as f64 / 90.0).round() as usize) + old_index) % 4;
-
-                ship_direction = rotations[new_index];
+            Instruction::Left(amount) => {
+                ship_direction = turn(ship_direction, &LEFT_TURNS, *amount);
+            }
+            Instruction::Right(amount) => {
+                ship_direction = turn(ship_direction, &RIGHT_TURNS, *amount);
             }
         }
+
+        path.push(ship_point);
     }
 
-    ship_point.0.abs() + ship_point.1.abs()
+    Ok(path)
 }
 
-pub fn part_2(file_path: String) -> i32 {
-    let instructions = parse_data(file_path);
+pub fn part_1(instructions: &[Instruction]) -> Result<i32, SolveError> {
+    manhattan_distance(*sail_by_heading(instructions)?.last().unwrap())
+}
+
+// Counter-clockwise by `theta`, which is a multiple of a quarter turn.
+fn rotate(waypoint: (i32, i32), theta: f64) -> Result<(i32, i32), SolveError> {
+    let (cos, sin) = (theta.cos().round() as i32, theta.sin().round() as i32);
+    let rotated = || {
+        let waypoint_x = cos
+            .checked_mul(waypoint.0)?
+            .checked_sub(sin.checked_mul(waypoint.1)?)?;
+        let waypoint_y = sin
+            .checked_mul(waypoint.0)?
+            .checked_add(cos.checked_mul(waypoint.1)?)?;
+        Some((waypoint_x, waypoint_y))
+    };
+
+    rotated().or_overflow("turning the waypoint")
+}
+
+fn sail_by_waypoint(instructions: &[Instruction]) -> Result<Vec<(i32, i32)>, SolveError> {
     let mut ship = (0, 0);
     let mut waypoint = (10, 1);
+    let mut path = vec![ship];
 
     for instruction in instructions.iter() {
         match instruction {
             Instruction::Direction(direction, amount) => {
-                waypoint = simulate_move_in_direction(&waypoint, direction, *amount);
+                waypoint = simulate_move_in_direction(&waypoint, direction, *amount)?;
             }
             Instruction::Forward(amount) => {
-                ship = (ship.0 + amount * waypoint.0, ship.1 + amount * waypoint.1);
-            }
-            Instruction::Left(amount) | Instruction::Right(amount) => {
-                let theta = match instruction {
-                    Instruction::Left(_) => (*amount as f64).to_radians(),
-                    Instruction::Right(_) => -(*amount as f64).to_radians(),
-                    _ => panic!(),
+                let towards = |ship: i32, waypoint: i32| {
+                    amount
+                        .checked_mul(waypoint)
+                        .and_then(|distance| ship.checked_add(distance))
                 };
+                ship = towards(ship.0, waypoint.0)
+                    .zip(towards(ship.1, waypoint.1))
+                    .or_overflow("sailing to the waypoint")?;
+            }
+            Instruction::Left(amount) => {
+                waypoint = rotate(waypoint, (*amount as f64).to_radians())?;
+            }
+            Instruction::Right(amount) => {
+                waypoint = rotate(waypoint, -(*amount as f64).to_radians())?;
+            }
+        }
+
+        path.push(ship);
+    }
+
+    Ok(path)
+}
+
+pub fn part_2(instructions: &[Instruction]) -> Result<i32, SolveError> {
+    manhattan_distance(*sail_by_waypoint(instructions)?.last().unwrap())
+}
+
+const IMAGE_SIZE: f64 = 800.0;
+const PATH: Color = Color(20, 60, 140);
+const START: Color = Color(30, 140, 30);
+const END: Color = Color(200, 30, 30);
 
-                let waypoint_x = (theta.cos().round() as i32 * waypoint.0)
-                    - (theta.sin().round() as i32 * waypoint.1);
-                let waypoint_y = (theta.sin().round() as i32 * waypoint.0)
-                    + (theta.cos().round() as i32 * waypoint.1);
+// North is up. The start and the end of the voyage are marked with small squares.
+pub fn render(instructions: &[Instruction], part: u32) -> Result<Vec<Scene>, SolveError> {
+    let path = match part {
+        1 => sail_by_heading(instructions)?,
+        _ => sail_by_waypoint(instructions)?,
+    };
 
-                waypoint = (waypoint_x, waypoint_y);
+    let (west, east) = path
+        .iter()
+        .map(|point| point.0)
+        .minmax()
+        .into_option()
+        .unwrap();
+    let (south, north) = path
+        .iter()
+        .map(|point| point.1)
+        .minmax()
+        .into_option()
+        .unwrap();
+    let margin = ((east - west).max(north - south) as f64 / 20.0).max(1.0);
+    let to_scene = |(x, y): (i32, i32)| ((x - west) as f64 + margin, (north - y) as f64 + margin);
+
+    let mut scene = Scene::new(
+        (east - west) as f64 + 2.0 * margin,
+        (north - south) as f64 + 2.0 * margin,
+        Color::WHITE,
+    )
+    .fit(IMAGE_SIZE);
+
+    scene.push(Shape::Polyline {
+        points: path.iter().copied().map(to_scene).collect(),
+        color: PATH,
+    });
+
+    let marker = 6.0 / scene.scale;
+    for (point, color) in [(path[0], START), (*path.last().unwrap(), END)] {
+        let (x, y) = to_scene(point);
+        scene.rectangle(
+            (x - marker / 2.0, y - marker / 2.0),
+            (marker, marker),
+            color,
+        );
+    }
+
+    Ok(vec![scene])
+}
+
+// Positions are (east, north), and each quarter turn to the left maps (x, y) to (-y, x).
+fn turn_left((east, north): (i64, i64), degrees: i64) -> (i64, i64) {
+    (0..degrees.rem_euclid(360) / 90).fold((east, north), |(east, north), _| (-north, east))
+}
+
+// Both answers come from following the instructions as they are written.
+pub fn generate(rng: &mut Rng, size: usize) -> Result<Generated, String> {
+    let mut input = String::new();
+    let (mut ship, mut heading) = ((0, 0), (1, 0));
+    let (mut ship_2, mut waypoint) = ((0, 0), (10, 1));
+
+    for _ in 0..size {
+        let action = *rng.choose(&['N', 'S', 'E', 'W', 'L', 'R', 'F']);
+        let value = match action {
+            'L' | 'R' => 90 * rng.range(1..=3),
+            'F' => rng.range(1..=100),
+            _ => rng.range(1..=5),
+        };
+        input.push_str(&format!("{action}{value}\n"));
+
+        let step = match action {
+            'N' => (0, value),
+            'S' => (0, -value),
+            'E' => (value, 0),
+            'W' => (-value, 0),
+            _ => (0, 0),
+        };
+
+        match action {
+            'L' => {
+                heading = turn_left(heading, value);
+                waypoint = turn_left(waypoint, value);
+            }
+            'R' => {
+                heading = turn_left(heading, -value);
+                waypoint = turn_left(waypoint, -value);
+            }
+            'F' => {
+                ship = (ship.0 + heading.0 * value, ship.1 + heading.1 * value);
+                ship_2 = (ship_2.0 + waypoint.0 * value, ship_2.1 + waypoint.1 * value);
+            }
+            _ => {
+                ship = (ship.0 + step.0, ship.1 + step.1);
+                waypoint = (waypoint.0 + step.0, waypoint.1 + step.1);
             }
         }
     }
 
-    ship.0.abs() + ship.1.abs()
+    Ok(Generated::new(
+        input,
+        Some(Answer::from(ship.0.abs() + ship.1.abs())),
+        Some(Answer::from(ship_2.0.abs() + ship_2.1.abs())),
+    ))
+}
+
+pub struct Day12;
+
+impl Solution for Day12 {
+    const DAY: u32 = 12;
+
+    type Input = Vec<Instruction>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_data(input)
+    }
+
+    fn part_1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_1(input)?.into())
+    }
+
+    fn part_2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_2(input)?.into())
+    }
 }
 
 #[cfg(test)]
@@ -132,19 +318,48 @@ mod tests {
     use super::*;
     use rstest::rstest;
 
-    use crate::utilities::file_utilities::get_file_path;
+    use crate::utilities::input::puzzle_input;
 
     #[rstest]
     #[case(true, 25)]
     #[case(false, 904)]
     fn test_part_1(#[case] is_test: bool, #[case] expected: i32) {
-        assert_eq!(expected, part_1(get_file_path(is_test, 12, None)));
+        let instructions = parse_data(&puzzle_input(is_test, 12, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_1(&instructions).unwrap());
     }
 
     #[rstest]
     #[case(true, 286)]
     #[case(false, 18747)]
     fn test_part_2(#[case] is_test: bool, #[case] expected: i32) {
-        assert_eq!(expected, part_2(get_file_path(is_test, 12, None)));
+        let instructions = parse_data(&puzzle_input(is_test, 12, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_2(&instructions).unwrap());
+    }
+
+    #[rstest]
+    #[case(1, vec![(0, 0), (10, 0), (10, 3), (17, 3), (17, 3), (17, -8)])]
+    #[case(2, vec![(0, 0), (100, 10), (100, 10), (170, 38), (170, 38), (214, -72)])]
+    fn test_sail(#[case] part: u32, #[case] expected: Vec<(i32, i32)>) {
+        let instructions = parse_data(&puzzle_input(true, 12, None).read().unwrap()).unwrap();
+        let path = match part {
+            1 => sail_by_heading(&instructions).unwrap(),
+            _ => sail_by_waypoint(&instructions).unwrap(),
+        };
+
+        assert_eq!(expected, path);
+    }
+
+    #[rstest]
+    #[case("E2000000000\nE2000000000", 1, "moving")]
+    #[case("E2000000000\nN2000000000", 1, "measuring the distance")]
+    #[case("F300000000", 2, "sailing to the waypoint")]
+    fn test_overflow(#[case] input: &str, #[case] part: u32, #[case] operation: &str) {
+        let instructions = parse_data(input).unwrap();
+        let distance = match part {
+            1 => part_1(&instructions),
+            _ => part_2(&instructions),
+        };
+
+        assert_eq!(Err(SolveError::Overflow(String::from(operation))), distance);
     }
 }
@@ -1,29 +1,60 @@
 use std::str;
 
-use crate::utilities::file_utilities::read_lines;
-
-fn parse_line(line: &str) -> Vec<Option<usize>> {
-    line.split(',')
-        .map(str::trim)
-        .map(|x| match x.parse::<usize>() {
-            Ok(number) => Some(number),
-            Err(_) => None,
-        })
-        .collect()
+use nom::branch::alt;
+use nom::combinator::{map, value, verify};
+use nom::error::context;
+
+use crate::error::{parse_lines, OrOverflow, ParseError, SolveError};
+use crate::generate::Generated;
+use crate::solution::{Answer, Solution};
+use crate::utilities::binary::{from_bytes, to_bytes};
+use crate::utilities::grammar::{list, number, parse_all, token, ws, GResult};
+use crate::utilities::random::Rng;
+
+// 7,13,x,x,59 or just the earliest departure on its own.
+fn busses(input: &str) -> GResult<'_, Vec<Option<usize>>> {
+    let bus_id = context(
+        "a positive bus id",
+        verify(ws(number), |bus: &usize| *bus > 0),
+    );
+
+    list(
+        token(","),
+        alt((value(None, token("x")), map(bus_id, Some))),
+    )(input)
 }
 
-fn parse_data(file_path: String) -> Vec<Vec<Option<usize>>> {
-    read_lines(file_path)
-        .into_iter()
-        .map(|line| parse_line(line.as_str()))
-        .collect()
+fn parse_line(line: &str) -> Result<Vec<Option<usize>>, ParseError> {
+    parse_all(line, busses)
 }
 
-pub fn part_1(file_path: String) -> usize {
-    let numbers = parse_data(file_path);
+pub fn parse_data(input: &str) -> Result<Vec<Vec<Option<usize>>>, ParseError> {
+    parse_lines(input, parse_line)
+}
 
-    let arrival_time = numbers[0][0].unwrap();
-    let busses = numbers[1].clone().into_iter().flatten().collect::<Vec<_>>();
+fn bus_line(numbers: &[Vec<Option<usize>>]) -> Result<&[Option<usize>], SolveError> {
+    numbers
+        .get(1)
+        .map(Vec::as_slice)
+        .ok_or_else(|| SolveError::NoSolution(String::from("Missing the line of busses")))
+}
+
+pub fn part_1(numbers: &[Vec<Option<usize>>]) -> Result<usize, SolveError> {
+    let arrival_time = numbers
+        .first()
+        .and_then(|line| line.first().copied().flatten())
+        .ok_or_else(|| SolveError::NoSolution(String::from("Missing the arrival time")))?;
+    let busses = bus_line(numbers)?
+        .iter()
+        .flatten()
+        .copied()
+        .collect::<Vec<_>>();
+
+    if busses.is_empty() {
+        return Err(SolveError::NoSolution(String::from(
+            "No busses are in service",
+        )));
+    }
 
     let mut min_bus = usize::MAX;
     let mut min_wait = usize::MAX;
@@ -36,25 +67,128 @@ pub fn part_1(file_path: String) -> usize {
         }
     }
 
-    min_wait * min_bus
+    min_wait
+        .checked_mul(min_bus)
+        .or_overflow("multiplying the wait by the bus id")
 }
 
-pub fn part_2(file_path: String) -> usize {
-    let numbers = parse_data(file_path);
-
-    let mut time = 1;
-    let mut jump = 1;
+pub fn part_2(numbers: &[Vec<Option<usize>>]) -> Result<u128, SolveError> {
+    let mut time: u128 = 1;
+    let mut jump: u128 = 1;
+    let operation = "lining up the busses";
 
-    for (required_modulo, bus) in numbers[1].iter().enumerate() {
+    for (required_modulo, bus) in bus_line(numbers)?.iter().enumerate() {
         if let Some(bus) = bus {
-            while (time + required_modulo) % bus != 0 {
-                time += jump;
+            let (required_modulo, bus) = (required_modulo as u128, *bus as u128);
+
+            while time.checked_add(required_modulo).or_overflow(operation)? % bus != 0 {
+                time = time.checked_add(jump).or_overflow(operation)?;
             }
-            jump *= bus;
+            jump = jump.checked_mul(bus).or_overflow(operation)?;
+        }
+    }
+
+    Ok(time)
+}
+
+const SMALL_PRIMES: [i64; 15] = [7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61];
+
+fn is_prime(number: i64) -> bool {
+    number > 1
+        && (2..)
+            .take_while(|divisor| divisor * divisor <= number)
+            .all(|divisor| number % divisor != 0)
+}
+
+// Like the real inputs, one bus has a large prime id and the rest small ones. The timestamp is picked first and every bus is placed where it departs in step with it.
+pub fn generate(rng: &mut Rng, size: usize) -> Result<Generated, String> {
+    if !(1..=SMALL_PRIMES.len() + 1).contains(&size) {
+        return Err(format!(
+            "Day 13 needs between 1 and {} busses",
+            SMALL_PRIMES.len() + 1
+        ));
+    }
+
+    let mut busses = SMALL_PRIMES.to_vec();
+    rng.shuffle(&mut busses);
+    busses.truncate(size - 1);
+    busses.push(loop {
+        let large = rng.range(400..=1000);
+        if is_prime(large) {
+            break large;
         }
+    });
+
+    let period: i64 = busses.iter().product();
+    let timestamp = rng.range(1..=period - 1);
+
+    let mut schedule: Vec<Option<i64>> = vec![];
+    for bus in busses.iter() {
+        let mut offset = (bus - timestamp % bus) as usize % *bus as usize;
+        while schedule.get(offset).is_some_and(Option::is_some) {
+            offset += *bus as usize;
+        }
+
+        if schedule.len() <= offset {
+            schedule.resize(offset + 1, None);
+        }
+        schedule[offset] = Some(*bus);
     }
 
-    time
+    // The bus to take has to be unique, and no bus leaves right as you arrive.
+    let (arrival, bus, wait) = loop {
+        let arrival = rng.range(100_000..=1_000_000);
+        let mut waits: Vec<(i64, i64)> = busses
+            .iter()
+            .map(|bus| (bus - arrival % bus, *bus))
+            .collect();
+        waits.sort();
+
+        if waits.iter().all(|(wait, bus)| wait != bus)
+            && waits.get(1).is_none_or(|next| next.0 != waits[0].0)
+        {
+            break (arrival, waits[0].1, waits[0].0);
+        }
+    };
+
+    let schedule: Vec<String> = schedule
+        .iter()
+        .map(|bus| bus.map_or(String::from("x"), |bus| bus.to_string()))
+        .collect();
+
+    Ok(Generated::new(
+        format!("{arrival}\n{}\n", schedule.join(",")),
+        Some(Answer::from(bus * wait)),
+        Some(Answer::from(timestamp)),
+    ))
+}
+
+pub struct Day13;
+
+impl Solution for Day13 {
+    const DAY: u32 = 13;
+
+    type Input = Vec<Vec<Option<usize>>>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_data(input)
+    }
+
+    fn part_1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_1(input)?.into())
+    }
+
+    fn part_2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_2(input)?.into())
+    }
+
+    fn encode(input: &Self::Input) -> Option<Vec<u8>> {
+        Some(to_bytes(input))
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self::Input> {
+        from_bytes(bytes)
+    }
 }
 
 #[cfg(test)]
@@ -62,19 +196,133 @@ mod tests {
     use super::*;
     use rstest::rstest;
 
-    use crate::utilities::file_utilities::get_file_path;
+    use itertools::Itertools;
+
+    use crate::utilities::input::puzzle_input;
+    use crate::utilities::property::{agree, shrink_number, Property};
+
+    const TINY_PRIMES: [usize; 6] = [2, 3, 5, 7, 11, 13];
+
+    fn earliest_bus_naive(arrival_time: usize, busses: &[Option<usize>]) -> usize {
+        (arrival_time + 1..)
+            .find_map(|time| {
+                busses
+                    .iter()
+                    .flatten()
+                    .find(|bus| time % *bus == 0)
+                    .map(|bus| (time - arrival_time) * bus)
+            })
+            .unwrap()
+    }
+
+    fn earliest_timestamp_naive(busses: &[Option<usize>]) -> usize {
+        (1..)
+            .find(|time| {
+                (0..)
+                    .zip(busses)
+                    .all(|(offset, bus)| bus.is_none_or(|bus| (time + offset) % bus == 0))
+            })
+            .unwrap()
+    }
+
+    // Distinct primes keep the ids coprime, so every schedule has a timestamp.
+    fn random_notes(rng: &mut Rng) -> (usize, Vec<Option<usize>>) {
+        let mut primes = TINY_PRIMES.to_vec();
+        rng.shuffle(&mut primes);
+
+        let mut busses: Vec<Option<usize>> = primes
+            .into_iter()
+            .take(rng.range(1..=4) as usize)
+            .map(Some)
+            .collect();
+        busses.resize(rng.range(busses.len() as i64..=8) as usize, None);
+        rng.shuffle(&mut busses);
+
+        (rng.range(0..=200) as usize, busses)
+    }
+
+    fn shrink_notes(
+        (arrival_time, busses): &(usize, Vec<Option<usize>>),
+    ) -> Vec<(usize, Vec<Option<usize>>)> {
+        let mut smaller = vec![];
+
+        for index in 0..busses.len() {
+            let mut fewer = busses.clone();
+            fewer.remove(index);
+            smaller.push((*arrival_time, fewer));
+        }
+        for (index, bus) in busses.iter().enumerate() {
+            if bus.is_some() {
+                let mut fewer = busses.clone();
+                fewer[index] = None;
+                smaller.push((*arrival_time, fewer));
+            }
+        }
+        smaller.extend(
+            shrink_number(*arrival_time)
+                .into_iter()
+                .map(|arrival_time| (arrival_time, busses.clone())),
+        );
+
+        smaller
+    }
+
+    #[test]
+    fn test_part_1_matches_naive() {
+        Property::new().check(
+            random_notes,
+            |notes| {
+                shrink_notes(notes)
+                    .into_iter()
+                    .filter(|(_, busses)| busses.iter().any(Option::is_some))
+                    .collect()
+            },
+            |(arrival_time, busses)| {
+                let numbers = vec![vec![Some(*arrival_time)], busses.clone()];
+                agree(
+                    part_1(&numbers).unwrap(),
+                    earliest_bus_naive(*arrival_time, busses),
+                )
+            },
+        );
+    }
+
+    #[test]
+    fn test_part_2_matches_naive() {
+        Property::new().check(random_notes, shrink_notes, |(arrival_time, busses)| {
+            let numbers = vec![vec![Some(*arrival_time)], busses.clone()];
+            agree(
+                part_2(&numbers).unwrap(),
+                earliest_timestamp_naive(busses) as u128,
+            )
+        });
+    }
 
     #[rstest]
     #[case(true, 295)]
     #[case(false, 1835)]
     fn test_part_1(#[case] is_test: bool, #[case] expected: usize) {
-        assert_eq!(expected, part_1(get_file_path(is_test, 13, None)));
+        let numbers = parse_data(&puzzle_input(is_test, 13, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_1(&numbers).unwrap());
     }
 
     #[rstest]
     #[case(true, 1068781)]
     #[case(false, 247086664214628)]
-    fn test_part_2(#[case] is_test: bool, #[case] expected: usize) {
-        assert_eq!(expected, part_2(get_file_path(is_test, 13, None)));
+    fn test_part_2(#[case] is_test: bool, #[case] expected: u128) {
+        let numbers = parse_data(&puzzle_input(is_test, 13, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_2(&numbers).unwrap());
+    }
+
+    #[test]
+    fn test_part_2_overflow() {
+        // The first 30 primes multiply to more than a u128 holds.
+        let primes: Vec<i64> = (2..).filter(|number| is_prime(*number)).take(30).collect();
+        let numbers = parse_data(&format!("1\n{}", primes.iter().join(","))).unwrap();
+
+        assert_eq!(
+            Err(SolveError::Overflow(String::from("lining up the busses"))),
+            part_2(&numbers)
+        );
     }
 }
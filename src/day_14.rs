@@ -1,62 +1,101 @@
 use std::collections::HashMap;
 use std::str;
 
-use crate::utilities::file_utilities::read_lines;
+use nom::branch::alt;
+use nom::bytes::complete::take_while_m_n;
+use nom::character::complete::satisfy;
+use nom::combinator::{map, not};
+use nom::error::context;
+use nom::sequence::{pair, preceded, terminated, tuple};
+
+use crate::error::{parse_lines, ParseError, SolveError};
+use crate::generate::Generated;
+use crate::solution::{Answer, Solution};
+use crate::utilities::grammar::{number, parse_all, token, ws, GResult};
+use crate::utilities::random::Rng;
 
 const BITS: usize = 36;
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MaskBit {
+    Floating,
+    Zero,
+    One,
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
-enum InputType {
-    Mask(Box<[Option<usize>; BITS]>),
+pub enum InputType {
+    Mask(Box<[MaskBit; BITS]>),
     MemOverride(usize, i64),
 }
 
-fn parse_line(line: &str) -> InputType {
-    let split_line: Vec<&str> = line.split('=').map(str::trim).collect();
-    if split_line[0] == "mask" {
-        let mut mask: [Option<usize>; BITS] = [None; BITS];
-        for (index, char) in split_line[1].chars().rev().enumerate() {
-            match char {
-                'X' => mask[index] = None,
-                '1' => mask[index] = Some(1),
-                '0' => mask[index] = Some(0),
-                _ => panic!(),
+// mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X
+fn mask(input: &str) -> GResult<'_, InputType> {
+    let bits = take_while_m_n(BITS, BITS, |c| matches!(c, 'X' | '1' | '0'));
+
+    map(
+        preceded(
+            pair(token("mask"), token("=")),
+            ws(context(
+                "a mask of 36 bits",
+                terminated(bits, not(satisfy(|c| c.is_alphanumeric()))),
+            )),
+        ),
+        |bits: &str| {
+            let mut mask = [MaskBit::Floating; BITS];
+
+            for (index, bit) in bits.chars().rev().enumerate() {
+                mask[index] = match bit {
+                    '0' => MaskBit::Zero,
+                    '1' => MaskBit::One,
+                    _ => MaskBit::Floating,
+                };
             }
-        }
 
-        return InputType::Mask(Box::new(mask));
-    }
+            InputType::Mask(Box::new(mask))
+        },
+    )(input)
+}
 
-    let length = split_line[0].len();
-    let address = split_line[0][4..length - 1].parse::<usize>().unwrap();
-    let value = split_line[1].parse::<i64>().unwrap();
+// mem[8] = 11
+fn mem_override(input: &str) -> GResult<'_, InputType> {
+    map(
+        tuple((
+            token("mem"),
+            token("["),
+            ws(number),
+            token("]"),
+            token("="),
+            ws(number),
+        )),
+        |(_, _, address, _, _, value)| InputType::MemOverride(address, value),
+    )(input)
+}
 
-    InputType::MemOverride(address, value)
+fn parse_line(line: &str) -> Result<InputType, ParseError> {
+    parse_all(line, alt((mask, mem_override)))
 }
 
-fn parse_data(file_path: String) -> Vec<InputType> {
-    read_lines(file_path)
-        .into_iter()
-        .map(|line| parse_line(line.as_str()))
-        .collect()
+pub fn parse_data(input: &str) -> Result<Vec<InputType>, ParseError> {
+    parse_lines(input, parse_line)
 }
 
-pub fn part_1(file_path: String) -> i64 {
-    let instructions = parse_data(file_path);
-    let mut mask = [None; 36];
+pub fn part_1(instructions: &[InputType]) -> Result<i64, SolveError> {
+    let mut mask = [MaskBit::Floating; BITS];
     let mut memory: HashMap<usize, i64> = HashMap::new();
 
     for instruction in instructions {
-        match instruction {
-            InputType::Mask(m) => mask = *m,
+        match *instruction {
+            InputType::Mask(ref m) => mask = **m,
             InputType::MemOverride(address, value) => {
                 let masked_value = mask
                     .iter()
                     .enumerate()
                     .map(|(index, mask_bit)| {
                         let masked_bit = match *mask_bit {
-                            None => (value >> index) & 1,
-                            Some(b) => b as i64,
+                            MaskBit::Floating => (value >> index) & 1,
+                            MaskBit::Zero => 0,
+                            MaskBit::One => 1,
                         };
                         masked_bit * 2_i64.pow(index as u32)
                     })
@@ -67,26 +106,24 @@ pub fn part_1(file_path: String) -> i64 {
         }
     }
 
-    memory.values().sum::<i64>()
+    Ok(memory.values().sum::<i64>())
 }
 
-pub fn part_2(file_path: String) -> i64 {
-    let instructions = parse_data(file_path);
-    let mut mask = [None; 36];
+pub fn part_2(instructions: &[InputType]) -> Result<i64, SolveError> {
+    let mut mask = [MaskBit::Floating; BITS];
     let mut memory: HashMap<usize, i64> = HashMap::new();
 
     for instruction in instructions {
-        match instruction {
-            InputType::Mask(m) => mask = *m,
+        match *instruction {
+            InputType::Mask(ref m) => mask = **m,
             InputType::MemOverride(address, value) => {
                 let masked_address = mask
                     .iter()
                     .enumerate()
                     .map(|(index, mask_bit)| match *mask_bit {
-                        None => None,
-                        Some(0) => Some((address >> index) & 1),
-                        Some(1) => Some(1),
-                        _ => panic!(),
+                        MaskBit::Floating => None,
+                        MaskBit::Zero => Some((address >> index) & 1),
+                        MaskBit::One => Some(1),
                     })
                     .collect::<Vec<_>>();
 
@@ -98,12 +135,10 @@ pub fn part_2(file_path: String) -> i64 {
                             .into_iter()
                             .flat_map(|address| vec![address, address + 2_usize.pow(index as u32)])
                             .collect(),
-                        Some(0) => addresses,
-                        Some(1) => addresses
+                        Some(bit) => addresses
                             .into_iter()
-                            .map(|address| address + 2_usize.pow(index as u32))
+                            .map(|address| address + bit * 2_usize.pow(index as u32))
                             .collect(),
-                        _ => panic!(),
                     }
                 }
 
@@ -114,7 +149,85 @@ pub fn part_2(file_path: String) -> i64 {
         }
     }
 
-    memory.values().sum::<i64>()
+    Ok(memory.values().sum::<i64>())
+}
+
+// Masks float between three and eight bits, so version 2 writes at most 256 addresses at a time.
+pub fn generate(rng: &mut Rng, size: usize) -> Result<Generated, String> {
+    let mut input = String::new();
+    let (mut ones, mut floating) = (0_u64, 0_u64);
+    let mut memory_1: HashMap<u64, u64> = HashMap::new();
+    let mut memory_2: HashMap<u64, u64> = HashMap::new();
+
+    for index in 0..size {
+        if index == 0 || rng.chance(1, 5) {
+            let mut bits: Vec<usize> = (0..BITS).collect();
+            rng.shuffle(&mut bits);
+            floating = bits[..rng.range(3..=8) as usize]
+                .iter()
+                .map(|bit| 1 << bit)
+                .sum();
+            ones = (0..BITS as u64)
+                .filter(|_| rng.chance(1, 2))
+                .map(|bit| 1 << bit)
+                .sum::<u64>()
+                & !floating;
+
+            let mask: String = (0..BITS)
+                .rev()
+                .map(|bit| match (floating >> bit & 1, ones >> bit & 1) {
+                    (1, _) => 'X',
+                    (_, 1) => '1',
+                    _ => '0',
+                })
+                .collect();
+            input.push_str(&format!("mask = {mask}\n"));
+            continue;
+        }
+
+        let address = rng.range(0..=65535) as u64;
+        let value = rng.range(0..=(1 << 30)) as u64;
+        input.push_str(&format!("mem[{address}] = {value}\n"));
+
+        memory_1.insert(address, value & floating | ones);
+
+        // Walks every subset of the floating bits.
+        let base = (address | ones) & !floating;
+        let mut subset = floating;
+        loop {
+            memory_2.insert(base | subset, value);
+            if subset == 0 {
+                break;
+            }
+            subset = (subset - 1) & floating;
+        }
+    }
+
+    Ok(Generated::new(
+        input,
+        Some(Answer::from(memory_1.values().sum::<u64>())),
+        Some(Answer::from(memory_2.values().sum::<u64>())),
+    ))
+}
+
+pub struct Day14;
+
+impl Solution for Day14 {
+    const DAY: u32 = 14;
+
+    type Input = Vec<InputType>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_data(input)
+    }
+
+    fn part_1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_1(input)?.into())
+    }
+
+    fn part_2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_2(input)?.into())
+    }
 }
 
 #[cfg(test)]
@@ -122,19 +235,21 @@ mod tests {
     use super::*;
     use rstest::rstest;
 
-    use crate::utilities::file_utilities::get_file_path;
+    use crate::utilities::input::puzzle_input;
 
     #[rstest]
     #[case(true, 51)]
     #[case(false, 9628746976360)]
     fn test_part_1(#[case] is_test: bool, #[case] expected: i64) {
-        assert_eq!(expected, part_1(get_file_path(is_test, 14, None)));
+        let instructions = parse_data(&puzzle_input(is_test, 14, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_1(&instructions).unwrap());
     }
 
     #[rstest]
     #[case(true, 208)]
     #[case(false, 4574598714592)]
     fn test_part_2(#[case] is_test: bool, #[case] expected: i64) {
-        assert_eq!(expected, part_2(get_file_path(is_test, 14, None)));
+        let instructions = parse_data(&puzzle_input(is_test, 14, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_2(&instructions).unwrap());
     }
 }
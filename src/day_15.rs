@@ -1,8 +1,21 @@
 use std::collections::HashMap;
 
-pub fn do_math(numbers: &Vec<usize>, turns: usize) -> i64 {
+use crate::error::{parse_single_line, ParseError, SolveError};
+use crate::generate::Generated;
+use crate::observer::{self, Event};
+use crate::solution::{Answer, Solution};
+use crate::utilities::grammar::{list, number, parse_all, token, ws};
+use crate::utilities::random::Rng;
+
+pub fn parse_data(input: &str) -> Result<Vec<usize>, ParseError> {
+    parse_single_line(input, |line| parse_all(line, list(token(","), ws(number))))
+}
+
+pub fn do_math(numbers: &[usize], turns: usize) -> Result<i64, SolveError> {
     let mut memory = HashMap::new();
-    let mut last_number = numbers[0];
+    let mut last_number = *numbers
+        .first()
+        .ok_or_else(|| SolveError::NoSolution(String::from("There are no starting numbers")))?;
 
     for turn in 0..turns {
         let new_number = if turn < numbers.len() {
@@ -13,7 +26,10 @@ pub fn do_math(numbers: &Vec<usize>, turns: usize) -> i64 {
             0
         };
 
-        // println!("{new_number} in turn {}", turn + 1);
+        observer::emit(|| Event::NumberSpoken {
+            turn: turn + 1,
+            number: new_number,
+        });
 
         if turn > 0 {
             memory.insert(last_number, turn - 1);
@@ -22,53 +38,111 @@ pub fn do_math(numbers: &Vec<usize>, turns: usize) -> i64 {
         last_number = new_number;
     }
 
-    last_number as i64
+    Ok(last_number as i64)
 }
 
-pub fn part_1(numbers: &Vec<usize>) -> i64 {
+pub fn part_1(numbers: &[usize]) -> Result<i64, SolveError> {
     do_math(numbers, 2020)
 }
 
-pub fn part_2(numbers: &Vec<usize>) -> i64 {
+pub fn part_2(numbers: &[usize]) -> Result<i64, SolveError> {
     do_math(numbers, 30000000)
 }
 
+// Only the 2020th number is worked out, the 30000000th takes as long as solving.
+pub fn generate(rng: &mut Rng, size: usize) -> Result<Generated, String> {
+    if !(1..=20).contains(&size) {
+        return Err(String::from(
+            "Day 15 needs between 1 and 20 starting numbers",
+        ));
+    }
+
+    let starting: Vec<usize> = rng
+        .distinct(0..=20, size)
+        .into_iter()
+        .map(|number| number as usize)
+        .collect();
+
+    let mut last_spoken: Vec<Option<usize>> = vec![None; 2020];
+    let mut spoken = starting[0];
+    for turn in 1..2020 {
+        let next = match starting.get(turn) {
+            Some(number) => *number,
+            None => last_spoken[spoken].map_or(0, |previous| turn - 1 - previous),
+        };
+        last_spoken[spoken] = Some(turn - 1);
+        spoken = next;
+    }
+
+    let starting: Vec<String> = starting.iter().map(usize::to_string).collect();
+
+    Ok(Generated::new(
+        starting.join(",") + "\n",
+        Some(Answer::from(spoken)),
+        None,
+    ))
+}
+
+pub struct Day15;
+
+impl Solution for Day15 {
+    const DAY: u32 = 15;
+
+    type Input = Vec<usize>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_data(input)
+    }
+
+    fn part_1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_1(input)?.into())
+    }
+
+    fn part_2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_2(input)?.into())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use rstest::rstest;
 
+    use crate::utilities::input::InputSource;
+
     #[rstest]
-    #[case(vec![0, 3, 6], 436)]
-    #[case(vec![1, 3, 2], 1)]
-    #[case(vec![2, 1, 3], 10)]
-    #[case(vec![1, 2, 3], 27)]
-    #[case(vec![2, 3, 1], 78)]
-    #[case(vec![3, 2, 1], 438)]
-    #[case(vec![3, 1, 2], 1836)]
-    #[case(vec![6, 4, 12, 1, 20, 0, 16], 475)]
-    fn test_part_1(#[case] input: Vec<usize>, #[case] expected: i64) {
-        assert_eq!(expected, part_1(&input));
+    #[case("0,3,6", 436)]
+    #[case("1,3,2", 1)]
+    #[case("2,1,3", 10)]
+    #[case("1,2,3", 27)]
+    #[case("2,3,1", 78)]
+    #[case("3,2,1", 438)]
+    #[case("3,1,2", 1836)]
+    #[case("6,4,12,1,20,0,16", 475)]
+    fn test_part_1(#[case] input: &str, #[case] expected: i64) {
+        let numbers = parse_data(&InputSource::from(input).read().unwrap()).unwrap();
+        assert_eq!(expected, part_1(&numbers).unwrap());
     }
 
     #[rstest]
     #[ignore]
-    #[case(vec![0, 3, 6], 175594)]
+    #[case("0,3,6", 175594)]
     #[ignore]
-    #[case(vec![1, 3, 2], 2578)]
+    #[case("1,3,2", 2578)]
     #[ignore]
-    #[case(vec![2, 1, 3], 3544142)]
+    #[case("2,1,3", 3544142)]
     #[ignore]
-    #[case(vec![1, 2, 3], 261214)]
+    #[case("1,2,3", 261214)]
     #[ignore]
-    #[case(vec![2, 3, 1], 6895259)]
+    #[case("2,3,1", 6895259)]
     #[ignore]
-    #[case(vec![3, 2, 1], 18)]
+    #[case("3,2,1", 18)]
     #[ignore]
-    #[case(vec![3, 1, 2], 362)]
+    #[case("3,1,2", 362)]
     #[ignore]
-    #[case(vec![6, 4, 12, 1, 20, 0, 16], 11261)]
-    fn test_part_2(#[case] input: Vec<usize>, #[case] expected: i64) {
-        assert_eq!(expected, part_2(&input));
+    #[case("6,4,12,1,20,0,16", 11261)]
+    fn test_part_2(#[case] input: &str, #[case] expected: i64) {
+        let numbers = parse_data(&InputSource::from(input).read().unwrap()).unwrap();
+        assert_eq!(expected, part_2(&numbers).unwrap());
     }
 }
@@ -1,53 +1,60 @@
-use crate::utilities::file_utilities::read_lines;
-
-type Rule = (String, (usize, usize), (usize, usize));
-type Ticket = Vec<usize>;
-
-fn parse_line(line: &str) -> (Option<Rule>, Option<Ticket>) {
-    if line.contains(':') {
-        let split_by_colon: Vec<&str> = line.split(':').map(str::trim).collect();
-        let field = split_by_colon[0].to_string();
-        let two_rules: Vec<&str> = split_by_colon[1].split(' ').map(str::trim).collect();
-        let rule_1_string: Vec<&str> = two_rules[0].split('-').map(str::trim).collect();
-        let rule_2_string: Vec<&str> = two_rules[2].split('-').map(str::trim).collect();
-
-        (
-            Some((
-                field,
-                (
-                    rule_1_string[0].parse::<usize>().unwrap(),
-                    rule_1_string[1].parse::<usize>().unwrap(),
-                ),
-                (
-                    rule_2_string[0].parse::<usize>().unwrap(),
-                    rule_2_string[1].parse::<usize>().unwrap(),
-                ),
-            )),
-            None,
-        )
-    } else {
-        (
-            None,
-            Some(
-                line.split(',')
-                    .map(str::trim)
-                    .map(|x| x.parse::<usize>().unwrap())
-                    .collect(),
-            ),
-        )
-    }
+use nom::branch::alt;
+use nom::bytes::complete::take_while1;
+use nom::combinator::{eof, map, value};
+use nom::error::context;
+use nom::sequence::{separated_pair, tuple};
+
+use crate::error::{parse_lines, ParseError, SolveError};
+use crate::generate::Generated;
+use crate::observer::{self, Event};
+use crate::solution::{Answer, Solution};
+use crate::utilities::grammar::{list, number, parse_all, token, ws, GResult};
+use crate::utilities::random::Rng;
+
+pub type Rule = (String, (usize, usize), (usize, usize));
+pub type Ticket = Vec<usize>;
+pub type Note = (Option<Rule>, Option<Ticket>);
+
+fn range(input: &str) -> GResult<'_, (usize, usize)> {
+    separated_pair(ws(number), token("-"), ws(number))(input)
 }
 
-fn parse_data(file_path: String) -> Vec<(Option<Rule>, Option<Ticket>)> {
-    read_lines(file_path)
-        .into_iter()
-        .map(|line| parse_line(line.as_str()))
-        .collect()
+// departure location: 25-80 or 90-961
+fn rule(input: &str) -> GResult<'_, Rule> {
+    let field = context("a field name", take_while1(|c| c != ':'));
+
+    map(
+        tuple((field, token(":"), range, token("or"), range)),
+        |(field, _, range_1, _, range_2): (&str, _, _, _, _)| {
+            (String::from(field.trim()), range_1, range_2)
+        },
+    )(input)
+}
+
+fn ticket(input: &str) -> GResult<'_, Ticket> {
+    list(token(","), ws(number))(input)
 }
 
-pub fn part_1(file_path: String) -> i64 {
-    let data = parse_data(file_path);
+// The puzzle's own layout labels the tickets and separates the sections with blank lines, none of which carry data.
+fn note(input: &str) -> GResult<'_, Note> {
+    let label = alt((token("your ticket:"), token("nearby tickets:"), ws(eof)));
 
+    alt((
+        value((None, None), label),
+        map(ticket, |ticket| (None, Some(ticket))),
+        map(rule, |rule| (Some(rule), None)),
+    ))(input)
+}
+
+fn parse_line(line: &str) -> Result<Note, ParseError> {
+    parse_all(line, note)
+}
+
+pub fn parse_data(input: &str) -> Result<Vec<Note>, ParseError> {
+    parse_lines(input, parse_line)
+}
+
+pub fn part_1(data: &[Note]) -> Result<i64, SolveError> {
     let rules: Vec<(usize, usize)> = data
         .iter()
         .cloned()
@@ -62,20 +69,20 @@ pub fn part_1(file_path: String) -> i64 {
         .flatten()
         .collect();
 
-    fields
+    let error_rate = fields
         .into_iter()
         .skip(rules.len() / 2)
         .filter(|field| {
-            return rules
+            rules
                 .iter()
-                .all(|(start, end)| !(start..=end).contains(&field));
+                .all(|(start, end)| !(start..=end).contains(&field))
         })
-        .sum::<usize>() as i64
-}
+        .sum::<usize>();
 
-pub fn part_2(file_path: String) -> i64 {
-    let data = parse_data(file_path);
+    Ok(error_rate as i64)
+}
 
+pub fn part_2(data: &[Note]) -> Result<i64, SolveError> {
     let rules: Vec<Rule> = data.iter().cloned().filter_map(|(rule, _)| rule).collect();
 
     let valid_tickets: Vec<Ticket> = data
@@ -92,6 +99,23 @@ pub fn part_2(file_path: String) -> i64 {
         })
         .collect();
 
+    if valid_tickets.is_empty() {
+        return Err(SolveError::NoSolution(String::from(
+            "Your ticket is missing",
+        )));
+    }
+
+    if let Some(ticket) = valid_tickets
+        .iter()
+        .find(|ticket| ticket.len() != rules.len())
+    {
+        return Err(SolveError::NoSolution(format!(
+            "A ticket has {} fields, but there are {} rules",
+            ticket.len(),
+            rules.len()
+        )));
+    }
+
     let mut validity_matrix = vec![vec![false; rules.len()]; rules.len()];
 
     for field_index in 0..rules.len() {
@@ -110,6 +134,8 @@ pub fn part_2(file_path: String) -> i64 {
     let mut known_rule_field_indices = vec![42; rules.len()];
 
     while known_field_indices.len() < rules.len() {
+        let mut made_progress = false;
+
         for field_index in 0..rules.len() {
             if known_field_indices.contains(&field_index) {
                 continue;
@@ -130,27 +156,165 @@ pub fn part_2(file_path: String) -> i64 {
                 )
                 .collect::<Vec<_>>();
 
-            let first_match = matches.first().unwrap();
-            let last_match = matches.last().unwrap();
-
-            if first_match == last_match {
+            if let [only_match] = matches[..] {
                 known_field_indices.push(field_index);
-                known_rule_field_indices[field_index] = *first_match;
+                known_rule_field_indices[field_index] = only_match;
+                made_progress = true;
                 break;
             }
         }
+
+        if !made_progress {
+            return Err(SolveError::NoSolution(String::from(
+                "Cannot tell which field each rule belongs to",
+            )));
+        }
     }
 
     let mut result = 1;
     for (field_index, rule_index) in known_rule_field_indices.into_iter().enumerate() {
-        // println!("{}: {}", rules[rule_index].0, valid_tickets[0][field_index]);
+        observer::emit(|| Event::FieldIdentified {
+            field: rules[rule_index].0.clone(),
+            column: field_index,
+        });
 
         if rules[rule_index].0.contains("departure") {
             result *= valid_tickets[0][field_index];
         }
     }
 
-    result as i64
+    Ok(result as i64)
+}
+
+const FIELD_NAMES: [&str; 20] = [
+    "departure location",
+    "departure station",
+    "departure platform",
+    "departure track",
+    "departure date",
+    "departure time",
+    "arrival location",
+    "arrival station",
+    "arrival platform",
+    "arrival track",
+    "class",
+    "duration",
+    "price",
+    "route",
+    "row",
+    "seat",
+    "train",
+    "type",
+    "wagon",
+    "zone",
+];
+
+// Every field draws its values from a band of its own, and each rule accepts its band and all the bands above it. Any field can then be placed by elimination, starting with the field of the last rule.
+pub fn generate(rng: &mut Rng, size: usize) -> Result<Generated, String> {
+    if !(1..=FIELD_NAMES.len()).contains(&size) {
+        return Err(format!(
+            "Day 16 needs between 1 and {} fields",
+            FIELD_NAMES.len()
+        ));
+    }
+
+    let mut names = FIELD_NAMES.to_vec();
+    rng.shuffle(&mut names);
+    names.truncate(size);
+
+    let mut bands = vec![];
+    let mut start = 25;
+    for _ in 0..size {
+        let end = start + rng.range(30..=45);
+        bands.push((start, end));
+        start = end + 1;
+    }
+    let highest = bands[size - 1].1;
+
+    let mut rules: Vec<String> = names
+        .iter()
+        .zip(bands.iter())
+        .map(|(name, (start, _))| {
+            let split = rng.range(*start..=highest - 1);
+            format!("{name}: {start}-{split} or {}-{highest}", split + 1)
+        })
+        .collect();
+    rng.shuffle(&mut rules);
+
+    let mut positions: Vec<usize> = (0..size).collect();
+    rng.shuffle(&mut positions);
+
+    let ticket = |rng: &mut Rng| -> Vec<i64> {
+        positions
+            .iter()
+            .map(|field| rng.range(bands[*field].0..=bands[*field].1))
+            .collect()
+    };
+
+    let yours = ticket(rng);
+    let mut error_rate = 0;
+    let nearby: Vec<Vec<i64>> = (0..10 * size + 20)
+        .map(|_| {
+            let mut nearby = ticket(rng);
+            if rng.chance(1, 4) {
+                let invalid = if rng.chance(1, 2) {
+                    rng.range(1..=24)
+                } else {
+                    rng.range(highest + 1..=highest + 100)
+                };
+                nearby[rng.index(size)] = invalid;
+                error_rate += invalid;
+            }
+            nearby
+        })
+        .collect();
+
+    let departure: i64 = positions
+        .iter()
+        .zip(yours.iter())
+        .filter(|(field, _)| names[**field].starts_with("departure"))
+        .map(|(_, value)| value)
+        .product();
+
+    let render = |ticket: &Vec<i64>| {
+        ticket
+            .iter()
+            .map(i64::to_string)
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    let input = format!(
+        "{}\n\nyour ticket:\n{}\n\nnearby tickets:\n{}\n",
+        rules.join("\n"),
+        render(&yours),
+        nearby.iter().map(render).collect::<Vec<_>>().join("\n")
+    );
+
+    Ok(Generated::new(
+        input,
+        Some(Answer::from(error_rate)),
+        Some(Answer::from(departure)),
+    ))
+}
+
+pub struct Day16;
+
+impl Solution for Day16 {
+    const DAY: u32 = 16;
+
+    type Input = Vec<Note>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_data(input)
+    }
+
+    fn part_1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_1(input)?.into())
+    }
+
+    fn part_2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_2(input)?.into())
+    }
 }
 
 #[cfg(test)]
@@ -158,22 +322,46 @@ mod tests {
     use super::*;
     use rstest::rstest;
 
-    use crate::utilities::file_utilities::get_file_path;
+    use crate::utilities::input::puzzle_input;
 
     #[rstest]
     #[case("-a", 71)]
     #[case("-b", 0)]
     fn test_part_1(#[case] suffix: &str, #[case] expected: i64) {
-        assert_eq!(expected, part_1(get_file_path(true, 16, Some(suffix))));
+        let data = parse_data(&puzzle_input(true, 16, Some(suffix)).read().unwrap()).unwrap();
+        assert_eq!(expected, part_1(&data).unwrap());
+    }
+
+    #[test]
+    fn test_parse_data_accepts_the_puzzle_layout() {
+        let input = "class: 1-3 or 5-7\nrow: 6-11 or 33-44\n\nyour ticket:\n7,1,14\n\nnearby tickets:\n7,3,47\n";
+        let data = parse_data(input).unwrap();
+
+        assert_eq!(
+            parse_data("class: 1-3 or 5-7\nrow: 6-11 or 33-44\n7,1,14\n7,3,47").unwrap(),
+            data.into_iter()
+                .filter(|note| *note != (None, None))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[rstest]
+    #[case("class: 1-3 or x-7", 15)]
+    #[case("class: 1-3 and 5-7", 12)]
+    #[case("7,1,", 5)]
+    fn test_parse_line_reports_span(#[case] line: &str, #[case] column: usize) {
+        assert_eq!(column, parse_line(line).unwrap_err().column);
     }
 
     #[test]
     fn test_part_1_real() {
-        assert_eq!(20058, part_1(get_file_path(false, 16, None)));
+        let data = parse_data(&puzzle_input(false, 16, None).read().unwrap()).unwrap();
+        assert_eq!(20058, part_1(&data).unwrap());
     }
 
     #[test]
     fn test_part_2_real() {
-        assert_eq!(366871907221, part_2(get_file_path(false, 16, None)));
+        let data = parse_data(&puzzle_input(false, 16, None).read().unwrap()).unwrap();
+        assert_eq!(366871907221, part_2(&data).unwrap());
     }
 }
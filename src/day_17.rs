@@ -1,7 +1,15 @@
-use crate::utilities::file_utilities::read_lines;
+use crate::error::{check_characters, parse_lines, ParseError, SolveError};
+use crate::generate::Generated;
+use crate::repl::{count_argument, Session};
+use crate::solution::{Answer, Solution};
+use crate::utilities::automaton::{Automaton, Rule};
+use crate::utilities::image::{Color, Scene};
+use crate::utilities::random::Rng;
 
-use itertools::iproduct;
+use itertools::{iproduct, Itertools};
+use rustc_hash::FxHashSet;
 use std::collections::HashSet;
+use std::ops::RangeInclusive;
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 enum CoordinateTuple {
@@ -9,6 +17,13 @@ enum CoordinateTuple {
     HyperCoordinates(isize, isize, isize, isize),
 }
 
+pub fn parse_data(input: &str) -> Result<Vec<String>, ParseError> {
+    parse_lines(input, |line| {
+        check_characters(line, &['.', '#'])?;
+        Ok(String::from(line))
+    })
+}
+
 fn get_neighbours(cube: &CoordinateTuple) -> Vec<CoordinateTuple> {
     match cube {
         CoordinateTuple::Coordinates(x, y, z) => iproduct!(-1..=1, -1..=1, -1..=1)
@@ -24,102 +39,245 @@ fn get_neighbours(cube: &CoordinateTuple) -> Vec<CoordinateTuple> {
     }
 }
 
-fn simulate(active_cubes: HashSet<CoordinateTuple>) -> HashSet<CoordinateTuple> {
-    let mut active_cubes: HashSet<CoordinateTuple> = active_cubes.into_iter().collect();
+// Every cube of the starting slice sits at z = 0, and w = 0 in four dimensions.
+fn automaton(input: &[String], dimensions: usize) -> Automaton<'static, CoordinateTuple> {
+    let active_cubes: HashSet<_> = (0..input.len())
+        .flat_map(|y| {
+            input[y]
+                .chars()
+                .enumerate()
+                .filter_map(move |(x, character)| match (character, dimensions) {
+                    ('#', 3) => Some(CoordinateTuple::Coordinates(x as isize, y as isize, 0)),
+                    ('#', _) => Some(CoordinateTuple::HyperCoordinates(
+                        x as isize, y as isize, 0, 0,
+                    )),
+                    _ => None,
+                })
+        })
+        .collect();
+
+    Automaton::new(
+        active_cubes,
+        get_neighbours,
+        Rule {
+            birth: 3..=3,
+            survival: 2..=3,
+        },
+    )
+}
 
-    for _cycle in 1..=6 {
-        // println!("Cycle {cycle} starts with {} active cubes", active_cubes.len());
+const CYCLES: usize = 6;
 
-        let cubes_to_investigate: HashSet<_> = active_cubes
-            .iter()
-            .flat_map(get_neighbours)
-            .chain(active_cubes.iter().cloned())
-            .collect();
+fn simulate(mut automaton: Automaton<CoordinateTuple>) -> usize {
+    automaton.run(CYCLES);
+    automaton.population()
+}
 
-        // println!("Cycle {cycle} active_cubes: {active_cubes:?}");
-        // println!("Cycle {cycle} cubes_to_investigate: {cubes_to_investigate:?}");
+pub fn part_1(input: &[String]) -> Result<i64, SolveError> {
+    Ok(simulate(automaton(input, 3)) as i64)
+}
 
-        let still_active_cubes: Vec<_> = cubes_to_investigate
-            .iter()
-            .filter(|cube| active_cubes.contains(cube))
-            .filter(|cube| {
-                let active_neighbours_count = get_neighbours(cube)
-                    .iter()
-                    .filter(|neighbour| active_cubes.contains(neighbour))
-                    .count();
-                (2..=3).contains(&active_neighbours_count)
-            })
-            .cloned()
-            .collect();
+pub fn part_2(input: &[String]) -> Result<i64, SolveError> {
+    Ok(simulate(automaton(input, 4)) as i64)
+}
 
-        let new_active_cubes: Vec<_> = cubes_to_investigate
+// Answers are left to the solver, working them out means running the simulation.
+pub fn generate(rng: &mut Rng, size: usize) -> Result<Generated, String> {
+    let input = (0..size)
+        .map(|_| {
+            let mut row: String = (0..size)
+                .map(|_| if rng.chance(1, 2) { '#' } else { '.' })
+                .collect();
+            row.push('\n');
+            row
+        })
+        .collect();
+
+    Ok(Generated::new(input, None, None))
+}
+
+fn four_coordinates(cube: &CoordinateTuple) -> [isize; 4] {
+    match *cube {
+        CoordinateTuple::Coordinates(x, y, z) => [x, y, z, 0],
+        CoordinateTuple::HyperCoordinates(x, y, z, w) => [x, y, z, w],
+    }
+}
+
+fn cubes_and_bounds(
+    active: &FxHashSet<CoordinateTuple>,
+) -> (HashSet<[isize; 4]>, [RangeInclusive<isize>; 4]) {
+    let cubes: HashSet<[isize; 4]> = active.iter().map(four_coordinates).collect();
+    let bounds = [0, 1, 2, 3].map(|axis| {
+        let (low, high) = cubes
             .iter()
-            .filter(|cube| !active_cubes.contains(cube))
-            .filter(|cube| {
-                let active_neighbours_count = get_neighbours(cube)
-                    .iter()
-                    .filter(|neighbour| active_cubes.contains(neighbour))
-                    .count();
-                active_neighbours_count == 3
-            })
-            .cloned()
-            .collect();
+            .map(|cube| cube[axis])
+            .minmax()
+            .into_option()
+            .unwrap_or_default();
+        low..=high
+    });
 
-        // println!("Cycle {cycle} still_active_cubes: {still_active_cubes:?}");
-        // println!("Cycle {cycle} new_active_cubes: {new_active_cubes:?}");
+    (cubes, bounds)
+}
 
-        active_cubes = still_active_cubes
-            .into_iter()
-            .chain(new_active_cubes.into_iter())
-            .collect();
+const SLICE: Color = Color(220, 220, 235);
+const ACTIVE_CUBE: Color = Color(40, 40, 120);
+
+// Slices sit side by side with z growing to the right and w growing downwards, a cell apart.
+fn draw_slices(active: &FxHashSet<CoordinateTuple>) -> Scene {
+    let (cubes, [xs, ys, zs, ws]) = cubes_and_bounds(active);
+    let (slice_width, slice_height) = (xs.clone().count() + 1, ys.clone().count() + 1);
+
+    let mut scene = Scene::new(
+        (zs.clone().count() * slice_width + 1) as f64,
+        (ws.clone().count() * slice_height + 1) as f64,
+        Color::WHITE,
+    );
+
+    for (slice_row, w) in ws.enumerate() {
+        for (slice_column, z) in zs.clone().enumerate() {
+            let (top, left) = (slice_row * slice_height + 1, slice_column * slice_width + 1);
+            scene.rectangle(
+                (left as f64, top as f64),
+                ((slice_width - 1) as f64, (slice_height - 1) as f64),
+                SLICE,
+            );
+
+            for ((row, y), (column, x)) in iproduct!(ys.clone().enumerate(), xs.clone().enumerate())
+            {
+                if cubes.contains(&[x, y, z, w]) {
+                    scene.cell(top + row, left + column, ACTIVE_CUBE);
+                }
+            }
+        }
     }
 
-    // println!("{active_cubes:?}");
-    // println!("{} active cubes", active_cubes.len());
+    scene
+}
 
-    active_cubes
+// The pocket dimension after the six cycles, or after each one of them.
+pub fn render(input: &[String], part: u32, frames: bool) -> Result<Vec<Scene>, SolveError> {
+    let mut automaton = automaton(input, part as usize + 2);
+
+    if frames {
+        Ok(automaton.frames(CYCLES, draw_slices))
+    } else {
+        automaton.run(CYCLES);
+        Ok(vec![draw_slices(automaton.active())])
+    }
 }
 
-pub fn part_1(file_path: String) -> i64 {
-    let input: Vec<String> = read_lines(file_path);
+// Runs the cycles one at a time and draws the active cubes slice by slice.
+pub struct CubeSession {
+    input: Vec<String>,
+    dimensions: usize,
+    automaton: Automaton<'static, CoordinateTuple>,
+}
 
-    let mut active_cubes: HashSet<_> = (0..input.len())
-        .flat_map(|y| {
-            input[y]
-                .chars()
-                .enumerate()
-                .filter_map(move |(x, character)| match character {
-                    '#' => Some(CoordinateTuple::Coordinates(x as isize, y as isize, 0)),
-                    _ => None,
-                })
-        })
-        .collect();
+impl CubeSession {
+    pub fn new(input: &[String]) -> Self {
+        Self {
+            input: input.to_vec(),
+            dimensions: 3,
+            automaton: automaton(input, 3),
+        }
+    }
+
+    fn state(&self) -> String {
+        format!(
+            "cycle {}, {} active cubes in {} dimensions",
+            self.automaton.generation(),
+            self.automaton.population(),
+            self.dimensions
+        )
+    }
 
-    active_cubes = simulate(active_cubes);
+    // The same layout as the puzzle: one block per z (and w) slice, cropped to the active cubes.
+    fn slices(&self) -> String {
+        let (cubes, bounds) = cubes_and_bounds(self.automaton.active());
+        let range = |axis: usize| bounds[axis].clone();
 
-    active_cubes.len() as i64
+        iproduct!(range(3), range(2))
+            .map(|(w, z)| {
+                let header = match self.dimensions {
+                    3 => format!("z={z}"),
+                    _ => format!("z={z}, w={w}"),
+                };
+                let rows = range(1).map(|y| {
+                    range(0)
+                        .map(|x| {
+                            if cubes.contains(&[x, y, z, w]) {
+                                '#'
+                            } else {
+                                '.'
+                            }
+                        })
+                        .collect::<String>()
+                });
+
+                std::iter::once(header).chain(rows).join("\n")
+            })
+            .join("\n\n")
+    }
 }
 
-pub fn part_2(file_path: String) -> i64 {
-    let input: Vec<String> = read_lines(file_path);
+impl Session for CubeSession {
+    fn commands(&self) -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("state", "Show the cycle and the number of active cubes"),
+            ("step [count]", "Run one or more cycles"),
+            ("show", "Draw every slice holding active cubes"),
+            (
+                "dimensions <3|4>",
+                "Start again in three or four dimensions",
+            ),
+            ("reset", "Start again from the first cycle"),
+        ]
+    }
 
-    let mut active_cubes: HashSet<_> = (0..input.len())
-        .flat_map(|y| {
-            input[y]
-                .chars()
-                .enumerate()
-                .filter_map(move |(x, character)| match character {
-                    '#' => Some(CoordinateTuple::HyperCoordinates(
-                        x as isize, y as isize, 0, 0,
-                    )),
-                    _ => None,
-                })
+    fn execute(&mut self, command: &str, arguments: &[&str]) -> Option<Result<String, String>> {
+        Some(match command {
+            "state" => Ok(self.state()),
+            "step" => count_argument(arguments).map(|count| {
+                self.automaton.run(count);
+                self.state()
+            }),
+            "show" => Ok(self.slices()),
+            "dimensions" => match arguments {
+                ["3"] | ["4"] => {
+                    self.dimensions = arguments[0].parse().unwrap();
+                    self.automaton = automaton(&self.input, self.dimensions);
+                    Ok(self.state())
+                }
+                _ => Err(String::from("Usage: dimensions <3|4>")),
+            },
+            "reset" => {
+                self.automaton = automaton(&self.input, self.dimensions);
+                Ok(self.state())
+            }
+            _ => return None,
         })
-        .collect();
+    }
+}
 
-    active_cubes = simulate(active_cubes);
+pub struct Day17;
 
-    active_cubes.len() as i64
+impl Solution for Day17 {
+    const DAY: u32 = 17;
+
+    type Input = Vec<String>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_data(input)
+    }
+
+    fn part_1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_1(input)?.into())
+    }
+
+    fn part_2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_2(input)?.into())
+    }
 }
 
 #[cfg(test)]
@@ -127,19 +285,54 @@ mod tests {
     use super::*;
     use rstest::rstest;
 
-    use crate::utilities::file_utilities::get_file_path;
+    use crate::utilities::input::puzzle_input;
 
     #[rstest]
     #[case(true, 112)]
     #[case(false, 267)]
     fn test_part_1(#[case] is_test: bool, #[case] expected: i64) {
-        assert_eq!(expected, part_1(get_file_path(is_test, 17, None)));
+        let input = parse_data(&puzzle_input(is_test, 17, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_1(&input).unwrap());
     }
 
     #[rstest]
     #[case(true, 848)]
     #[case(false, 1812)]
     fn test_part_2(#[case] is_test: bool, #[case] expected: i64) {
-        assert_eq!(expected, part_2(get_file_path(is_test, 17, None)));
+        let input = parse_data(&puzzle_input(is_test, 17, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_2(&input).unwrap());
+    }
+
+    #[rstest]
+    #[case(1, (13.0, 5.0))]
+    #[case(2, (13.0, 13.0))]
+    fn test_render_first_cycle(#[case] part: u32, #[case] expected_size: (f64, f64)) {
+        let input = parse_data(&puzzle_input(true, 17, None).read().unwrap()).unwrap();
+        let scenes = render(&input, part, true).unwrap();
+        let scene = &scenes[1];
+
+        assert_eq!(CYCLES + 1, scenes.len());
+        assert_eq!(expected_size, (scene.width, scene.height));
+    }
+
+    #[test]
+    fn test_cube_session() {
+        let input = parse_data(&puzzle_input(true, 17, None).read().unwrap()).unwrap();
+        let mut session = CubeSession::new(&input);
+
+        assert_eq!(
+            Some(Ok(String::from("cycle 1, 11 active cubes in 3 dimensions"))),
+            session.execute("step", &[])
+        );
+        assert_eq!(
+            Some(Ok(String::from(
+                "z=-1\n#..\n..#\n.#.\n\nz=0\n#.#\n.##\n.#.\n\nz=1\n#..\n..#\n.#."
+            ))),
+            session.execute("show", &[])
+        );
+        assert_eq!(
+            Some(Ok(String::from("cycle 0, 5 active cubes in 4 dimensions"))),
+            session.execute("dimensions", &["4"])
+        );
     }
 }
@@ -1,9 +1,20 @@
-use std::str;
+use nom::branch::alt;
+use nom::character::complete::{char, satisfy};
+use nom::combinator::{cut, map, value};
+use nom::error::context;
+use nom::multi::many0;
+use nom::sequence::{pair, preceded, terminated};
 
-use crate::utilities::file_utilities::read_lines;
+use crate::error::{parse_lines, OrOverflow, ParseError, SolveError};
+use crate::generate::Generated;
+use crate::observer::{self, Event};
+use crate::repl::Session;
+use crate::solution::{Answer, Solution};
+use crate::utilities::grammar::{parse_all, token, ws, GResult};
+use crate::utilities::random::Rng;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
-enum Character {
+pub enum Character {
     Summation,
     Multiplication,
     OpenParentheses,
@@ -11,37 +22,70 @@ enum Character {
     Number(u64),
 }
 
-fn parse_line(line: &str) -> Vec<Character> {
-    line.chars()
-        .filter_map(|c| match c {
-            ' ' => None,
-            '+' => Some(Character::Summation),
-            '*' => Some(Character::Multiplication),
-            '(' => Some(Character::OpenParentheses),
-            ')' => Some(Character::CloseParentheses),
-            n => n.to_digit(10).map(|n| Character::Number(n as u64)),
-        })
-        .collect()
+// Either a single digit or a whole parenthesised expression, flattened back into characters.
+fn operand(input: &str) -> GResult<'_, Vec<Character>> {
+    let digit = map(ws(satisfy(|c| c.is_ascii_digit())), |c| {
+        vec![Character::Number(u64::from(c as u8 - b'0'))]
+    });
+    let parenthesised = map(
+        preceded(token("("), cut(terminated(expression, token(")")))),
+        |inner| {
+            let mut characters = vec![Character::OpenParentheses];
+            characters.extend(inner);
+            characters.push(Character::CloseParentheses);
+            characters
+        },
+    );
+
+    context("an operand", alt((digit, parenthesised)))(input)
+}
+
+fn operator(input: &str) -> GResult<'_, Character> {
+    ws(alt((
+        value(Character::Summation, char('+')),
+        value(Character::Multiplication, char('*')),
+    )))(input)
+}
+
+// Operands and operators have to alternate, so an operator always needs an operand after it.
+fn expression(input: &str) -> GResult<'_, Vec<Character>> {
+    map(
+        pair(operand, many0(pair(operator, cut(operand)))),
+        |(mut characters, rest)| {
+            for (operator, operand) in rest {
+                characters.push(operator);
+                characters.extend(operand);
+            }
+            characters
+        },
+    )(input)
+}
+
+fn parse_line(line: &str) -> Result<Vec<Character>, ParseError> {
+    parse_all(line, expression)
+}
+
+pub fn parse_data(input: &str) -> Result<Vec<Vec<Character>>, ParseError> {
+    parse_lines(input, parse_line)
 }
 
-fn parse_data(file_path: String) -> Vec<Vec<Character>> {
-    read_lines(file_path)
-        .into_iter()
-        .map(|line| parse_line(line.as_str()))
-        .collect()
+fn malformed(message: String) -> SolveError {
+    SolveError::NoSolution(format!("Malformed expression: {message}"))
 }
 
+type Evaluation = Result<u64, SolveError>;
+
 fn get_number(
-    line: &Vec<Character>,
+    line: &[Character],
     start_index: usize,
     end_index: usize,
-    recursion_function: fn(&Vec<Character>, usize, usize) -> u64,
-) -> (u64, usize) {
-    match line[start_index] {
-        Character::Number(n) => (n, start_index + 1),
-        Character::OpenParentheses => {
+    recursion_function: fn(&[Character], usize, usize) -> Evaluation,
+) -> Result<(u64, usize), SolveError> {
+    match line[..end_index].get(start_index) {
+        Some(Character::Number(n)) => Ok((*n, start_index + 1)),
+        Some(Character::OpenParentheses) => {
             let mut number_of_open_parentheses = 0;
-            let mut parentheses_end_index = 0;
+            let mut parentheses_end_index = None;
 
             for (index, character) in line.iter().enumerate().take(end_index).skip(start_index) {
                 if matches!(character, Character::OpenParentheses) {
@@ -50,70 +94,116 @@ fn get_number(
                     number_of_open_parentheses -= 1;
 
                     if number_of_open_parentheses == 0 {
-                        parentheses_end_index = index;
+                        parentheses_end_index = Some(index);
                         break;
                     }
                 }
             }
 
-            // println!("Parentheses open at {start_index} and close at {parentheses_end_index}");
-            (
-                recursion_function(line, start_index + 1, parentheses_end_index),
-                parentheses_end_index + 1,
-            )
+            let parentheses_end_index = parentheses_end_index.ok_or_else(|| {
+                malformed(format!("the parentheses at {start_index} are never closed"))
+            })?;
+            let value = recursion_function(line, start_index + 1, parentheses_end_index)?;
+            observer::emit(|| Event::GroupEvaluated {
+                start: start_index,
+                end: parentheses_end_index,
+                value,
+            });
+
+            Ok((value, parentheses_end_index + 1))
         }
-        _ => panic!("At index {start_index} I should have a number or parentheses! ({line:?})"),
+        _ => Err(malformed(format!(
+            "expected a number or parentheses at {start_index}"
+        ))),
+    }
+}
+
+// A number can only be followed by a summation or a multiplication.
+fn operator_at(line: &[Character], index: usize) -> Result<Character, SolveError> {
+    match line[index] {
+        operator @ (Character::Summation | Character::Multiplication) => Ok(operator),
+        _ => Err(malformed(format!("expected + or * at {index}"))),
     }
 }
 
-fn recursion_is_fun(line: &Vec<Character>, start_index: usize, end_index: usize) -> u64 {
+fn recursion_is_fun(line: &[Character], start_index: usize, end_index: usize) -> Evaluation {
     // First character should always be an open parentheses, or a number.
-    let (mut result, mut start_index) = get_number(line, start_index, end_index, recursion_is_fun);
+    let (mut result, mut start_index) = get_number(line, start_index, end_index, recursion_is_fun)?;
 
     // Then we can only have an operation, which we use and connect to the next thing until we run out.
     while start_index != end_index {
+        let operator = operator_at(line, start_index)?;
         let (next_number, next_start_index) =
-            get_number(line, start_index + 1, end_index, recursion_is_fun);
+            get_number(line, start_index + 1, end_index, recursion_is_fun)?;
 
-        match line[start_index] {
-            Character::Summation => result += next_number,
-            Character::Multiplication => result *= next_number,
-            _ => panic!("a number can only be followed by a summation or a multiplication!"),
-        };
+        result = match operator {
+            Character::Summation => result.checked_add(next_number),
+            _ => result.checked_mul(next_number),
+        }
+        .or_overflow("evaluating the expression")?;
 
         start_index = next_start_index;
     }
 
-    result
+    Ok(result)
 }
 
-pub fn part_1(file_path: String) -> u64 {
-    let lines = parse_data(file_path);
-    lines
-        .iter()
-        .map(|line| recursion_is_fun(line, 0, line.len()))
-        .sum()
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Precedence {
+    LeftToRight,
+    AdditionFirst,
 }
 
-fn recursion_is_fun_2(line: &Vec<Character>, start_index: usize, end_index: usize) -> u64 {
+// Expressions from the parser are always well formed, hand built ones are checked as they are evaluated.
+pub fn evaluate(expression: &[Character], precedence: Precedence) -> Evaluation {
+    match precedence {
+        Precedence::LeftToRight => recursion_is_fun(expression, 0, expression.len()),
+        Precedence::AdditionFirst => recursion_is_fun_2(expression, 0, expression.len()),
+    }
+}
+
+fn sum_lines(lines: &[Vec<Character>], precedence: Precedence) -> Evaluation {
+    (1..)
+        .zip(lines)
+        .try_fold(0_u64, |total, (line_number, line)| {
+            let value = evaluate(line, precedence)?;
+            observer::emit(|| Event::ExpressionEvaluated {
+                line: line_number,
+                value,
+            });
+            total
+                .checked_add(value)
+                .or_overflow("adding up the expressions")
+        })
+}
+
+pub fn part_1(lines: &[Vec<Character>]) -> Result<u64, SolveError> {
+    sum_lines(lines, Precedence::LeftToRight)
+}
+
+fn recursion_is_fun_2(line: &[Character], start_index: usize, end_index: usize) -> Evaluation {
     let mut factors = vec![];
 
     // First character should always be an open parentheses, or a number.
     let (mut result, mut start_index) =
-        get_number(line, start_index, end_index, recursion_is_fun_2);
+        get_number(line, start_index, end_index, recursion_is_fun_2)?;
 
     // Take care of all pluses first, then multiply all of the multiplications...
     while start_index != end_index {
+        let operator = operator_at(line, start_index)?;
         let (next_number, next_start_index) =
-            get_number(line, start_index + 1, end_index, recursion_is_fun_2);
+            get_number(line, start_index + 1, end_index, recursion_is_fun_2)?;
 
-        match line[start_index] {
-            Character::Summation => result += next_number,
-            Character::Multiplication => {
+        match operator {
+            Character::Summation => {
+                result = result
+                    .checked_add(next_number)
+                    .or_overflow("evaluating the expression")?
+            }
+            _ => {
                 factors.push(result);
                 result = next_number;
             }
-            _ => panic!("a number can only be followed by a summation or a multiplication!"),
         };
 
         start_index = next_start_index;
@@ -121,16 +211,142 @@ fn recursion_is_fun_2(line: &Vec<Character>, start_index: usize, end_index: usiz
 
     factors.push(result);
 
-    // println!("Handling {temp_factors:?}");
-    factors.iter().product()
+    factors
+        .iter()
+        .try_fold(1_u64, |product, factor| product.checked_mul(*factor))
+        .or_overflow("evaluating the expression")
 }
 
-pub fn part_2(file_path: String) -> u64 {
-    let lines = parse_data(file_path);
-    lines
-        .iter()
-        .map(|line| recursion_is_fun_2(line, 0, line.len()))
-        .sum()
+pub fn part_2(lines: &[Vec<Character>]) -> Result<u64, SolveError> {
+    sum_lines(lines, Precedence::AdditionFirst)
+}
+
+// Builds an expression along with its value under both precedences.
+fn random_expression(rng: &mut Rng, depth: usize) -> (String, i128, i128) {
+    let mut text = String::new();
+    let mut left_to_right = 0;
+    let mut factors = vec![];
+    let mut sum = 0;
+
+    for index in 0..rng.range(2..=5) {
+        let (operand, value_1, value_2) = if depth > 0 && rng.chance(1, 4) {
+            let (inner, value_1, value_2) = random_expression(rng, depth - 1);
+            (format!("({inner})"), value_1, value_2)
+        } else {
+            let digit = rng.range(1..=9);
+            (digit.to_string(), digit as i128, digit as i128)
+        };
+
+        if index == 0 {
+            text = operand;
+            left_to_right = value_1;
+            sum = value_2;
+        } else if rng.chance(1, 2) {
+            text = format!("{text} + {operand}");
+            left_to_right += value_1;
+            sum += value_2;
+        } else {
+            text = format!("{text} * {operand}");
+            left_to_right *= value_1;
+            factors.push(sum);
+            sum = value_2;
+        }
+    }
+
+    factors.push(sum);
+    (text, left_to_right, factors.into_iter().product())
+}
+
+pub fn generate(rng: &mut Rng, size: usize) -> Result<Generated, String> {
+    let mut input = String::new();
+    let (mut total_1, mut total_2) = (0, 0);
+
+    for _ in 0..size {
+        let (expression, value_1, value_2) = random_expression(rng, 2);
+        input.push_str(&expression);
+        input.push('\n');
+        total_1 += value_1;
+        total_2 += value_2;
+    }
+
+    Ok(Generated::new(
+        input,
+        Some(Answer::from(total_1)),
+        Some(Answer::from(total_2)),
+    ))
+}
+
+// Evaluates typed expressions, or lines of the input, under both precedences.
+pub struct ExpressionSession {
+    lines: Vec<Vec<Character>>,
+}
+
+impl ExpressionSession {
+    pub fn new(lines: &[Vec<Character>]) -> Self {
+        Self {
+            lines: lines.to_vec(),
+        }
+    }
+}
+
+fn describe(expression: &[Character]) -> Result<String, String> {
+    let value = |precedence| evaluate(expression, precedence).map_err(|error| error.to_string());
+
+    Ok(format!(
+        "left to right {}, addition first {}",
+        value(Precedence::LeftToRight)?,
+        value(Precedence::AdditionFirst)?
+    ))
+}
+
+impl Session for ExpressionSession {
+    fn commands(&self) -> Vec<(&'static str, &'static str)> {
+        vec![
+            (
+                "eval <expression>",
+                "Evaluate an expression under both precedences",
+            ),
+            ("line <number>", "Evaluate one line of the input"),
+        ]
+    }
+
+    fn execute(&mut self, command: &str, arguments: &[&str]) -> Option<Result<String, String>> {
+        Some(match command {
+            "eval" => parse_line(&arguments.join(" "))
+                .map_err(|error| error.to_string())
+                .and_then(|expression| describe(&expression)),
+            "line" => match arguments {
+                [line] => line
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|line| self.lines.get(line.checked_sub(1)?))
+                    .ok_or_else(|| format!("Expected a line between 1 and {}", self.lines.len()))
+                    .and_then(|expression| describe(expression)),
+                _ => Err(String::from("Usage: line <number>")),
+            },
+            _ => return None,
+        })
+    }
+}
+
+pub struct Day18;
+
+impl Solution for Day18 {
+    const DAY: u32 = 18;
+
+    type Input = Vec<Vec<Character>>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_data(input)
+    }
+
+    fn part_1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_1(input)?.into())
+    }
+
+    fn part_2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_2(input)?.into())
+    }
 }
 
 #[cfg(test)]
@@ -138,7 +354,7 @@ mod tests {
     use super::*;
     use rstest::rstest;
 
-    use crate::utilities::file_utilities::get_file_path;
+    use crate::utilities::input::puzzle_input;
 
     #[rstest]
     #[case("1", 1)]
@@ -150,8 +366,11 @@ mod tests {
     #[case("((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2", 13632)]
     fn test_random_cases_part_1(#[case] expression: String, #[case] expected: u64) {
         let as_str = expression.as_str();
-        let as_vector = parse_line(&as_str);
-        assert_eq!(expected, recursion_is_fun(&as_vector, 0, as_vector.len()));
+        let as_vector = parse_line(as_str).unwrap();
+        assert_eq!(
+            Ok(expected),
+            recursion_is_fun(&as_vector, 0, as_vector.len())
+        );
     }
 
     #[rstest]
@@ -166,21 +385,87 @@ mod tests {
     #[case("((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2", 23340)]
     fn test_random_cases_part_2(#[case] expression: String, #[case] expected: u64) {
         let as_str = expression.as_str();
-        let as_vector = parse_line(&as_str);
-        assert_eq!(expected, recursion_is_fun_2(&as_vector, 0, as_vector.len()));
+        let as_vector = parse_line(as_str).unwrap();
+        assert_eq!(
+            Ok(expected),
+            recursion_is_fun_2(&as_vector, 0, as_vector.len())
+        );
+    }
+
+    #[rstest]
+    #[case("2 * 3 + (4 * 5)", Precedence::LeftToRight, 26)]
+    #[case("2 * 3 + (4 * 5)", Precedence::AdditionFirst, 46)]
+    fn test_evaluate(
+        #[case] expression: &str,
+        #[case] precedence: Precedence,
+        #[case] expected: u64,
+    ) {
+        assert_eq!(
+            Ok(expected),
+            evaluate(&parse_line(expression).unwrap(), precedence)
+        );
+    }
+
+    #[rstest]
+    #[case(vec![], "expected a number or parentheses at 0")]
+    #[case(vec![Character::Summation, Character::Number(2)], "expected a number or parentheses at 0")]
+    #[case(vec![Character::Number(2), Character::Number(3)], "expected + or * at 1")]
+    #[case(vec![Character::Number(2), Character::Multiplication], "expected a number or parentheses at 2")]
+    #[case(vec![Character::OpenParentheses, Character::Number(2)], "the parentheses at 0 are never closed")]
+    fn test_evaluate_malformed(#[case] expression: Vec<Character>, #[case] message: &str) {
+        for precedence in [Precedence::LeftToRight, Precedence::AdditionFirst] {
+            assert_eq!(
+                Err(SolveError::NoSolution(format!(
+                    "Malformed expression: {message}"
+                ))),
+                evaluate(&expression, precedence)
+            );
+        }
+    }
+
+    #[rstest]
+    #[case("2 * x", 5)]
+    #[case("2 3", 3)]
+    #[case("(2 * 3", 7)]
+    #[case("2 * 3)", 6)]
+    #[case("2 *", 4)]
+    fn test_parse_line_rejects_malformed(#[case] expression: &str, #[case] column: usize) {
+        assert_eq!(column, parse_line(expression).unwrap_err().column);
     }
 
     #[rstest]
     #[case(true, 71+51+26+437+12240+13632)]
     #[case(false, 11297104473091)]
     fn test_part_1(#[case] is_test: bool, #[case] expected: u64) {
-        assert_eq!(expected, part_1(get_file_path(is_test, 18, None)));
+        let lines = parse_data(&puzzle_input(is_test, 18, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_1(&lines).unwrap());
     }
 
     #[rstest]
     #[case(true, 231+51+46+1445+669060+23340)]
     #[case(false, 185348874183674)]
     fn test_part_2(#[case] is_test: bool, #[case] expected: u64) {
-        assert_eq!(expected, part_2(get_file_path(is_test, 18, None)));
+        let lines = parse_data(&puzzle_input(is_test, 18, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_2(&lines).unwrap());
+    }
+
+    #[rstest]
+    #[case("eval", "2 * 3 + (4 * 5)", Ok("left to right 26, addition first 46"))]
+    #[case("line", "2", Ok("left to right 51, addition first 51"))]
+    #[case("line", "7", Err("Expected a line between 1 and 6"))]
+    fn test_expression_session(
+        #[case] command: &str,
+        #[case] arguments: &str,
+        #[case] expected: Result<&str, &str>,
+    ) {
+        let lines = parse_data(&puzzle_input(true, 18, None).read().unwrap()).unwrap();
+        let arguments: Vec<&str> = arguments.split(' ').collect();
+
+        assert_eq!(
+            expected.map(String::from).map_err(String::from),
+            ExpressionSession::new(&lines)
+                .execute(command, &arguments)
+                .unwrap()
+        );
     }
 }
@@ -1,65 +1,81 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeSet, HashMap, VecDeque};
 
-use crate::utilities::file_utilities::read_lines;
+use nom::branch::alt;
+use nom::character::complete::alpha1;
+use nom::combinator::{cut, eof, map, value};
+use nom::multi::many1;
+use nom::sequence::separated_pair;
+
+use crate::error::{parse_lines, ParseError, SolveError};
+use crate::generate::Generated;
+use crate::solution::{Answer, Solution};
+use crate::utilities::grammar::{number, parse_all, token, ws, GResult};
+use crate::utilities::random::Rng;
 
 #[derive(Clone, Debug, Eq, PartialEq)]
-enum RuleType {
+pub enum RuleType {
     OneOption(Vec<usize>),
     TwoOptions(Vec<usize>, Vec<usize>),
     A,
     B,
 }
 
-type Rule = (usize, RuleType);
-type Message = VecDeque<char>;
-
-fn parse_sub_rule_tuple(sub_rule_str: &str) -> Vec<usize> {
-    sub_rule_str
-        .split(' ')
-        .map(str::trim)
-        .map(|x| x.parse::<usize>().unwrap())
-        .collect()
-}
-
-fn parse_line(line: &str) -> (Option<Rule>, Option<Message>) {
-    if line.contains(':') {
-        let split_by_colon: Vec<&str> = line.split(':').map(str::trim).collect();
-        let id = split_by_colon[0].trim().parse::<usize>().unwrap();
-        let other_part = split_by_colon[1].trim();
-
-        return if other_part == "\"a\"" {
-            (Some((id, RuleType::A)), None)
-        } else if other_part == "\"b\"" {
-            (Some((id, RuleType::B)), None)
-        } else if other_part.contains('|') {
-            let split_by_or: Vec<Vec<usize>> = other_part
-                .split('|')
-                .map(str::trim)
-                .map(parse_sub_rule_tuple)
-                .collect();
-            (
-                Some((
-                    id,
-                    RuleType::TwoOptions(split_by_or[0].clone(), split_by_or[1].clone()),
-                )),
-                None,
-            )
-        } else {
-            (
-                Some((id, RuleType::OneOption(parse_sub_rule_tuple(other_part)))),
-                None,
-            )
-        };
-    }
+pub type Rule = (usize, RuleType);
+pub type Message = VecDeque<char>;
+pub type Entry = (Option<Rule>, Option<Message>);
 
-    (None, Some(line.chars().collect()))
+fn sub_rules(input: &str) -> GResult<'_, Vec<usize>> {
+    many1(ws(number))(input)
 }
 
-fn parse_data(file_path: String) -> Vec<(Option<Rule>, Option<Message>)> {
-    read_lines(file_path)
-        .into_iter()
-        .map(|line| parse_line(line.as_str()))
-        .collect()
+// 8: 42 | 42 8
+fn rule(input: &str) -> GResult<'_, Rule> {
+    let rule_type = alt((
+        value(RuleType::A, token("\"a\"")),
+        value(RuleType::B, token("\"b\"")),
+        map(
+            separated_pair(sub_rules, token("|"), cut(sub_rules)),
+            |(option_1, option_2)| RuleType::TwoOptions(option_1, option_2),
+        ),
+        map(sub_rules, RuleType::OneOption),
+    ));
+
+    separated_pair(ws(number), token(":"), cut(rule_type))(input)
+}
+
+// The blank line between the rules and the messages carries no data.
+fn entry(input: &str) -> GResult<'_, Entry> {
+    alt((
+        map(rule, |rule| (Some(rule), None)),
+        map(alpha1, |message: &str| {
+            (None, Some(message.chars().collect()))
+        }),
+        value((None, None), ws(eof)),
+    ))(input)
+}
+
+fn parse_line(line: &str) -> Result<Entry, ParseError> {
+    parse_all(line, entry)
+}
+
+pub fn parse_data(input: &str) -> Result<Vec<Entry>, ParseError> {
+    parse_lines(input, parse_line)
+}
+
+fn check_rules(all_rules: &HashMap<usize, RuleType>) -> Result<(), SolveError> {
+    let referenced = all_rules.values().flat_map(|rule_type| match rule_type {
+        RuleType::OneOption(rules) => rules.clone(),
+        RuleType::TwoOptions(rules_1, rules_2) => [rules_1.clone(), rules_2.clone()].concat(),
+        RuleType::A | RuleType::B => vec![],
+    });
+
+    match std::iter::once(0)
+        .chain(referenced)
+        .find(|rule_id| !all_rules.contains_key(rule_id))
+    {
+        Some(rule_id) => Err(SolveError::NoSolution(format!("Rule {rule_id} is missing"))),
+        None => Ok(()),
+    }
 }
 
 fn iterate_over_rules(
@@ -88,16 +104,14 @@ fn rule_type_matched(
     rule_id: usize,
     all_rules: &HashMap<usize, RuleType>,
 ) -> Vec<VecDeque<char>> {
-    return match all_rules.get(&rule_id).unwrap() {
+    // check_rules has made sure every referenced rule exists.
+    match &all_rules[&rule_id] {
         RuleType::OneOption(rules) => iterate_over_rules(message, rules, all_rules),
         RuleType::TwoOptions(rules_1, rules_2) => {
             let remainders_1 = iterate_over_rules(message, rules_1, all_rules);
             let remainders_2 = iterate_over_rules(message, rules_2, all_rules);
 
-            remainders_1
-                .into_iter()
-                .chain(remainders_2.into_iter())
-                .collect()
+            remainders_1.into_iter().chain(remainders_2).collect()
         }
         RuleType::A => {
             if message.is_empty() || message[0] != 'a' {
@@ -115,12 +129,10 @@ fn rule_type_matched(
             let message = message.clone();
             vec![message.into_iter().skip(1).collect()]
         }
-    };
+    }
 }
 
-pub fn part_1(file_path: String) -> i64 {
-    let data = parse_data(file_path);
-
+pub fn part_1(data: &[Entry]) -> Result<i64, SolveError> {
     let rules: HashMap<usize, RuleType> = data
         .iter()
         .cloned()
@@ -134,18 +146,20 @@ pub fn part_1(file_path: String) -> i64 {
         .filter_map(|(_, message)| message)
         .collect();
 
-    messages
+    check_rules(&rules)?;
+
+    let matching = messages
         .into_iter()
         .filter(|message| {
             let result = rule_type_matched(message, 0, &rules);
-            return !result.is_empty() && result.iter().any(|r| r.is_empty());
+            !result.is_empty() && result.iter().any(|r| r.is_empty())
         })
-        .count() as i64
-}
+        .count();
 
-pub fn part_2(file_path: String) -> i64 {
-    let data = parse_data(file_path);
+    Ok(matching as i64)
+}
 
+pub fn part_2(data: &[Entry]) -> Result<i64, SolveError> {
     let mut rules: HashMap<usize, RuleType> = data
         .iter()
         .cloned()
@@ -165,13 +179,214 @@ pub fn part_2(file_path: String) -> i64 {
         RuleType::TwoOptions(vec![42, 31], vec![42, 11, 31]),
     );
 
-    messages
+    check_rules(&rules)?;
+
+    let matching = messages
         .into_iter()
         .filter(|message| {
             let result = rule_type_matched(message, 0, &rules);
-            return !result.is_empty() && result.iter().any(|r| r.is_empty());
+            !result.is_empty() && result.iter().any(|r| r.is_empty())
         })
-        .count() as i64
+        .count();
+
+    Ok(matching as i64)
+}
+
+const CHUNK: usize = 8;
+
+struct RuleBuilder {
+    rules: Vec<Rule>,
+    free_ids: Vec<usize>,
+    letters: Vec<(usize, BTreeSet<String>)>,
+}
+
+impl RuleBuilder {
+    fn add(&mut self, rule_type: RuleType) -> usize {
+        let id = self.free_ids.pop().expect("Ran out of rule ids");
+        self.rules.push((id, rule_type));
+        id
+    }
+
+    // A rule matching exactly the strings of `length` letters in the returned language.
+    fn build(&mut self, rng: &mut Rng, length: usize) -> (usize, BTreeSet<String>) {
+        if length == 1 {
+            return rng.choose(&self.letters).clone();
+        }
+
+        let mut options = vec![];
+        let mut language = BTreeSet::new();
+
+        for _ in 0..rng.range(1..=2) {
+            let split = rng.range(1..=length as i64 - 1) as usize;
+            let (first, first_language) = self.build(rng, split);
+            let (second, second_language) = self.build(rng, length - split);
+
+            options.push(vec![first, second]);
+            for prefix in first_language.iter() {
+                for suffix in second_language.iter() {
+                    language.insert(format!("{prefix}{suffix}"));
+                }
+            }
+        }
+
+        let rule_type = match options.len() {
+            1 => RuleType::OneOption(options.remove(0)),
+            _ => RuleType::TwoOptions(options.remove(0), options.remove(0)),
+        };
+
+        (self.add(rule_type), language)
+    }
+}
+
+// Whether a message matches rule 0 before and after the loops are added, which for these rules means 42 42 31, or 42 repeated m times then 31 repeated n times with m > n >= 1.
+fn classify(
+    message: &str,
+    language_42: &BTreeSet<String>,
+    language_31: &BTreeSet<String>,
+) -> (bool, bool) {
+    if !message.len().is_multiple_of(CHUNK) {
+        return (false, false);
+    }
+
+    let chunks: Vec<&str> = (0..message.len())
+        .step_by(CHUNK)
+        .map(|start| &message[start..start + CHUNK])
+        .collect();
+    let fronts = chunks
+        .iter()
+        .take_while(|chunk| language_42.contains(**chunk))
+        .count();
+    let backs = chunks[fronts..]
+        .iter()
+        .take_while(|chunk| language_31.contains(**chunk))
+        .count();
+
+    if fronts + backs != chunks.len() {
+        return (false, false);
+    }
+
+    (fronts == 2 && backs == 1, backs >= 1 && fronts > backs)
+}
+
+// Rules 42 and 31 match 8 letters each and differ in the first one, like the real inputs, so a message can be checked chunk by chunk.
+pub fn generate(rng: &mut Rng, size: usize) -> Result<Generated, String> {
+    let mut free_ids: Vec<usize> = (1..200)
+        .filter(|id| ![8, 11, 31, 42].contains(id))
+        .collect();
+    rng.shuffle(&mut free_ids);
+
+    let mut builder = RuleBuilder {
+        rules: vec![],
+        free_ids,
+        letters: vec![],
+    };
+
+    let a = builder.add(RuleType::A);
+    let b = builder.add(RuleType::B);
+    let either = builder.add(RuleType::TwoOptions(vec![a], vec![b]));
+    builder.letters = vec![
+        (a, BTreeSet::from([String::from("a")])),
+        (b, BTreeSet::from([String::from("b")])),
+        (
+            either,
+            BTreeSet::from([String::from("a"), String::from("b")]),
+        ),
+    ];
+
+    let (rest_42, language_rest_42) = builder.build(rng, CHUNK - 1);
+    let (rest_31, language_rest_31) = builder.build(rng, CHUNK - 1);
+    let language_42: BTreeSet<String> = language_rest_42
+        .iter()
+        .map(|rest| format!("a{rest}"))
+        .collect();
+    let language_31: BTreeSet<String> = language_rest_31
+        .iter()
+        .map(|rest| format!("b{rest}"))
+        .collect();
+
+    builder.rules.extend([
+        (0, RuleType::OneOption(vec![8, 11])),
+        (8, RuleType::OneOption(vec![42])),
+        (11, RuleType::OneOption(vec![42, 31])),
+        (42, RuleType::OneOption(vec![a, rest_42])),
+        (31, RuleType::OneOption(vec![b, rest_31])),
+    ]);
+
+    let choices_42: Vec<String> = language_42.iter().cloned().collect();
+    let choices_31: Vec<String> = language_31.iter().cloned().collect();
+    let mut messages = vec![];
+    let (mut matching_1, mut matching_2) = (0, 0);
+
+    for _ in 0..size {
+        let message = match rng.index(4) {
+            0 => (0..rng.range(2..=6) * CHUNK as i64)
+                .map(|_| if rng.chance(1, 2) { 'a' } else { 'b' })
+                .collect(),
+            _ => {
+                let fronts = rng.range(1..=5);
+                let backs = rng.range(1..=fronts.max(2));
+                let mut message = String::new();
+                (0..fronts).for_each(|_| message.push_str(rng.choose::<String>(&choices_42)));
+                (0..backs).for_each(|_| message.push_str(rng.choose::<String>(&choices_31)));
+                message
+            }
+        };
+
+        let (matches_1, matches_2) = classify(&message, &language_42, &language_31);
+        matching_1 += i64::from(matches_1);
+        matching_2 += i64::from(matches_2);
+        messages.push(message);
+    }
+
+    let mut rules: Vec<String> = builder
+        .rules
+        .iter()
+        .map(|(id, rule_type)| {
+            let sequence = |rules: &Vec<usize>| {
+                rules
+                    .iter()
+                    .map(usize::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            };
+
+            match rule_type {
+                RuleType::A => format!("{id}: \"a\""),
+                RuleType::B => format!("{id}: \"b\""),
+                RuleType::OneOption(rules) => format!("{id}: {}", sequence(rules)),
+                RuleType::TwoOptions(first, second) => {
+                    format!("{id}: {} | {}", sequence(first), sequence(second))
+                }
+            }
+        })
+        .collect();
+    rng.shuffle(&mut rules);
+
+    Ok(Generated::new(
+        format!("{}\n\n{}\n", rules.join("\n"), messages.join("\n")),
+        Some(Answer::from(matching_1)),
+        Some(Answer::from(matching_2)),
+    ))
+}
+
+pub struct Day19;
+
+impl Solution for Day19 {
+    const DAY: u32 = 19;
+
+    type Input = Vec<Entry>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_data(input)
+    }
+
+    fn part_1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_1(input)?.into())
+    }
+
+    fn part_2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_2(input)?.into())
+    }
 }
 
 #[cfg(test)]
@@ -179,19 +394,40 @@ mod tests {
     use super::*;
     use rstest::rstest;
 
-    use crate::utilities::file_utilities::get_file_path;
+    use crate::utilities::input::puzzle_input;
+
+    #[rstest]
+    #[case("8: 42 | 42 8", (Some((8, RuleType::TwoOptions(vec![42], vec![42, 8]))), None))]
+    #[case("4: \"a\"", (Some((4, RuleType::A)), None))]
+    #[case("0: 4 1 5", (Some((0, RuleType::OneOption(vec![4, 1, 5]))), None))]
+    #[case("abab", (None, Some("abab".chars().collect())))]
+    #[case("", (None, None))]
+    fn test_parse_line(#[case] line: &str, #[case] expected: Entry) {
+        assert_eq!(expected, parse_line(line).unwrap());
+    }
+
+    #[rstest]
+    #[case("8 42 | 42 8", 3)]
+    #[case("8: 42 | x", 9)]
+    #[case("8: 1 | 2 | 3", 10)]
+    #[case("ab1", 3)]
+    fn test_parse_line_reports_span(#[case] line: &str, #[case] column: usize) {
+        assert_eq!(column, parse_line(line).unwrap_err().column);
+    }
 
     #[rstest]
     #[case(true, 3)]
     #[case(false, 104)]
     fn test_part_1(#[case] is_test: bool, #[case] expected: i64) {
-        assert_eq!(expected, part_1(get_file_path(is_test, 19, None)));
+        let data = parse_data(&puzzle_input(is_test, 19, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_1(&data).unwrap());
     }
 
     #[rstest]
     #[case(true, 12)]
     #[case(false, 314)]
     fn test_part_2(#[case] is_test: bool, #[case] expected: i64) {
-        assert_eq!(expected, part_2(get_file_path(is_test, 19, None)));
+        let data = parse_data(&puzzle_input(is_test, 19, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_2(&data).unwrap());
     }
 }
@@ -1,71 +1,484 @@
 use std::str;
 
-use crate::utilities::file_utilities::read_lines;
+use nom::bytes::complete::take_while1;
+use nom::combinator::{cut, map, opt};
+use nom::error::context;
+use nom::sequence::{preceded, tuple};
+use regex::Regex;
 
-struct PolicyPassword {
-    first_number: usize,
-    second_number: usize,
-    character: String,
-    password: String,
+use crate::error::{parse_lines, ParseError, SolveError};
+use crate::generate::Generated;
+use crate::solution::{Answer, Solution};
+use crate::utilities::file_utilities::lines;
+use crate::utilities::grammar::{number, parse_all, token, ws, GResult};
+use crate::utilities::random::Rng;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PolicyPassword {
+    pub first_number: usize,
+    pub second_number: usize,
+    pub character: String,
+    pub password: String,
 }
 
-fn parse_line(line: &str) -> PolicyPassword {
-    // 2-9 c: ccccccccc
-    let password_policy: Vec<&str> = line.split(':').map(str::trim).collect();
-    let policy = password_policy[0];
-    let password = password_policy[1];
+// 2-9 c: ccccccccc, or 3 ab: cabab with one number and a longer token.
+fn policy_password(input: &str) -> GResult<'_, PolicyPassword> {
+    map(
+        tuple((
+            ws(number),
+            opt(preceded(token("-"), cut(ws(number)))),
+            ws(context(
+                "a letter or token",
+                take_while1(char::is_alphanumeric),
+            )),
+            token(":"),
+            ws(context(
+                "a password",
+                take_while1(|c: char| !c.is_whitespace()),
+            )),
+        )),
+        |(first_number, second_number, character, _, password)| PolicyPassword {
+            first_number,
+            second_number: second_number.unwrap_or(first_number),
+            character: String::from(character),
+            password: String::from(password),
+        },
+    )(input)
+}
 
-    let numbers_character: Vec<&str> = policy.split(' ').map(str::trim).collect();
-    let numbers = numbers_character[0];
-    let character = numbers_character[1];
+fn parse_line(line: &str) -> Result<PolicyPassword, ParseError> {
+    let entry = parse_all(line, policy_password)?;
 
-    let number_split: Vec<&str> = numbers.split('-').map(str::trim).collect();
-    let first_number = number_split[0];
-    let second_number = number_split[1];
+    if entry.first_number > entry.second_number {
+        return Err(ParseError::at(
+            line,
+            line.trim_start(),
+            format!(
+                "The range {}-{} runs backwards",
+                entry.first_number, entry.second_number
+            ),
+        ));
+    }
+
+    Ok(entry)
+}
 
-    PolicyPassword {
-        first_number: first_number.parse::<usize>().unwrap(),
-        second_number: second_number.parse::<usize>().unwrap(),
-        character: String::from(character),
-        password: String::from(password),
+// Blank lines and lines starting with # hold no password.
+fn database_line(line: &str) -> Result<Option<PolicyPassword>, ParseError> {
+    let trimmed = line.trim();
+    if trimmed.is_empty() || trimmed.starts_with('#') {
+        return Ok(None);
     }
+
+    parse_line(line).map(Some)
 }
 
-fn parse_data(file_path: String) -> Vec<PolicyPassword> {
-    read_lines(file_path)
+pub fn parse_data(input: &str) -> Result<Vec<PolicyPassword>, ParseError> {
+    Ok(parse_lines(input, database_line)?
         .into_iter()
-        .map(|line| parse_line(line.as_str()))
-        .collect()
+        .flatten()
+        .collect())
 }
 
-pub fn part_1(file_path: String) -> i32 {
-    parse_data(file_path)
-        .iter()
-        .filter(|password_policy| {
-            let matches = password_policy
-                .password
-                .matches(&password_policy.character)
-                .count();
+// Every password that could be read with its line number, and a problem for each line that could not.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Lint {
+    pub entries: Vec<(usize, PolicyPassword)>,
+    pub problems: Vec<ParseError>,
+}
+
+pub fn lint(input: &str) -> Lint {
+    let mut lint = Lint::default();
+
+    for (number, line) in lines(input) {
+        match database_line(line) {
+            Ok(Some(entry)) => lint.entries.push((number, entry)),
+            Ok(None) => {}
+            Err(error) => lint.problems.push(error.with_line(number)),
+        }
+    }
+
+    lint
+}
+
+impl Lint {
+    pub fn report(&self) -> String {
+        let mut report: String = self
+            .problems
+            .iter()
+            .map(|problem| format!("{problem}\n"))
+            .collect();
+        report.push_str(&format!(
+            "{} password(s) read, {} line(s) with problems\n",
+            self.entries.len(),
+            self.problems.len()
+        ));
+
+        report
+    }
+}
+
+pub trait Policy {
+    fn name(&self) -> String;
+
+    // Why the password breaks the policy, or `None` when it keeps to it.
+    fn violation(&self, entry: &PolicyPassword) -> Option<String>;
+}
+
+// The letter appears between the two numbers of times, inclusive.
+pub struct OccurrenceCount;
+
+impl Policy for OccurrenceCount {
+    fn name(&self) -> String {
+        String::from("count")
+    }
+
+    fn violation(&self, entry: &PolicyPassword) -> Option<String> {
+        let matches = entry.password.matches(&entry.character).count();
+
+        (!(entry.first_number..=entry.second_number).contains(&matches)).then(|| {
+            format!(
+                "{} appears {matches} time(s), expected {} to {}",
+                entry.character, entry.first_number, entry.second_number
+            )
+        })
+    }
+}
+
+// The letter is at exactly one of the two positions, counted in characters from 1. A policy with a single number, like `3 c`, names one position and the letter has to be there.
+pub struct ExactlyOnePosition;
+
+// Positions past the end of the password hold nothing.
+fn holds_at(password: &str, position: usize, character: &str) -> bool {
+    position
+        .checked_sub(1)
+        .and_then(|skip| password.char_indices().nth(skip))
+        .is_some_and(|(offset, _)| password[offset..].starts_with(character))
+}
+
+impl Policy for ExactlyOnePosition {
+    fn name(&self) -> String {
+        String::from("positions")
+    }
+
+    fn violation(&self, entry: &PolicyPassword) -> Option<String> {
+        let (first, second) = (entry.first_number, entry.second_number);
+        let character = &entry.character;
+
+        if first == second {
+            return (!holds_at(&entry.password, first, character))
+                .then(|| format!("position {first} does not hold {character}"));
+        }
 
-            password_policy.first_number <= matches && matches <= password_policy.second_number
+        match (
+            holds_at(&entry.password, first, character),
+            holds_at(&entry.password, second, character),
+        ) {
+            (true, true) => Some(format!(
+                "positions {first} and {second} both hold {character}"
+            )),
+            (false, false) => Some(format!(
+                "neither position {first} nor {second} holds {character}"
+            )),
+            _ => None,
+        }
+    }
+}
+
+pub struct MatchesPattern(pub Regex);
+
+impl Policy for MatchesPattern {
+    fn name(&self) -> String {
+        format!("regex:{}", self.0)
+    }
+
+    fn violation(&self, entry: &PolicyPassword) -> Option<String> {
+        (!self.0.is_match(&entry.password)).then(|| format!("does not match {}", self.0))
+    }
+}
+
+pub struct MinimumLength(pub usize);
+
+impl Policy for MinimumLength {
+    fn name(&self) -> String {
+        format!("min-length:{}", self.0)
+    }
+
+    fn violation(&self, entry: &PolicyPassword) -> Option<String> {
+        let length = entry.password.chars().count();
+
+        (length < self.0).then(|| {
+            format!(
+                "is {length} character(s) long, expected at least {}",
+                self.0
+            )
         })
-        .count() as i32
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CharacterClass {
+    Lowercase,
+    Uppercase,
+    Digit,
+    Symbol,
+}
+
+impl CharacterClass {
+    fn contains(&self, character: char) -> bool {
+        match self {
+            CharacterClass::Lowercase => character.is_lowercase(),
+            CharacterClass::Uppercase => character.is_uppercase(),
+            CharacterClass::Digit => character.is_numeric(),
+            CharacterClass::Symbol => !character.is_alphanumeric(),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            CharacterClass::Lowercase => "lower",
+            CharacterClass::Uppercase => "upper",
+            CharacterClass::Digit => "digit",
+            CharacterClass::Symbol => "symbol",
+        }
+    }
+}
+
+impl str::FromStr for CharacterClass {
+    type Err = String;
+
+    fn from_str(class: &str) -> Result<Self, Self::Err> {
+        match class {
+            "lower" => Ok(CharacterClass::Lowercase),
+            "upper" => Ok(CharacterClass::Uppercase),
+            "digit" => Ok(CharacterClass::Digit),
+            "symbol" => Ok(CharacterClass::Symbol),
+            _ => Err(format!(
+                "Unknown character class {class}, expected lower, upper, digit or symbol"
+            )),
+        }
+    }
+}
+
+// At least one character from each class.
+pub struct RequiredClasses(pub Vec<CharacterClass>);
+
+impl Policy for RequiredClasses {
+    fn name(&self) -> String {
+        let classes: Vec<&str> = self.0.iter().map(CharacterClass::name).collect();
+        format!("classes:{}", classes.join(","))
+    }
+
+    fn violation(&self, entry: &PolicyPassword) -> Option<String> {
+        let missing: Vec<&str> = self
+            .0
+            .iter()
+            .filter(|class| !entry.password.chars().any(|c| class.contains(c)))
+            .map(CharacterClass::name)
+            .collect();
+
+        (!missing.is_empty()).then(|| format!("has no {} character", missing.join(" or ")))
+    }
+}
+
+pub struct ForbiddenSubstrings(pub Vec<String>);
+
+impl Policy for ForbiddenSubstrings {
+    fn name(&self) -> String {
+        format!("forbid:{}", self.0.join(","))
+    }
+
+    fn violation(&self, entry: &PolicyPassword) -> Option<String> {
+        let found: Vec<&str> = self
+            .0
+            .iter()
+            .filter(|forbidden| entry.password.contains(forbidden.as_str()))
+            .map(String::as_str)
+            .collect();
+
+        (!found.is_empty()).then(|| format!("contains {}", found.join(" and ")))
+    }
+}
+
+const POLICIES: &str =
+    "count, positions, regex:<pattern>, min-length:<length>, classes:<class,...> or forbid:<text,...>";
+
+// How policies are named on the command line, as `count` or `min-length:12`.
+pub fn parse_policy(policy: &str) -> Result<Box<dyn Policy>, String> {
+    let (name, argument) = match policy.split_once(':') {
+        Some((name, argument)) => (name, Some(argument)),
+        None => (policy, None),
+    };
+    let list = |argument: &str| -> Vec<String> {
+        argument
+            .split(',')
+            .filter(|item| !item.is_empty())
+            .map(String::from)
+            .collect()
+    };
+
+    match (name, argument) {
+        ("count", None) => Ok(Box::new(OccurrenceCount)),
+        ("positions", None) => Ok(Box::new(ExactlyOnePosition)),
+        ("regex", Some(pattern)) => Regex::new(pattern)
+            .map(|regex| Box::new(MatchesPattern(regex)) as Box<dyn Policy>)
+            .map_err(|error| format!("Invalid pattern {pattern}: {error}")),
+        ("min-length", Some(length)) => length
+            .parse()
+            .map(|length| Box::new(MinimumLength(length)) as Box<dyn Policy>)
+            .map_err(|_| format!("min-length expects a number, got {length}")),
+        ("classes", Some(classes)) if !list(classes).is_empty() => Ok(Box::new(RequiredClasses(
+            list(classes)
+                .iter()
+                .map(|class| class.parse())
+                .collect::<Result<_, _>>()?,
+        ))),
+        ("forbid", Some(forbidden)) if !list(forbidden).is_empty() => {
+            Ok(Box::new(ForbiddenSubstrings(list(forbidden))))
+        }
+        _ => Err(format!("Unknown policy {policy}, expected {POLICIES}")),
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Verdict {
+    // Counted from 1, like the lines of the database.
+    pub line: usize,
+    pub password: String,
+    pub violations: Vec<String>,
 }
 
-pub fn part_2(file_path: String) -> i32 {
-    parse_data(file_path)
+impl Verdict {
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+// A password is valid when it keeps to every policy.
+pub fn verdicts(entries: &[(usize, PolicyPassword)], policies: &[Box<dyn Policy>]) -> Vec<Verdict> {
+    entries
         .iter()
-        .filter(|password_policy| {
-            let first_equal = password_policy.password
-                [password_policy.first_number - 1..=password_policy.first_number - 1]
-                == password_policy.character;
-            let second_equal = password_policy.password
-                [password_policy.second_number - 1..=password_policy.second_number - 1]
-                == password_policy.character;
-
-            (first_equal && !second_equal) || (!first_equal && second_equal)
+        .map(|(line, entry)| Verdict {
+            line: *line,
+            password: entry.password.clone(),
+            violations: policies
+                .iter()
+                .filter_map(|policy| policy.violation(entry))
+                .collect(),
         })
-        .count() as i32
+        .collect()
+}
+
+pub fn report(verdicts: &[Verdict]) -> String {
+    let mut report = String::new();
+
+    for verdict in verdicts {
+        if verdict.is_valid() {
+            report.push_str(&format!(
+                "line {}: valid {}\n",
+                verdict.line, verdict.password
+            ));
+        } else {
+            report.push_str(&format!(
+                "line {}: invalid {}: {}\n",
+                verdict.line,
+                verdict.password,
+                verdict.violations.join("; ")
+            ));
+        }
+    }
+
+    let valid = verdicts.iter().filter(|verdict| verdict.is_valid()).count();
+    report.push_str(&format!(
+        "{valid} of {} password(s) are valid\n",
+        verdicts.len()
+    ));
+
+    report
+}
+
+fn count_valid(password_policies: &[PolicyPassword], policy: &dyn Policy) -> usize {
+    password_policies
+        .iter()
+        .filter(|password_policy| policy.violation(password_policy).is_none())
+        .count()
+}
+
+pub fn part_1(password_policies: &[PolicyPassword]) -> Result<usize, SolveError> {
+    Ok(count_valid(password_policies, &OccurrenceCount))
+}
+
+pub fn part_2(password_policies: &[PolicyPassword]) -> Result<usize, SolveError> {
+    Ok(count_valid(password_policies, &ExactlyOnePosition))
+}
+
+// Half of the passwords satisfy the count policy. Both answers are tallied while the passwords are built.
+pub fn generate(rng: &mut Rng, size: usize) -> Result<Generated, String> {
+    let mut input = String::new();
+    let mut valid_by_count = 0;
+    let mut valid_by_position = 0;
+
+    for _ in 0..size {
+        let character = (b'a' + rng.below(26) as u8) as char;
+        let first_number = rng.range(1..=10) as usize;
+        let second_number = rng.range(first_number as i64 + 1..=first_number as i64 + 10) as usize;
+        let length = rng.range(second_number as i64 + 1..=second_number as i64 + 6) as usize;
+
+        let count = if rng.chance(1, 2) {
+            rng.range(first_number as i64..=second_number as i64) as usize
+        } else if rng.chance(1, 2) {
+            rng.range(0..=first_number as i64 - 1) as usize
+        } else {
+            rng.range(second_number as i64 + 1..=length as i64) as usize
+        };
+
+        let mut password = vec![character; count];
+        while password.len() < length {
+            let other = (b'a' + rng.below(26) as u8) as char;
+            if other != character {
+                password.push(other);
+            }
+        }
+        rng.shuffle(&mut password);
+
+        if (first_number..=second_number).contains(&count) {
+            valid_by_count += 1;
+        }
+        if (password[first_number - 1] == character) != (password[second_number - 1] == character) {
+            valid_by_position += 1;
+        }
+
+        let password: String = password.into_iter().collect();
+        input.push_str(&format!(
+            "{first_number}-{second_number} {character}: {password}\n"
+        ));
+    }
+
+    Ok(Generated::new(
+        input,
+        Some(Answer::from(valid_by_count)),
+        Some(Answer::from(valid_by_position)),
+    ))
+}
+
+pub struct Day2;
+
+impl Solution for Day2 {
+    const DAY: u32 = 2;
+
+    type Input = Vec<PolicyPassword>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_data(input)
+    }
+
+    fn part_1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_1(input)?.into())
+    }
+
+    fn part_2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_2(input)?.into())
+    }
 }
 
 #[cfg(test)]
@@ -73,19 +486,171 @@ mod tests {
     use super::*;
     use rstest::rstest;
 
-    use crate::utilities::file_utilities::get_file_path;
+    use crate::utilities::input::puzzle_input;
+
+    #[rstest]
+    #[case("1-3 a: abcde")]
+    #[case(" 1 - 3  a :abcde ")]
+    fn test_parse_line(#[case] line: &str) {
+        assert_eq!(
+            PolicyPassword {
+                first_number: 1,
+                second_number: 3,
+                character: String::from("a"),
+                password: String::from("abcde"),
+            },
+            parse_line(line).unwrap()
+        );
+    }
+
+    #[rstest]
+    #[case("1-3 a abcde", 7)]
+    #[case("1-x a: abcde", 3)]
+    #[case("1-3 a:", 7)]
+    #[case("1-3 : abcde", 5)]
+    #[case("3-1 a: aaa", 1)]
+    #[case("  3-1 a: aaa", 3)]
+    fn test_parse_line_reports_span(#[case] line: &str, #[case] column: usize) {
+        assert_eq!(column, parse_line(line).unwrap_err().column);
+    }
+
+    #[rstest]
+    #[case("3 c: abccd", 3, 3, "c")]
+    #[case("1-2 ab: abab", 1, 2, "ab")]
+    fn test_parse_line_formats(
+        #[case] line: &str,
+        #[case] first_number: usize,
+        #[case] second_number: usize,
+        #[case] character: &str,
+    ) {
+        let entry = parse_line(line).unwrap();
+
+        assert_eq!(
+            (first_number, second_number, character),
+            (
+                entry.first_number,
+                entry.second_number,
+                entry.character.as_str()
+            )
+        );
+    }
+
+    #[test]
+    fn test_lint() {
+        let lint =
+            lint("# from the shopkeeper\n1-3 a: abcde\n\n1-3 b cdefg\n3-1 c: ccc\n2 ab: abab\n");
+
+        assert_eq!(
+            vec![2, 6],
+            lint.entries
+                .iter()
+                .map(|(line, _)| *line)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            "<input>:4:7: Expected `:`\n    1-3 b cdefg\n          ^\n\
+             <input>:5:1: The range 3-1 runs backwards\n    3-1 c: ccc\n    ^\n\
+             2 password(s) read, 2 line(s) with problems\n",
+            lint.report()
+        );
+    }
+
+    #[test]
+    fn test_parse_data_skips_comments() {
+        let password_policies =
+            parse_data("# header\n\n1-3 a: abcde\n  # note\n3 ab: abab").unwrap();
+
+        assert_eq!(2, password_policies.len());
+        assert_eq!(1, part_1(&password_policies).unwrap());
+    }
 
     #[rstest]
     #[case(true, 2)]
     #[case(false, 422)]
-    fn test_part_1(#[case] is_test: bool, #[case] expected: i32) {
-        assert_eq!(expected, part_1(get_file_path(is_test, 2, None)));
+    fn test_part_1(#[case] is_test: bool, #[case] expected: usize) {
+        let password_policies =
+            parse_data(&puzzle_input(is_test, 2, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_1(&password_policies).unwrap());
     }
 
     #[rstest]
     #[case(true, 1)]
     #[case(false, 451)]
-    fn test_part_2(#[case] is_test: bool, #[case] expected: i32) {
-        assert_eq!(expected, part_2(get_file_path(is_test, 2, None)));
+    fn test_part_2(#[case] is_test: bool, #[case] expected: usize) {
+        let password_policies =
+            parse_data(&puzzle_input(is_test, 2, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_2(&password_policies).unwrap());
+    }
+
+    #[rstest]
+    #[case("count", "1-3 a: abcde", None)]
+    #[case("count", "1-3 b: cdefg", Some("b appears 0 time(s), expected 1 to 3"))]
+    #[case("positions", "1-3 a: abcde", None)]
+    #[case("positions", "2-9 c: ccccccccc", Some("positions 2 and 9 both hold c"))]
+    #[case("positions", "2-9 c: ccc", None)]
+    #[case("positions", "4-9 é: ééé", Some("neither position 4 nor 9 holds é"))]
+    #[case("positions", "2-3 é: aéz", None)]
+    #[case("positions", "3 c: abc", None)]
+    #[case("positions", "2 c: abc", Some("position 2 does not hold c"))]
+    #[case("positions", "2 bc: abc", None)]
+    #[case("regex:^[a-z]+$", "1-3 a: abcde", None)]
+    #[case("regex:^[a-z]+$", "1-3 a: abc-de", Some("does not match ^[a-z]+$"))]
+    #[case(
+        "min-length:6",
+        "1-3 a: abcde",
+        Some("is 5 character(s) long, expected at least 6")
+    )]
+    #[case("classes:lower,digit", "1-3 a: abc1", None)]
+    #[case(
+        "classes:upper,digit,symbol",
+        "1-3 a: ab!",
+        Some("has no upper or digit character")
+    )]
+    #[case("forbid:cd,ef,xyz", "1-3 a: abcdef", Some("contains cd and ef"))]
+    fn test_policy_violation(
+        #[case] policy: &str,
+        #[case] line: &str,
+        #[case] expected: Option<&str>,
+    ) {
+        let policy = parse_policy(policy).unwrap();
+
+        assert_eq!(
+            expected.map(String::from),
+            policy.violation(&parse_line(line).unwrap())
+        );
+    }
+
+    #[rstest]
+    #[case("count", Ok("count"))]
+    #[case("classes:digit,upper", Ok("classes:digit,upper"))]
+    #[case("min-length:x", Err("min-length expects a number, got x"))]
+    #[case(
+        "classes:lower,emoji",
+        Err("Unknown character class emoji, expected lower, upper, digit or symbol")
+    )]
+    #[case("forbid:", Err("Unknown policy forbid:, expected count, positions, regex:<pattern>, min-length:<length>, classes:<class,...> or forbid:<text,...>"))]
+    #[case("count:3", Err("Unknown policy count:3, expected count, positions, regex:<pattern>, min-length:<length>, classes:<class,...> or forbid:<text,...>"))]
+    fn test_parse_policy(#[case] policy: &str, #[case] expected: Result<&str, &str>) {
+        assert_eq!(
+            expected.map(String::from).map_err(String::from),
+            parse_policy(policy).map(|policy| policy.name())
+        );
+    }
+
+    #[test]
+    fn test_report() {
+        let password_policies = lint(&puzzle_input(true, 2, None).read().unwrap()).entries;
+        let policies = vec![
+            parse_policy("count").unwrap(),
+            parse_policy("positions").unwrap(),
+        ];
+
+        assert_eq!(
+            "line 1: valid abcde\n\
+             line 2: invalid cdefg: b appears 0 time(s), expected 1 to 3; neither position 1 nor 3 holds b\n\
+             line 3: invalid ccccccccc: positions 2 and 9 both hold c\n\
+             1 of 3 password(s) are valid\n",
+            report(&verdicts(&password_policies, &policies))
+        );
     }
 }
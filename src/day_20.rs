@@ -1,14 +1,27 @@
 use itertools::Itertools;
-
-use crate::utilities::file_utilities::read_lines;
+use std::sync::OnceLock;
+
+use nom::combinator::cut;
+use nom::sequence::delimited;
+
+use crate::error::{parse_records, ParseError, SolveError};
+use crate::generate::Generated;
+use crate::observer::{self, Event};
+use crate::solution::{Answer, Solution};
+use crate::utilities::binary::{from_bytes, to_bytes, Binary};
+use crate::utilities::file_utilities::Record;
+use crate::utilities::grammar::{number, parse_all, token, ws, GResult};
+use crate::utilities::grid::Grid;
+use crate::utilities::image::{Color, Scene};
+use crate::utilities::random::Rng;
 use rustc_hash::{FxHashMap, FxHashSet};
 
-type Tile = (isize, Vec<Vec<Pixel>>);
+pub type Tile = (isize, Grid<Pixel>);
 
 const TILE_SIZE: usize = 10;
 
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
-enum Pixel {
+pub enum Pixel {
     On,
     #[default]
     Off,
@@ -41,40 +54,49 @@ enum Flip {
 
 type Orientation = (Rotation, Flip);
 
-fn parse_cluster(line: String) -> Tile {
-    let cluster: Vec<&str> = line.split(' ').collect();
-    let tile_id = cluster[1][0..4].parse::<isize>().unwrap();
-    let tile_data = &cluster[2..];
-
-    let mut pixels: Vec<Vec<Pixel>> = vec![vec![Default::default(); TILE_SIZE]; TILE_SIZE];
-
-    for (i, row) in tile_data.iter().enumerate() {
-        for (j, char) in row.chars().enumerate() {
-            let pixel = match char {
-                '#' => Pixel::On,
-                '.' => Pixel::Off,
-                _ => panic!("Something's wrong with this pixel"),
-            };
-            pixels[i][j] = pixel;
-        }
+// Tile 2311:
+fn tile_header(input: &str) -> GResult<'_, isize> {
+    delimited(token("Tile"), cut(ws(number)), cut(token(":")))(input)
+}
+
+fn parse_cluster(record: &Record) -> Result<Tile, ParseError> {
+    let header = record.lines[0];
+    let tile_id = parse_all(header, tile_header)?;
+    let tile_data = &record.lines[1..];
+
+    if tile_data.len() != TILE_SIZE {
+        return Err(ParseError::new(
+            header,
+            1,
+            format!("Expected {TILE_SIZE} rows in tile {tile_id}"),
+        ));
     }
 
-    (tile_id, pixels)
+    let numbered_rows = tile_data
+        .iter()
+        .enumerate()
+        .map(|(i, row)| (record.line + i + 1, *row));
+    let pixels = Grid::parse_lines(numbered_rows, |c| match c {
+        '#' => Some(Pixel::On),
+        '.' => Some(Pixel::Off),
+        _ => None,
+    })?;
+
+    if pixels.width() != TILE_SIZE {
+        let row = tile_data[0];
+        return Err(ParseError::new(
+            row,
+            row.len() + 1,
+            format!("Expected a row of width {TILE_SIZE}"),
+        )
+        .with_line(record.line + 1));
+    }
+
+    Ok((tile_id, pixels))
 }
 
-fn parse_data(file_path: String) -> Vec<Tile> {
-    read_lines(file_path)
-        .into_iter()
-        .coalesce(|x, y| {
-            if x.is_empty() == y.is_empty() {
-                Ok(format!("{} {}", x, y))
-            } else {
-                Err((x, y))
-            }
-        })
-        .filter(|s| !s.is_empty())
-        .map(parse_cluster)
-        .collect()
+pub fn parse_data(input: &str) -> Result<Vec<Tile>, ParseError> {
+    parse_records(input, parse_cluster)
 }
 
 fn rotated_row_column_by_ninety(row: usize, column: usize) -> (usize, usize) {
@@ -102,7 +124,7 @@ fn get_pixel_on_tile(tile: &Tile, orientation: &Orientation, row: usize, column:
         Flip::RowWise => (TILE_SIZE - 1 - rotated_row, rotated_column),
     };
 
-    tile.1[flipped_row][flipped_column]
+    tile.1[(flipped_row, flipped_column)]
 }
 
 fn get_edge(tile: &Tile, edge: &Edge, orientation: &Orientation) -> [Pixel; TILE_SIZE] {
@@ -163,13 +185,14 @@ fn tiles_match(
     None
 }
 
+#[derive(Clone, Debug, PartialEq)]
 struct PuzzlePieces {
     corners: FxHashMap<isize, Vec<isize>>,
     edges: FxHashMap<isize, Vec<isize>>,
     insides: FxHashMap<isize, Vec<isize>>,
 }
 
-fn categorize_tiles(tiles: &[Tile]) -> PuzzlePieces {
+fn categorize_tiles(tiles: &[Tile]) -> Result<PuzzlePieces, SolveError> {
     let mut corners: FxHashMap<isize, Vec<isize>> = FxHashMap::default();
     let mut edges: FxHashMap<isize, Vec<isize>> = FxHashMap::default();
     let mut insides: FxHashMap<isize, Vec<isize>> = FxHashMap::default();
@@ -187,269 +210,608 @@ fn categorize_tiles(tiles: &[Tile]) -> PuzzlePieces {
             2 => corners.insert(tile_1.0, matching_tiles),
             3 => edges.insert(tile_1.0, matching_tiles),
             4 => insides.insert(tile_1.0, matching_tiles),
-            _ => panic!("The data or the algorithm is faulty."),
+            count => {
+                return Err(SolveError::NoSolution(format!(
+                    "Tile {} borders {count} other tiles",
+                    tile_1.0
+                )))
+            }
         };
     }
 
-    PuzzlePieces {
+    if corners.len() != 4 {
+        return Err(SolveError::NoSolution(format!(
+            "Found {} corner tiles instead of 4",
+            corners.len()
+        )));
+    }
+
+    Ok(PuzzlePieces {
         corners,
         edges,
         insides,
-    }
+    })
 }
 
-pub fn part_1(file_path: String) -> i64 {
-    let tiles = parse_data(file_path);
+fn corner_product(puzzle_pieces: &PuzzlePieces) -> i64 {
+    puzzle_pieces.corners.keys().map(|i| *i as i64).product()
+}
 
-    let puzzle_pieces = categorize_tiles(&tiles);
+pub fn part_1(tiles: &[Tile]) -> Result<i64, SolveError> {
+    Ok(corner_product(&categorize_tiles(tiles)?))
+}
 
-    if puzzle_pieces.corners.len() != 4 {
-        panic!("Your algorithm is faulty, you're making wrong assumptions.");
-    }
+pub type Image = Grid<Pixel>;
+
+const MONSTER_OFFSETS: [(usize, usize); 15] = [
+    (0, 18),
+    (1, 0),
+    (1, 5),
+    (1, 6),
+    (1, 11),
+    (1, 12),
+    (1, 17),
+    (1, 18),
+    (1, 19),
+    (2, 1),
+    (2, 4),
+    (2, 7),
+    (2, 10),
+    (2, 13),
+    (2, 16),
+];
+
+fn all_orientations() -> Vec<Orientation> {
+    let rotations = [
+        Rotation::Zero,
+        Rotation::Ninety,
+        Rotation::OneEighty,
+        Rotation::TwoSeventy,
+    ];
+    let flips = [Flip::No, Flip::RowWise, Flip::ColumnWise];
+
+    rotations
+        .iter()
+        .cartesian_product(flips.iter())
+        .map(|(rotation, flip)| (*rotation, *flip))
+        .collect()
+}
 
-    puzzle_pieces.corners.keys().map(|i| *i as i64).product()
+struct Puzzle<'a> {
+    tiles_by_id: FxHashMap<isize, &'a Tile>,
+    puzzle_pieces: &'a PuzzlePieces,
+    puzzle_size: usize,
 }
 
-pub fn part_2(file_path: String) -> i64 {
-    let tiles = parse_data(file_path);
-    let tiles_by_id: FxHashMap<isize, &Tile> = tiles.iter().map(|tile| (tile.0, tile)).collect();
+impl<'a> Puzzle<'a> {
+    fn new(tiles: &'a [Tile], puzzle_pieces: &'a PuzzlePieces) -> Result<Self, SolveError> {
+        let puzzle_size = (tiles.len() as f64).sqrt().round() as usize;
 
-    let puzzle_pieces = categorize_tiles(&tiles);
-    let puzzle_size = (tiles.len() as f64).sqrt().round() as usize;
+        if puzzle_size * puzzle_size != tiles.len() {
+            return Err(SolveError::NoSolution(format!(
+                "{} tiles cannot form a square",
+                tiles.len()
+            )));
+        }
 
-    let all_edges_and_corners_pieces = puzzle_pieces
-        .corners
-        .iter()
-        .chain(puzzle_pieces.edges.iter())
-        .collect::<FxHashMap<_, _>>();
+        Ok(Self {
+            tiles_by_id: tiles.iter().map(|tile| (tile.0, tile)).collect(),
+            puzzle_pieces,
+            puzzle_size,
+        })
+    }
 
-    let all_neighbours = puzzle_pieces
-        .corners
-        .iter()
-        .chain(puzzle_pieces.edges.iter())
-        .chain(puzzle_pieces.insides.iter())
-        .collect::<FxHashMap<_, _>>();
+    // For every corner, start with it in all orientations, and skip the ones where the puzzle doesn't add up.
+    fn assemblies(&self) -> impl Iterator<Item = Image> + '_ {
+        self.puzzle_pieces
+            .corners
+            .keys()
+            .cartesian_product(all_orientations())
+            .filter_map(|(first_corner, first_corner_rotation)| {
+                self.assemble_from(*first_corner, first_corner_rotation)
+            })
+    }
 
-    // For every corner, start with it in all orientations, and give up when/if the puzzle doesn't add up.
-    let all_corners = puzzle_pieces.corners.keys().collect::<Vec<_>>();
+    fn assemble_from(
+        &self,
+        first_corner: isize,
+        first_corner_rotation: Orientation,
+    ) -> Option<Image> {
+        let puzzle_size = self.puzzle_size;
+        let tiles_by_id = &self.tiles_by_id;
+        let puzzle_pieces = self.puzzle_pieces;
+
+        let all_edges_and_corners_pieces = puzzle_pieces
+            .corners
+            .iter()
+            .chain(puzzle_pieces.edges.iter())
+            .collect::<FxHashMap<_, _>>();
 
-    for first_corner in all_corners.iter() {
-        let rotations = [
-            Rotation::Zero,
-            Rotation::Ninety,
-            Rotation::OneEighty,
-            Rotation::TwoSeventy,
-        ];
-        let flips = [Flip::No, Flip::RowWise, Flip::ColumnWise];
+        let all_neighbours = puzzle_pieces
+            .corners
+            .iter()
+            .chain(puzzle_pieces.edges.iter())
+            .chain(puzzle_pieces.insides.iter())
+            .collect::<FxHashMap<_, _>>();
+
+        let mut pieces: Vec<Vec<isize>> = vec![vec![Default::default(); puzzle_size]; puzzle_size];
+        let mut orientations: Vec<Vec<Orientation>> =
+            vec![vec![Default::default(); puzzle_size]; puzzle_size];
+
+        observer::emit(|| Event::CornerTried {
+            tile: first_corner,
+            orientation: format!("{first_corner_rotation:?}"),
+        });
+        let first_corner_friends = puzzle_pieces.corners.get(&first_corner).unwrap().to_vec();
+
+        let (first_first_corner_friend, _, _, first_first_corner_friend_rotation) =
+            first_corner_friends
+                .iter()
+                .filter_map(|friend| {
+                    let matching = tiles_match(
+                        tiles_by_id.get(&first_corner).unwrap(),
+                        &first_corner_rotation,
+                        tiles_by_id.get(friend).unwrap(),
+                        Some((&Edge::Right, &Edge::Left)),
+                    );
+
+                    matching.map(|matching| (friend, matching.0, matching.1, matching.2))
+                })
+                .next()?;
+
+        pieces[0][0] = first_corner;
+        orientations[0][0] = first_corner_rotation;
+
+        pieces[0][1] = *first_first_corner_friend;
+        orientations[0][1] = first_first_corner_friend_rotation;
+
+        for (column, tile) in pieces[0].iter().take(2).enumerate() {
+            observer::emit(|| Event::TilePlaced {
+                tile: *tile,
+                row: 0,
+                column,
+            });
+        }
 
-        'orientation_loop: for first_corner_rotation in
-            rotations.iter().cartesian_product(flips.iter())
-        {
-            let mut pieces: Vec<Vec<isize>> =
-                vec![vec![Default::default(); puzzle_size]; puzzle_size];
-            let mut orientations: Vec<Vec<Orientation>> =
-                vec![vec![Default::default(); puzzle_size]; puzzle_size];
+        let mut handled: FxHashSet<isize> = vec![first_corner, *first_first_corner_friend]
+            .into_iter()
+            .collect();
+
+        // Fill up the puzzle from top to bottom, from left to right.
+        for row in 0..puzzle_size {
+            for column in 0..puzzle_size {
+                if row == 0 && column < 2 {
+                    continue;
+                }
 
-            // println!("Trying {first_corner} as first corner with rotation {first_corner_rotation:?}");
-            let first_corner_rotation = (*first_corner_rotation.0, *first_corner_rotation.1);
-            let first_corner_friends = puzzle_pieces.corners.get(first_corner).unwrap().to_vec();
+                let (neighbour_tile_id, neighbour_tile_rotation, tile_edge, neighbour_edge) =
+                    if row == 0 {
+                        (
+                            pieces[0][column - 1],
+                            orientations[0][column - 1],
+                            &Edge::Right,
+                            &Edge::Left,
+                        )
+                    } else {
+                        (
+                            pieces[row - 1][column],
+                            orientations[row - 1][column],
+                            &Edge::Bottom,
+                            &Edge::Top,
+                        )
+                    };
+
+                let important_subset = if row == 0 {
+                    &all_edges_and_corners_pieces
+                } else {
+                    &all_neighbours
+                };
 
-            let Some((first_first_corner_friend, _, _, first_first_corner_friend_rotation)) =
-                first_corner_friends
+                let (next_piece, next_piece_orientation) = all_neighbours
+                    .get(&neighbour_tile_id)
+                    .unwrap()
                     .iter()
-                    .filter_map(|friend| {
-                        let matching = tiles_match(
-                            tiles_by_id.get(first_corner).unwrap(),
-                            &first_corner_rotation,
-                            tiles_by_id.get(friend).unwrap(),
-                            Some((&Edge::Right, &Edge::Left)),
+                    .filter(|n| important_subset.contains_key(n) && !handled.contains(n))
+                    .filter_map(|piece| {
+                        let tile_match = tiles_match(
+                            tiles_by_id.get(&neighbour_tile_id).unwrap(),
+                            &neighbour_tile_rotation,
+                            tiles_by_id.get(piece).unwrap(),
+                            Some((tile_edge, neighbour_edge)),
                         );
 
-                        matching.map(|matching| (friend, matching.0, matching.1, matching.2))
+                        tile_match.map(|tile_match| (piece, tile_match.2))
                     })
-                    .next()
-            else {
-                continue;
-            };
-
-            pieces[0][0] = **first_corner;
-            orientations[0][0] = first_corner_rotation;
-
-            pieces[0][1] = *first_first_corner_friend;
-            orientations[0][1] = first_first_corner_friend_rotation;
-
-            let mut handled: FxHashSet<isize> = vec![**first_corner, *first_first_corner_friend]
-                .into_iter()
-                .collect();
-
-            // Fill up the puzzle from top to bottom, from left to right.
-            for row in 0..puzzle_size {
-                for column in 0..puzzle_size {
-                    if row == 0 && column < 2 {
-                        continue;
-                    }
+                    .next()?;
 
-                    let (neighbour_tile_id, neighbour_tile_rotation, tile_edge, neighbour_edge) =
-                        if row == 0 {
-                            (
-                                pieces[0][column - 1],
-                                orientations[0][column - 1],
-                                &Edge::Right,
-                                &Edge::Left,
-                            )
-                        } else {
-                            (
-                                pieces[row - 1][column],
-                                orientations[row - 1][column],
-                                &Edge::Bottom,
-                                &Edge::Top,
-                            )
-                        };
-
-                    let important_subset = if row == 0 {
-                        &all_edges_and_corners_pieces
-                    } else {
-                        &all_neighbours
-                    };
+                pieces[row][column] = *next_piece;
+                orientations[row][column] = next_piece_orientation;
+                observer::emit(|| Event::TilePlaced {
+                    tile: *next_piece,
+                    row,
+                    column,
+                });
 
-                    let Some((next_piece, next_piece_orientation)) = all_neighbours
-                        .get(&neighbour_tile_id)
-                        .unwrap()
-                        .iter()
-                        .filter(|n| important_subset.contains_key(n) && !handled.contains(n))
-                        .filter_map(|piece| {
-                            let tile_match = tiles_match(
-                                tiles_by_id.get(&neighbour_tile_id).unwrap(),
-                                &neighbour_tile_rotation,
-                                tiles_by_id.get(piece).unwrap(),
-                                Some((tile_edge, neighbour_edge)),
-                            );
-
-                            tile_match.map(|tile_match| (piece, tile_match.2))
-                        })
-                        .next()
-                    else {
-                        continue 'orientation_loop;
-                    };
+                handled.insert(*next_piece);
+            }
+        }
 
-                    pieces[row][column] = *next_piece;
-                    orientations[row][column] = next_piece_orientation;
+        // Now construct the image without the tile borders.
+        let whole_picture_size = puzzle_size * (TILE_SIZE - 2);
+        let mut picture_pixels: Image =
+            Grid::new(whole_picture_size, whole_picture_size, Pixel::Off);
+
+        let inner_tile_size = TILE_SIZE - 2;
+
+        for tile_row in 0..puzzle_size {
+            for tile_column in 0..puzzle_size {
+                let tile_id = pieces[tile_row][tile_column];
+                let tile_orientation = orientations[tile_row][tile_column];
+                let tile_pixes = tiles_by_id.get(&tile_id).unwrap();
+
+                for pixel_row in 0..inner_tile_size {
+                    for pixel_column in 0..inner_tile_size {
+                        let pixel = get_pixel_on_tile(
+                            tile_pixes,
+                            &tile_orientation,
+                            pixel_row + 1,
+                            pixel_column + 1,
+                        );
 
-                    handled.insert(*next_piece);
+                        picture_pixels[(
+                            tile_row * inner_tile_size + pixel_row,
+                            tile_column * inner_tile_size + pixel_column,
+                        )] = pixel;
+                    }
                 }
             }
+        }
+
+        Some(picture_pixels)
+    }
+}
+
+fn assemble_from_pieces(tiles: &[Tile], puzzle_pieces: &PuzzlePieces) -> Result<Image, SolveError> {
+    Puzzle::new(tiles, puzzle_pieces)?
+        .assemblies()
+        .next()
+        .ok_or_else(|| SolveError::NoSolution(String::from("The tiles do not fit together")))
+}
+
+pub fn assemble_image(tiles: &[Tile]) -> Result<Image, SolveError> {
+    assemble_from_pieces(tiles, &categorize_tiles(tiles)?)
+}
+
+pub fn find_sea_monsters(image: &Image) -> FxHashSet<(usize, usize)> {
+    let mut monster_pixels = FxHashSet::default();
+
+    for (row_index, column_index) in image.positions() {
+        let fits_monster = MONSTER_OFFSETS.iter().all(|(row_offset, column_offset)| {
+            let row_in_picture = (row_offset + row_index) as isize;
+            let column_in_picture = (column_offset + column_index) as isize;
+
+            image.get(row_in_picture, column_in_picture) == Some(&Pixel::On)
+        });
+
+        if fits_monster {
+            observer::emit(|| Event::MonsterFound {
+                row: row_index,
+                column: column_index,
+            });
+            monster_pixels.extend(MONSTER_OFFSETS.iter().map(|(row_offset, column_offset)| {
+                (row_offset + row_index, column_offset + column_index)
+            }));
+        }
+    }
+
+    monster_pixels
+}
+
+// The first orientation of the image that shows sea monsters, along with their pixels.
+fn orient_to_sea_monsters(image: &Image) -> Result<(Image, FxHashSet<(usize, usize)>), SolveError> {
+    image
+        .orientations()
+        .into_iter()
+        .map(|picture_pixels| {
+            let monster_pixels = find_sea_monsters(&picture_pixels);
+            (picture_pixels, monster_pixels)
+        })
+        .find(|(_, monster_pixels)| !monster_pixels.is_empty())
+        .ok_or_else(|| {
+            SolveError::NoSolution(String::from(
+                "No arrangement of the image contains a sea monster",
+            ))
+        })
+}
+
+fn water_roughness(image: &Image) -> Result<i64, SolveError> {
+    let (picture_pixels, monster_pixels) = orient_to_sea_monsters(image)?;
+
+    // Don't figure out monsters. Figure out #'s without monsters, you idiot!!
+    let hashtag_total = picture_pixels
+        .cells()
+        .iter()
+        .filter(|pixel| **pixel == Pixel::On)
+        .count();
+    let hashtag_monster = monster_pixels.len();
+    Ok((hashtag_total - hashtag_monster) as i64)
+}
+
+pub fn part_2(tiles: &[Tile]) -> Result<i64, SolveError> {
+    water_roughness(&assemble_image(tiles)?)
+}
+
+const WATER: Color = Color(170, 200, 230);
+const WAVE: Color = Color(20, 50, 110);
+const SEA_MONSTER: Color = Color(200, 30, 30);
+
+// Part 1 draws the image as assembled, part 2 turns it to face the sea monsters and highlights them.
+pub fn render(tiles: &[Tile], part: u32) -> Result<Vec<Scene>, SolveError> {
+    let image = assemble_image(tiles)?;
+    let (image, monster_pixels) = match part {
+        1 => (image, FxHashSet::default()),
+        _ => orient_to_sea_monsters(&image)?,
+    };
+
+    let mut scene = Scene::new(image.width() as f64, image.height() as f64, WATER);
+    for ((row, column), pixel) in image.iter() {
+        if monster_pixels.contains(&(row, column)) {
+            scene.cell(row, column, SEA_MONSTER);
+        } else if *pixel == Pixel::On {
+            scene.cell(row, column, WAVE);
+        }
+    }
+
+    Ok(vec![scene])
+}
+
+// Neighbouring tiles share their border, so tiles start every TILE_SIZE - 1 pixels on the sheet they are cut from.
+const TILE_STEP: usize = TILE_SIZE - 1;
+
+fn edge_code(pixels: &[Pixel]) -> (u32, u32) {
+    let code = |pixels: &mut dyn Iterator<Item = &Pixel>| {
+        pixels.fold(0, |code, pixel| code << 1 | u32::from(*pixel == Pixel::On))
+    };
+
+    (code(&mut pixels.iter()), code(&mut pixels.iter().rev()))
+}
+
+fn random_pixel(rng: &mut Rng, numerator: u64, denominator: u64) -> Pixel {
+    if rng.chance(numerator, denominator) {
+        Pixel::On
+    } else {
+        Pixel::Off
+    }
+}
+
+// The image is drawn first, with its sea monsters, and then cut into tiles whose borders are all distinct, so only true neighbours fit together. Each tile is then turned or flipped at random.
+pub fn generate(rng: &mut Rng, size: usize) -> Result<Generated, String> {
+    if !(3..=12).contains(&size) {
+        return Err(String::from(
+            "Day 20 needs between 3 and 12 tiles along each side",
+        ));
+    }
+
+    let image_size = size * (TILE_SIZE - 2);
+    let mut monsters = vec![];
+    for _ in 0..100 * size {
+        if monsters.len() == size * size / 3 {
+            break;
+        }
+
+        let corner = (rng.index(image_size - 2), rng.index(image_size - 19));
+        let overlaps = monsters.iter().any(|(row, column): &(usize, usize)| {
+            row.abs_diff(corner.0) < 3 && column.abs_diff(corner.1) < 20
+        });
+        if !overlaps {
+            monsters.push(corner);
+        }
+    }
+
+    // Sea monsters may only show up in the orientation the image was drawn in.
+    let image = loop {
+        let mut image = Grid::from_fn(image_size, image_size, |_| random_pixel(rng, 1, 3));
+        for (row, column) in monsters.iter() {
+            for (row_offset, column_offset) in MONSTER_OFFSETS.iter() {
+                image[(row + row_offset, column + column_offset)] = Pixel::On;
+            }
+        }
+
+        if image.orientations()[1..]
+            .iter()
+            .all(|turned| find_sea_monsters(turned).is_empty())
+        {
+            break image;
+        }
+    };
 
-            // Now look for rotation and construct image.
-            let whole_picture_size = puzzle_size * (TILE_SIZE - 2);
-            let mut picture_pixels: Vec<Vec<Pixel>> =
-                vec![vec![Default::default(); whole_picture_size]; whole_picture_size];
-
-            let inner_tile_size = TILE_SIZE - 2;
-
-            for tile_row in 0..puzzle_size {
-                for tile_column in 0..puzzle_size {
-                    let tile_id = pieces[tile_row][tile_column];
-                    let tile_orientation = orientations[tile_row][tile_column];
-                    let tile_pixes = tiles_by_id.get(&tile_id).unwrap();
-
-                    for pixel_row in 0..inner_tile_size {
-                        for pixel_column in 0..inner_tile_size {
-                            let pixel = get_pixel_on_tile(
-                                tile_pixes,
-                                &tile_orientation,
-                                pixel_row + 1,
-                                pixel_column + 1,
-                            );
-
-                            picture_pixels[tile_row * inner_tile_size + pixel_row]
-                                [tile_column * inner_tile_size + pixel_column] = pixel;
+    let sheet_size = size * TILE_STEP + 1;
+    let mut sheet = Grid::from_fn(sheet_size, sheet_size, |(row, column)| {
+        match (row % TILE_STEP, column % TILE_STEP) {
+            (0, _) | (_, 0) => random_pixel(rng, 1, 2),
+            (row_offset, column_offset) => {
+                image[(
+                    row / TILE_STEP * (TILE_SIZE - 2) + row_offset - 1,
+                    column / TILE_STEP * (TILE_SIZE - 2) + column_offset - 1,
+                )]
+            }
+        }
+    });
+
+    // Redraws the inside of each border until it differs from every other border, forwards and backwards.
+    let mut used = FxHashSet::default();
+    for horizontal in [true, false] {
+        for line in 0..=size {
+            for segment in 0..size {
+                let positions: Vec<(usize, usize)> = (0..TILE_SIZE)
+                    .map(|offset| {
+                        if horizontal {
+                            (line * TILE_STEP, segment * TILE_STEP + offset)
+                        } else {
+                            (segment * TILE_STEP + offset, line * TILE_STEP)
                         }
+                    })
+                    .collect();
+
+                let mut attempts = 0;
+                loop {
+                    let pixels: Vec<Pixel> =
+                        positions.iter().map(|position| sheet[*position]).collect();
+                    let (forwards, backwards) = edge_code(&pixels);
+
+                    if forwards != backwards
+                        && !used.contains(&forwards)
+                        && !used.contains(&backwards)
+                    {
+                        used.extend([forwards, backwards]);
+                        break;
+                    }
+
+                    attempts += 1;
+                    if attempts == 1000 {
+                        return Err(String::from("Ran out of distinct tile borders"));
+                    }
+                    for position in positions[1..TILE_SIZE - 1].iter() {
+                        sheet[*position] = random_pixel(rng, 1, 2);
                     }
                 }
             }
+        }
+    }
 
-            let mut monsters = 0;
-            let mut monster_pixels = FxHashSet::default();
-            let monster_offsets = [
-                (0, 18),
-                (1, 0),
-                (1, 5),
-                (1, 6),
-                (1, 11),
-                (1, 12),
-                (1, 17),
-                (1, 18),
-                (1, 19),
-                (2, 1),
-                (2, 4),
-                (2, 7),
-                (2, 10),
-                (2, 13),
-                (2, 16),
-            ];
-
-            for row_index in 0..whole_picture_size {
-                for column_index in 0..whole_picture_size {
-                    let fits_monster = monster_offsets.iter().all(|(row_offset, column_offset)| {
-                        let row_in_picture = row_offset + row_index;
-                        let column_in_picture = column_offset + column_index;
-
-                        if !(0..whole_picture_size).contains(&row_in_picture)
-                            || !(0..whole_picture_size).contains(&column_in_picture)
-                        {
-                            return false;
-                        }
+    let ids: Vec<i64> = rng.distinct(1000..=9999, size * size);
+    let mut corner_product = 1;
+    let mut tiles = vec![];
 
-                        picture_pixels[row_offset + row_index][column_offset + column_index]
-                            == Pixel::On
-                    });
+    for (index, id) in ids.iter().enumerate() {
+        let (tile_row, tile_column) = (index / size, index % size);
+        if [0, size - 1].contains(&tile_row) && [0, size - 1].contains(&tile_column) {
+            corner_product *= id;
+        }
 
-                    if fits_monster {
-                        // println!("Monster at ({row_index}, {column_index})");
-                        monsters += 1;
+        let tile = Grid::from_fn(TILE_SIZE, TILE_SIZE, |(row, column)| {
+            sheet[(tile_row * TILE_STEP + row, tile_column * TILE_STEP + column)]
+        });
+        let tile = tile.orientations().swap_remove(rng.index(8));
+        let pixels = tile.render(|pixel| match pixel {
+            Pixel::On => '#',
+            Pixel::Off => '.',
+        });
+        tiles.push(format!("Tile {id}:\n{pixels}"));
+    }
+    rng.shuffle(&mut tiles);
 
-                        monster_pixels.extend(monster_offsets.iter().map(
-                            |(row_offset, column_offset)| {
-                                (row_offset + row_index, column_offset + column_index)
-                            },
-                        ));
-                    }
-                }
-            }
+    let on = image
+        .cells()
+        .iter()
+        .filter(|pixel| **pixel == Pixel::On)
+        .count();
+
+    Ok(Generated::new(
+        tiles.join("\n"),
+        Some(Answer::from(corner_product)),
+        Some(Answer::from(on - find_sea_monsters(&image).len())),
+    ))
+}
 
-            if monsters > 0 {
-                // println!("Found monsters in this map:");
-                //
-                // println!("{pieces:?}");
-                // println!("{orientations:?}");
-
-                // for row in picture_pixels.iter() {
-                //     let print = row
-                //         .iter()
-                //         .map(|pixel| {
-                //             if *pixel == Pixel::Off {
-                //                 ".".to_string()
-                //             } else {
-                //                 "#".to_string()
-                //             }
-                //         })
-                //         .join("");
-                //     println!("{print:?}");
-                // }
-
-                // Don't figure out monsters. Figure out #'s without monsters, you idiot!!
-                let hashtag_total: usize = picture_pixels
-                    .iter()
-                    .map(|row| row.iter().filter(|pixel| **pixel == Pixel::On).count())
-                    .sum();
-                let hashtag_monster = monster_pixels.len();
-                return (hashtag_total - hashtag_monster) as i64;
-            }
+// Both parts need to know which tiles border which, so that is worked out once and kept with the tiles.
+pub struct Photo {
+    tiles: Vec<Tile>,
+    puzzle_pieces: OnceLock<Result<PuzzlePieces, SolveError>>,
+}
+
+impl Photo {
+    pub fn new(tiles: Vec<Tile>) -> Self {
+        Self {
+            tiles,
+            puzzle_pieces: OnceLock::new(),
+        }
+    }
+
+    fn puzzle_pieces(&self) -> Result<&PuzzlePieces, SolveError> {
+        self.puzzle_pieces
+            .get_or_init(|| categorize_tiles(&self.tiles))
+            .as_ref()
+            .map_err(Clone::clone)
+    }
+}
+
+impl Binary for Pixel {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        (*self == Pixel::On).encode(bytes);
+    }
+
+    fn decode(bytes: &mut &[u8]) -> Option<Self> {
+        Some(if bool::decode(bytes)? {
+            Pixel::On
+        } else {
+            Pixel::Off
+        })
+    }
+}
+
+impl Binary for PuzzlePieces {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.corners.encode(bytes);
+        self.edges.encode(bytes);
+        self.insides.encode(bytes);
+    }
+
+    fn decode(bytes: &mut &[u8]) -> Option<Self> {
+        Some(Self {
+            corners: FxHashMap::decode(bytes)?,
+            edges: FxHashMap::decode(bytes)?,
+            insides: FxHashMap::decode(bytes)?,
+        })
+    }
+}
+
+impl Binary for Photo {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.tiles.encode(bytes);
+        let puzzle_pieces = self
+            .puzzle_pieces
+            .get()
+            .and_then(|pieces| pieces.as_ref().ok());
+        puzzle_pieces.cloned().encode(bytes);
+    }
+
+    fn decode(bytes: &mut &[u8]) -> Option<Self> {
+        let photo = Photo::new(Vec::decode(bytes)?);
+        if let Some(puzzle_pieces) = Option::decode(bytes)? {
+            let _ = photo.puzzle_pieces.set(Ok(puzzle_pieces));
         }
+        Some(photo)
+    }
+}
+
+pub struct Day20;
+
+impl Solution for Day20 {
+    const DAY: u32 = 20;
+
+    type Input = Photo;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        Ok(Photo::new(parse_data(input)?))
+    }
+
+    fn part_1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(corner_product(input.puzzle_pieces()?).into())
+    }
+
+    fn part_2(input: &Self::Input) -> Result<Answer, SolveError> {
+        let image = assemble_from_pieces(&input.tiles, input.puzzle_pieces()?)?;
+        Ok(water_roughness(&image)?.into())
+    }
+
+    fn encode(input: &Self::Input) -> Option<Vec<u8>> {
+        Some(to_bytes(input))
     }
 
-    println!("I failed...");
-    0
+    fn decode(bytes: &[u8]) -> Option<Self::Input> {
+        from_bytes(bytes)
+    }
 }
 
 #[cfg(test)]
@@ -457,19 +819,55 @@ mod tests {
     use super::*;
     use rstest::rstest;
 
-    use crate::utilities::file_utilities::get_file_path;
+    use crate::utilities::image::Shape;
+    use crate::utilities::input::puzzle_input;
 
     #[rstest]
     #[case(true, 20899048083289)]
     #[case(false, 8425574315321)]
     fn test_part_1(#[case] is_test: bool, #[case] expected: i64) {
-        assert_eq!(expected, part_1(get_file_path(is_test, 20, None)));
+        let tiles = parse_data(&puzzle_input(is_test, 20, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_1(&tiles).unwrap());
     }
 
     #[rstest]
     #[case(true, 273)]
     #[case(false, 1841)]
     fn test_part_2(#[case] is_test: bool, #[case] expected: i64) {
-        assert_eq!(expected, part_2(get_file_path(is_test, 20, None)));
+        let tiles = parse_data(&puzzle_input(is_test, 20, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_2(&tiles).unwrap());
+    }
+
+    #[test]
+    fn test_assemble_image() {
+        let tiles = parse_data(&puzzle_input(true, 20, None).read().unwrap()).unwrap();
+        let image = assemble_image(&tiles).unwrap();
+
+        assert_eq!((24, 24), (image.height(), image.width()));
+    }
+
+    #[test]
+    fn test_photo_keeps_puzzle_pieces() {
+        let photo = Day20::parse(&puzzle_input(true, 20, None).read().unwrap()).unwrap();
+        let puzzle_pieces = photo.puzzle_pieces().unwrap().clone();
+        let decoded: Photo = from_bytes(&to_bytes(&photo)).unwrap();
+
+        assert_eq!(Some(&Ok(puzzle_pieces)), decoded.puzzle_pieces.get());
+        assert_eq!(photo.tiles, decoded.tiles);
+    }
+
+    #[test]
+    fn test_render_highlights_sea_monsters() {
+        let tiles = parse_data(&puzzle_input(true, 20, None).read().unwrap()).unwrap();
+        let scene = render(&tiles, 2).unwrap().remove(0);
+        let monster_cells = scene
+            .shapes
+            .iter()
+            .filter(
+                |shape| matches!(shape, Shape::Rectangle { color, .. } if *color == SEA_MONSTER),
+            )
+            .count();
+
+        assert_eq!(2 * MONSTER_OFFSETS.len(), monster_cells);
     }
 }
@@ -1,50 +1,64 @@
-use crate::utilities::file_utilities::read_lines;
+use crate::error::{parse_lines, ParseError, SolveError};
+use crate::generate::Generated;
+use crate::observer::{self, Event};
+use crate::solution::{Answer, Solution};
+use crate::utilities::grammar::{list, parse_all, token, ws, GResult};
+use crate::utilities::random::Rng;
 use itertools::Itertools;
+use nom::character::complete::alpha1;
+use nom::combinator::{cut, map, opt};
+use nom::error::context;
+use nom::multi::many1;
+use nom::sequence::{delimited, pair, preceded};
 
 use rustc_hash::{FxHashMap, FxHashSet};
+use std::collections::BTreeSet;
 
-fn parse_line(line: &str) -> (Vec<String>, Vec<String>) {
-    let split_line: Vec<&str> = line
-        .split(&['(', ')'])
-        .map(str::trim)
-        .filter(|s| !s.is_empty())
-        .collect();
+pub type Food = (Vec<String>, Vec<String>);
 
-    let ingredients: Vec<String> = split_line[0]
-        .split(' ')
-        .map(str::trim)
-        .map(|s| s.to_string())
-        .collect();
+fn word(input: &str) -> GResult<'_, String> {
+    map(ws(alpha1), String::from)(input)
+}
 
-    let allergens: Vec<String> = split_line[1]
-        .split(&[' ', ','])
-        .filter(|s| !s.is_empty())
-        .skip(1)
-        .map(str::trim)
-        .map(|s| s.to_string())
-        .collect();
+// mxmxvkd kfcds sqjhc nhms (contains dairy, fish)
+fn food(input: &str) -> GResult<'_, Food> {
+    let ingredients = context("an ingredient", many1(word));
+    let allergens = preceded(
+        token("("),
+        cut(delimited(
+            token("contains"),
+            list(token(","), word),
+            token(")"),
+        )),
+    );
 
-    (ingredients, allergens)
+    map(
+        pair(ingredients, opt(allergens)),
+        |(ingredients, allergens)| (ingredients, allergens.unwrap_or_default()),
+    )(input)
 }
 
-fn parse_data(file_path: String) -> Vec<(Vec<String>, Vec<String>)> {
-    read_lines(file_path)
-        .into_iter()
-        .map(|line| parse_line(line.as_str()))
-        .collect()
+fn parse_line(line: &str) -> Result<Food, ParseError> {
+    parse_all(line, food)
+}
+
+pub fn parse_data(input: &str) -> Result<Vec<Food>, ParseError> {
+    parse_lines(input, parse_line)
 }
 
 fn get_allergens_mapping(
-    foods: &[(Vec<String>, Vec<String>)],
+    foods: &[Food],
     all_ingredients: &FxHashSet<String>,
     all_allergens: &FxHashSet<String>,
-) -> FxHashMap<String, String> {
+) -> Result<FxHashMap<String, String>, SolveError> {
     let mut ingredients_to_allergens_result = FxHashMap::default();
 
     let mut unknown_ingredients_remaining = all_ingredients.clone();
     let mut unknown_allergens_remaining = all_allergens.clone();
 
     while !unknown_allergens_remaining.is_empty() {
+        let mut made_progress = false;
+
         for allergen in all_allergens.iter() {
             if !unknown_allergens_remaining.contains(allergen) {
                 continue;
@@ -54,7 +68,6 @@ fn get_allergens_mapping(
                 .iter()
                 .filter(|food| food.1.contains(allergen))
                 .collect::<Vec<_>>();
-            // println!("I know that {allergen} is in {foods_that_have_it:?}");
 
             let ingredients_in_common = unknown_ingredients_remaining
                 .iter()
@@ -65,37 +78,44 @@ fn get_allergens_mapping(
                 })
                 .collect::<Vec<_>>();
 
-            // println!("And those foods have {ingredients_in_common:?} in common");
-
-            match ingredients_in_common.len() {
-                0 => panic!(
-                    "You should have something here, otherwise you forgot to update your sets!"
-                ),
-                1 => {
-                    let ingredient_in_common = *ingredients_in_common.first().unwrap();
-
+            match ingredients_in_common[..] {
+                [] => {
+                    return Err(SolveError::NoSolution(format!(
+                        "No ingredient can contain {allergen}"
+                    )))
+                }
+                [ingredient_in_common] => {
+                    observer::emit(|| Event::AllergenIdentified {
+                        allergen: allergen.clone(),
+                        ingredient: ingredient_in_common.clone(),
+                    });
                     ingredients_to_allergens_result
                         .insert(ingredient_in_common.clone(), allergen.clone());
                     unknown_allergens_remaining.remove(allergen);
                     unknown_ingredients_remaining.remove(&ingredient_in_common.clone());
+                    made_progress = true;
                     break;
                 }
                 _ => continue,
             }
         }
+
+        if !made_progress {
+            return Err(SolveError::NoSolution(String::from(
+                "Cannot tell which ingredient contains which allergen",
+            )));
+        }
     }
 
-    ingredients_to_allergens_result
+    Ok(ingredients_to_allergens_result)
 }
 
-pub fn part_1(file_path: String) -> i64 {
-    let foods = parse_data(file_path);
-
+pub fn part_1(foods: &[Food]) -> Result<i64, SolveError> {
     let all_ingredients: FxHashSet<String> = foods.iter().flat_map(|food| food.0.clone()).collect();
     let all_allergens: FxHashSet<String> = foods.iter().flat_map(|food| food.1.clone()).collect();
 
     let ingredients_with_allergens: FxHashSet<String> =
-        get_allergens_mapping(&foods, &all_ingredients, &all_allergens)
+        get_allergens_mapping(foods, &all_ingredients, &all_allergens)?
             .keys()
             .cloned()
             .collect();
@@ -105,28 +125,148 @@ pub fn part_1(file_path: String) -> i64 {
         .cloned()
         .collect();
 
-    // println!("Non-allergenic: {non_allergenic_ingredients:?}");
-
-    return foods
+    let appearances = foods
         .iter()
         .flat_map(|food| food.0.clone())
         .filter(|ingredient| non_allergenic_ingredients.contains(ingredient))
-        .count() as i64;
-}
+        .count();
 
-pub fn part_2(file_path: String) -> String {
-    let foods = parse_data(file_path);
+    Ok(appearances as i64)
+}
 
+pub fn part_2(foods: &[Food]) -> Result<String, SolveError> {
     let all_ingredients: FxHashSet<String> = foods.iter().flat_map(|food| food.0.clone()).collect();
     let all_allergens: FxHashSet<String> = foods.iter().flat_map(|food| food.1.clone()).collect();
 
-    let canonical_list = get_allergens_mapping(&foods, &all_ingredients, &all_allergens)
+    let canonical_list = get_allergens_mapping(foods, &all_ingredients, &all_allergens)?
         .iter()
         .sorted_by(|kvp_1, kvp_2| kvp_1.1.partial_cmp(kvp_2.1).unwrap())
         .map(|(key, _)| key)
         .join(",");
 
-    canonical_list
+    Ok(canonical_list)
+}
+
+const ALLERGENS: [&str; 8] = [
+    "dairy", "eggs", "fish", "nuts", "peanuts", "sesame", "soy", "wheat",
+];
+
+fn ingredient_name(rng: &mut Rng) -> String {
+    (0..rng.range(4..=8))
+        .map(|_| (b'a' + rng.below(26) as u8) as char)
+        .collect()
+}
+
+// Foods are added until every allergen's foods share only its own ingredient, so the allergens can be told apart.
+pub fn generate(rng: &mut Rng, size: usize) -> Result<Generated, String> {
+    let mut allergens = ALLERGENS.to_vec();
+    rng.shuffle(&mut allergens);
+    allergens.truncate(rng.range(3..=8) as usize);
+    allergens.sort();
+
+    let mut names = BTreeSet::new();
+    while names.len() < allergens.len() + 3 * size.max(10) {
+        names.insert(ingredient_name(rng));
+    }
+    let mut names: Vec<String> = names.into_iter().collect();
+    rng.shuffle(&mut names);
+    let (dangerous, safe) = names.split_at(allergens.len());
+
+    let food = |rng: &mut Rng, listed: Vec<usize>| -> (Vec<String>, Vec<usize>) {
+        let mut ingredients: Vec<String> = (0..allergens.len())
+            .filter(|allergen| listed.contains(allergen) || rng.chance(1, 4))
+            .map(|allergen| dangerous[allergen].clone())
+            .collect();
+
+        let mut safe = safe.to_vec();
+        rng.shuffle(&mut safe);
+        ingredients.extend(safe.into_iter().take(rng.range(5..=15) as usize));
+        rng.shuffle(&mut ingredients);
+
+        (ingredients, listed)
+    };
+
+    let mut foods: Vec<(Vec<String>, Vec<usize>)> = (0..size)
+        .map(|_| {
+            let mut listed: Vec<usize> = (0..allergens.len()).collect();
+            rng.shuffle(&mut listed);
+            listed.truncate(rng.range(0..=3) as usize);
+            listed.sort();
+            food(rng, listed)
+        })
+        .collect();
+
+    for allergen in 0..allergens.len() {
+        for attempt in 0.. {
+            let listing: Vec<&Vec<String>> = foods
+                .iter()
+                .filter(|(_, listed)| listed.contains(&allergen))
+                .map(|(ingredients, _)| ingredients)
+                .collect();
+            let shared = listing.first().map_or(0, |first| {
+                first
+                    .iter()
+                    .filter(|name| listing.iter().all(|food| food.contains(name)))
+                    .count()
+            });
+
+            if shared == 1 {
+                break;
+            }
+            if attempt == 100 {
+                return Err(String::from("Cannot tell the allergens apart"));
+            }
+            foods.push(food(rng, vec![allergen]));
+        }
+    }
+    rng.shuffle(&mut foods);
+
+    let safe_appearances = foods
+        .iter()
+        .flat_map(|(ingredients, _)| ingredients)
+        .filter(|ingredient| !dangerous.contains(ingredient))
+        .count();
+
+    let input = foods
+        .iter()
+        .map(|(ingredients, listed)| match listed.len() {
+            0 => format!("{}\n", ingredients.join(" ")),
+            _ => format!(
+                "{} (contains {})\n",
+                ingredients.join(" "),
+                listed
+                    .iter()
+                    .map(|allergen| allergens[*allergen])
+                    .join(", ")
+            ),
+        })
+        .collect();
+
+    Ok(Generated::new(
+        input,
+        Some(Answer::from(safe_appearances)),
+        Some(Answer::Text(dangerous.join(","))),
+    ))
+}
+
+pub struct Day21;
+
+impl Solution for Day21 {
+    const DAY: u32 = 21;
+
+    type Input = Vec<Food>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_data(input)
+    }
+
+    fn part_1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_1(input)?.into())
+    }
+
+    fn part_2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_2(input)?.into())
+    }
 }
 
 #[cfg(test)]
@@ -134,19 +274,36 @@ mod tests {
     use super::*;
     use rstest::rstest;
 
-    use crate::utilities::file_utilities::get_file_path;
+    use crate::utilities::input::puzzle_input;
+
+    #[rstest]
+    #[case("(contains dairy)", 1, "Expected an ingredient")]
+    #[case("sqjhc (dairy)", 8, "Expected `contains`")]
+    #[case("sqjhc (contains dairy, )", 24, "Unexpected `)`")]
+    #[case("sqjhc (contains dairy", 22, "Expected `)`")]
+    fn test_parse_line_reports_span(
+        #[case] line: &str,
+        #[case] column: usize,
+        #[case] message: &str,
+    ) {
+        let error = parse_line(line).unwrap_err();
+
+        assert_eq!((column, message), (error.column, error.message.as_str()));
+    }
 
     #[rstest]
     #[case(true, 5)]
     #[case(false, 2573)]
     fn test_part_1(#[case] is_test: bool, #[case] expected: i64) {
-        assert_eq!(expected, part_1(get_file_path(is_test, 21, None)));
+        let foods = parse_data(&puzzle_input(is_test, 21, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_1(&foods).unwrap());
     }
 
     #[rstest]
     #[case(true, "mxmxvkd,sqjhc,fvjkl")]
     #[case(false, "bjpkhx,nsnqf,snhph,zmfqpn,qrbnjtj,dbhfd,thn,sthnsg")]
     fn test_part_2(#[case] is_test: bool, #[case] expected: String) {
-        assert_eq!(expected, part_2(get_file_path(is_test, 21, None)));
+        let foods = parse_data(&puzzle_input(is_test, 21, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_2(&foods).unwrap());
     }
 }
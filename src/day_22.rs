@@ -2,164 +2,365 @@ use itertools::Itertools;
 use std::cmp::Ordering;
 use std::collections::VecDeque;
 
-use crate::utilities::file_utilities::read_lines;
+use nom::combinator::cut;
+use nom::sequence::delimited;
+
+use crate::error::{parse_records, ParseError, SolveError};
+use crate::generate::Generated;
+use crate::observer::{self, Event};
+use crate::repl::{count_argument, Session};
+use crate::solution::{Answer, Solution};
+use crate::utilities::file_utilities::Record;
+use crate::utilities::grammar::{number, parse_all, token, ws, GResult};
+use crate::utilities::random::Rng;
 use rustc_hash::FxHashSet;
 
-fn parse_cluster(line: String) -> Vec<usize> {
-    let split_by_colon: Vec<&str> = line.split(':').map(str::trim).collect();
-    split_by_colon[1]
-        .split(' ')
-        .map(|s| s.parse::<usize>().unwrap())
-        .collect()
+// Player 1:
+fn player_header(input: &str) -> GResult<'_, usize> {
+    delimited(token("Player"), cut(ws(number)), cut(token(":")))(input)
 }
 
-fn parse_data(file_path: String) -> Vec<Vec<usize>> {
-    read_lines(file_path)
-        .into_iter()
-        .coalesce(|x, y| {
-            if x.is_empty() == y.is_empty() {
-                Ok(format!("{} {}", x, y))
-            } else {
-                Err((x, y))
-            }
-        })
-        .filter(|s| !s.is_empty())
-        .map(parse_cluster)
+fn parse_cluster(record: &Record) -> Result<Vec<usize>, ParseError> {
+    parse_all(record.lines[0], player_header)?;
+
+    (record.line + 1..)
+        .zip(record.lines[1..].iter())
+        .map(|(line, card)| parse_all(card, ws(number)).map_err(|error| error.with_line(line)))
         .collect()
 }
 
-pub fn part_1(file_path: String) -> i64 {
-    let decks = parse_data(file_path);
-    let mut player_1_deck: VecDeque<usize> = decks[0].iter().cloned().collect();
-    let mut player_2_deck: VecDeque<usize> = decks[1].iter().cloned().collect();
+pub fn parse_data(input: &str) -> Result<Vec<Vec<usize>>, ParseError> {
+    parse_records(input, parse_cluster)
+}
 
-    let mut round = 0;
+fn deal(decks: &[Vec<usize>]) -> Result<(VecDeque<usize>, VecDeque<usize>), SolveError> {
+    match decks {
+        [player_1_deck, player_2_deck] => Ok((
+            player_1_deck.iter().cloned().collect(),
+            player_2_deck.iter().cloned().collect(),
+        )),
+        _ => Err(SolveError::NoSolution(format!(
+            "Expected 2 decks, found {}",
+            decks.len()
+        ))),
+    }
+}
 
-    while !player_1_deck.is_empty() && !player_2_deck.is_empty() {
-        round += 1;
-        // println!("Round {round}: Player 1 deck is {player_1_deck:?}");
-        // println!("Round {round}: Player 2 deck is {player_2_deck:?}");
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Combat {
+    pub decks: [VecDeque<usize>; 2],
+    pub round: usize,
+    recursive: bool,
+    game_level: usize,
+    history: FxHashSet<(String, String)>,
+    winner: Option<usize>,
+}
 
-        let Some(player_1_card) = player_1_deck.pop_front() else {
-            panic!("Player 1 pop")
-        };
-        let Some(player_2_card) = player_2_deck.pop_front() else {
-            panic!("Player 2 pop")
-        };
-
-        match player_1_card.cmp(&player_2_card) {
-            Ordering::Greater => {
-                player_1_deck.push_back(player_1_card);
-                player_1_deck.push_back(player_2_card);
-            }
-            Ordering::Less => {
-                player_2_deck.push_back(player_2_card);
-                player_2_deck.push_back(player_1_card);
-            }
-            Ordering::Equal => panic!("Round {round}: This should not happen!"),
+impl Combat {
+    pub fn new(
+        (player_1_deck, player_2_deck): (VecDeque<usize>, VecDeque<usize>),
+        recursive: bool,
+    ) -> Self {
+        let mut combat = Self {
+            decks: [player_1_deck, player_2_deck],
+            round: 0,
+            recursive,
+            game_level: 1,
+            history: FxHashSet::default(),
+            winner: None,
         };
+        combat.winner = combat.empty_deck_winner();
+        combat
     }
 
-    let winning_deck = if player_1_deck.is_empty() {
-        player_2_deck
-    } else {
-        player_1_deck
-    };
+    fn empty_deck_winner(&self) -> Option<usize> {
+        match (self.decks[0].is_empty(), self.decks[1].is_empty()) {
+            (_, true) => Some(1),
+            (true, false) => Some(2),
+            (false, false) => None,
+        }
+    }
 
-    // println!("Winning deck is {winning_deck:?}");
+    // The winning player, 1 or 2, once the game is over.
+    pub fn winner(&self) -> Option<usize> {
+        self.winner
+    }
 
-    winning_deck
-        .into_iter()
-        .rev()
-        .enumerate()
-        .map(|(i, card)| (card * (i + 1)) as i64)
-        .sum()
-}
+    pub fn play_round(&mut self) -> Result<Option<usize>, SolveError> {
+        if self.winner.is_some() {
+            return Ok(self.winner);
+        }
 
-fn recursive_game(
-    player_1_deck: &mut VecDeque<usize>,
-    player_2_deck: &mut VecDeque<usize>,
-    _game_level: usize,
-    // history?
-) -> bool {
-    // Player 1 wins
-    let mut history: FxHashSet<(String, String)> = FxHashSet::default();
-    let mut _round = 0;
-
-    // println!("=== Game {game_level} ===");
-
-    while !player_1_deck.is_empty() && !player_2_deck.is_empty() {
-        _round += 1;
-
-        // println!("-- Round {round} (Game {game_level}) --");
-        // println!("Player 1's deck: {player_1_deck:?}");
-        // println!("Player 2's deck: {player_2_deck:?}");
-
-        // This prevents infinite games of Recursive Combat, which everyone agrees is a bad idea.
-        let key = (
-            player_1_deck.iter().join("-"),
-            player_2_deck.iter().join("-"),
-        );
-        if history.contains(&key) {
-            // println!("History repeats itself, player 1 wins game {game_level}");
-            return true;
+        if self.recursive {
+            // This prevents infinite games of Recursive Combat, which everyone agrees is a bad idea.
+            let key = (
+                self.decks[0].iter().join("-"),
+                self.decks[1].iter().join("-"),
+            );
+            if !self.history.insert(key) {
+                return Ok(self.finish(1));
+            }
         }
 
-        history.insert(key);
+        self.round += 1;
+        let round = self.round;
 
-        let Some(player_1_card) = player_1_deck.pop_front() else {
-            panic!("Player 1 pop")
-        };
-        let Some(player_2_card) = player_2_deck.pop_front() else {
-            panic!("Player 2 pop")
+        let [player_1_deck, player_2_deck] = &mut self.decks;
+        let (Some(player_1_card), Some(player_2_card)) =
+            (player_1_deck.pop_front(), player_2_deck.pop_front())
+        else {
+            unreachable!("Both players hold cards until the game is won")
         };
 
-        // println!("Player 1 plays: {player_1_card}");
-        // println!("Player 2 plays: {player_2_card}");
-
-        let player_1_win =
-            if player_1_deck.len() >= player_1_card && player_2_deck.len() >= player_2_card {
-                // println!("Playing a sub-game to determine the winner...");
-                let mut player_1_clone: VecDeque<usize> =
-                    player_1_deck.iter().cloned().take(player_1_card).collect();
-                let mut player_2_clone: VecDeque<usize> =
-                    player_2_deck.iter().cloned().take(player_2_card).collect();
-                recursive_game(&mut player_1_clone, &mut player_2_clone, _game_level + 1)
-            } else {
-                player_1_card > player_2_card
-            };
+        let player_1_win = if self.recursive
+            && player_1_deck.len() >= player_1_card
+            && player_2_deck.len() >= player_2_card
+        {
+            let mut sub_game = Combat::new(
+                (
+                    player_1_deck.iter().cloned().take(player_1_card).collect(),
+                    player_2_deck.iter().cloned().take(player_2_card).collect(),
+                ),
+                true,
+            );
+            sub_game.game_level = self.game_level + 1;
+            sub_game.play()? == 1
+        } else {
+            match player_1_card.cmp(&player_2_card) {
+                Ordering::Greater => true,
+                Ordering::Less => false,
+                Ordering::Equal => {
+                    return Err(SolveError::NoSolution(format!(
+                        "Round {round}: both players drew {player_1_card}"
+                    )))
+                }
+            }
+        };
 
         if player_1_win {
-            // println!("Player 1 wins round {round} of game {game_level}!");
             player_1_deck.push_back(player_1_card);
             player_1_deck.push_back(player_2_card);
         } else {
-            // println!("Player 2 wins round {round} of game {game_level}!");
             player_2_deck.push_back(player_2_card);
             player_2_deck.push_back(player_1_card);
+        }
+
+        observer::emit(|| Event::RoundPlayed {
+            game_level: self.game_level,
+            round,
+            cards: [player_1_card, player_2_card],
+            winner: if player_1_win { 1 } else { 2 },
+        });
+
+        match self.empty_deck_winner() {
+            Some(winner) => Ok(self.finish(winner)),
+            None => Ok(None),
+        }
+    }
+
+    fn finish(&mut self, winner: usize) -> Option<usize> {
+        observer::emit(|| Event::GameWon {
+            game_level: self.game_level,
+            winner,
+            rounds: self.round,
+        });
+
+        self.winner = Some(winner);
+        self.winner
+    }
+
+    pub fn play(&mut self) -> Result<usize, SolveError> {
+        loop {
+            if let Some(winner) = self.play_round()? {
+                return Ok(winner);
+            }
+        }
+    }
+
+    pub fn score(&self, player: usize) -> i64 {
+        self.decks[player - 1]
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(i, card)| (card * (i + 1)) as i64)
+            .sum()
+    }
+}
+
+pub fn part_1(decks: &[Vec<usize>]) -> Result<i64, SolveError> {
+    let mut combat = Combat::new(deal(decks)?, false);
+    let winner = combat.play()?;
+
+    Ok(combat.score(winner))
+}
+
+pub fn part_2(decks: &[Vec<usize>]) -> Result<i64, SolveError> {
+    let mut combat = Combat::new(deal(decks)?, true);
+    let winner = combat.play()?;
+
+    Ok(combat.score(winner))
+}
+
+// Games that run this long are probably stuck in a loop, so the decks are dealt again.
+const MAX_ROUNDS: usize = 100_000;
+
+fn play_combat(
+    mut player_1_deck: VecDeque<usize>,
+    mut player_2_deck: VecDeque<usize>,
+) -> Option<usize> {
+    for _ in 0..MAX_ROUNDS {
+        let (Some(player_1_card), Some(player_2_card)) =
+            (player_1_deck.front(), player_2_deck.front())
+        else {
+            let winning_deck = player_1_deck.iter().chain(player_2_deck.iter());
+            return Some(
+                winning_deck
+                    .rev()
+                    .enumerate()
+                    .map(|(i, card)| card * (i + 1))
+                    .sum(),
+            );
         };
+        let (player_1_card, player_2_card) = (*player_1_card, *player_2_card);
+        player_1_deck.pop_front();
+        player_2_deck.pop_front();
+
+        if player_1_card > player_2_card {
+            player_1_deck.extend([player_1_card, player_2_card]);
+        } else {
+            player_2_deck.extend([player_2_card, player_1_card]);
+        }
     }
 
-    !player_1_deck.is_empty()
+    None
 }
 
-pub fn part_2(file_path: String) -> i64 {
-    let decks = parse_data(file_path);
-    let mut player_1_deck: VecDeque<usize> = decks[0].iter().cloned().collect();
-    let mut player_2_deck: VecDeque<usize> = decks[1].iter().cloned().collect();
+pub fn generate(rng: &mut Rng, size: usize) -> Result<Generated, String> {
+    if size == 0 {
+        return Err(String::from("Each deck needs at least one card"));
+    }
 
-    let winner = recursive_game(&mut player_1_deck, &mut player_2_deck, 1);
+    loop {
+        let mut cards: Vec<usize> = (1..=2 * size).collect();
+        rng.shuffle(&mut cards);
+        let (player_1_deck, player_2_deck) = cards.split_at(size);
 
-    let winning_deck = if winner { player_1_deck } else { player_2_deck };
+        if let Some(score) = play_combat(
+            player_1_deck.iter().copied().collect(),
+            player_2_deck.iter().copied().collect(),
+        ) {
+            let input = format!(
+                "Player 1:\n{}\n\nPlayer 2:\n{}\n",
+                player_1_deck.iter().join("\n"),
+                player_2_deck.iter().join("\n")
+            );
+            return Ok(Generated::new(input, Some(Answer::from(score)), None));
+        }
+    }
+}
 
-    // println!("Winning deck is {winning_deck:?}");
+// Plays Combat round by round, recursive or not; sub-games are played out within a round.
+pub struct CombatSession {
+    decks: Vec<Vec<usize>>,
+    combat: Result<Combat, String>,
+}
 
-    winning_deck
-        .into_iter()
-        .rev()
-        .enumerate()
-        .map(|(i, card)| (card * (i + 1)) as i64)
-        .sum()
+impl CombatSession {
+    pub fn new(decks: &[Vec<usize>]) -> Self {
+        Self {
+            decks: decks.to_vec(),
+            combat: Self::deal(decks, false),
+        }
+    }
+
+    fn deal(decks: &[Vec<usize>], recursive: bool) -> Result<Combat, String> {
+        deal(decks)
+            .map(|decks| Combat::new(decks, recursive))
+            .map_err(|error| error.to_string())
+    }
+
+    fn state(combat: &Combat) -> String {
+        let decks = format!(
+            "Player 1's deck: {}\nPlayer 2's deck: {}",
+            combat.decks[0].iter().join(", "),
+            combat.decks[1].iter().join(", ")
+        );
+
+        match combat.winner() {
+            Some(winner) => format!(
+                "Player {winner} won after {} rounds with a score of {}\n{decks}",
+                combat.round,
+                combat.score(winner)
+            ),
+            None => format!("After round {}\n{decks}", combat.round),
+        }
+    }
+}
+
+impl Session for CombatSession {
+    fn commands(&self) -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("state", "Show both decks"),
+            ("step [count]", "Play one or more rounds"),
+            ("play", "Play until someone wins"),
+            (
+                "reset [recursive]",
+                "Deal again, for Combat or Recursive Combat",
+            ),
+        ]
+    }
+
+    fn execute(&mut self, command: &str, arguments: &[&str]) -> Option<Result<String, String>> {
+        if command == "reset" {
+            return Some(match arguments {
+                [] | ["recursive"] => {
+                    self.combat = Self::deal(&self.decks, !arguments.is_empty());
+                    self.combat.as_ref().map(Self::state).map_err(Clone::clone)
+                }
+                _ => Err(String::from("Usage: reset [recursive]")),
+            });
+        }
+
+        let combat = match &mut self.combat {
+            Ok(combat) => combat,
+            Err(error) => return Some(Err(error.clone())),
+        };
+
+        Some(match command {
+            "state" => Ok(Self::state(combat)),
+            "step" => count_argument(arguments).and_then(|count| {
+                for _ in 0..count {
+                    combat.play_round().map_err(|error| error.to_string())?;
+                }
+                Ok(Self::state(combat))
+            }),
+            "play" => combat
+                .play()
+                .map(|_| Self::state(combat))
+                .map_err(|error| error.to_string()),
+            _ => return None,
+        })
+    }
+}
+
+pub struct Day22;
+
+impl Solution for Day22 {
+    const DAY: u32 = 22;
+
+    type Input = Vec<Vec<usize>>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_data(input)
+    }
+
+    fn part_1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_1(input)?.into())
+    }
+
+    fn part_2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_2(input)?.into())
+    }
 }
 
 #[cfg(test)]
@@ -167,19 +368,38 @@ mod tests {
     use super::*;
     use rstest::rstest;
 
-    use crate::utilities::file_utilities::get_file_path;
+    use crate::utilities::input::puzzle_input;
 
     #[rstest]
     #[case(true, 306)]
     #[case(false, 32856)]
     fn test_part_1(#[case] is_test: bool, #[case] expected: i64) {
-        assert_eq!(expected, part_1(get_file_path(is_test, 22, None)));
+        let decks = parse_data(&puzzle_input(is_test, 22, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_1(&decks).unwrap());
     }
 
     #[rstest]
     #[case(true, 291)]
     #[case(false, 33805)]
     fn test_part_2(#[case] is_test: bool, #[case] expected: i64) {
-        assert_eq!(expected, part_2(get_file_path(is_test, 22, None)));
+        let decks = parse_data(&puzzle_input(is_test, 22, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_2(&decks).unwrap());
+    }
+
+    #[rstest]
+    #[case(&["step 2"], "After round 2\nPlayer 1's deck: 6, 3, 1, 9, 5\nPlayer 2's deck: 4, 7, 10, 8, 2")]
+    #[case(&["play"], "Player 2 won after 29 rounds with a score of 306")]
+    #[case(&["reset recursive", "play"], "Player 2 won after 17 rounds with a score of 291")]
+    fn test_combat_session(#[case] commands: &[&str], #[case] expected: &str) {
+        let decks = parse_data(&puzzle_input(true, 22, None).read().unwrap()).unwrap();
+        let mut session = CombatSession::new(&decks);
+
+        let mut reply = Ok(String::new());
+        for command in commands {
+            let words: Vec<&str> = command.split_whitespace().collect();
+            reply = session.execute(words[0], &words[1..]).unwrap();
+        }
+
+        assert!(reply.unwrap().starts_with(expected));
     }
 }
@@ -1,4 +1,40 @@
 use itertools::Itertools;
+use nom::character::complete::satisfy;
+use nom::combinator::map;
+use nom::error::context;
+use nom::multi::many1;
+
+use crate::error::{parse_single_line, ParseError, SolveError};
+use crate::generate::Generated;
+use crate::observer::{self, Event};
+use crate::solution::{Answer, Solution};
+use crate::utilities::binary::{from_bytes, to_bytes};
+use crate::utilities::grammar::{parse_all, ws, GResult};
+use crate::utilities::random::Rng;
+
+fn cups(input: &str) -> GResult<'_, Vec<usize>> {
+    let cup = map(satisfy(|c| c.is_ascii_digit()), |c| {
+        usize::from(c as u8 - b'0')
+    });
+
+    context("a cup label", many1(cup))(input)
+}
+
+pub fn parse_data(input: &str) -> Result<Vec<usize>, ParseError> {
+    parse_single_line(input, |line| {
+        let cups = parse_all(line, ws(cups))?;
+
+        if !cups.iter().sorted().copied().eq(1..=9) {
+            return Err(ParseError::new(
+                line,
+                1,
+                "Expected each of the cups 1 to 9 exactly once",
+            ));
+        }
+
+        Ok(cups)
+    })
+}
 
 fn get_resulting_cups(right_neighbour: &[usize], max_cup: usize) -> Vec<usize> {
     let mut current_cup = right_neighbour[1];
@@ -30,12 +66,7 @@ fn simulate_moves(cups: &[usize], max_cup: usize, moves_count: usize) -> Vec<usi
 
     let mut current_cup = cups[0];
 
-    for _move in 1..=moves_count {
-        // println!("-- move {_move} --");
-        // println!("right_neighbour: {:?}", right_neighbour);
-        // println!("cups: {}", get_resulting_cups(&right_neighbour, max_cup).iter().join(" "));
-
-        // println!("current cup: {current_cup}");
+    for move_number in 1..=moves_count {
         let mut current_for_pickup = current_cup;
         let picked_up: Vec<usize> = (0..3)
             .map(|_| {
@@ -45,15 +76,18 @@ fn simulate_moves(cups: &[usize], max_cup: usize, moves_count: usize) -> Vec<usi
             })
             .collect();
 
-        // println!("picked up: {picked_up:?}");
-
         let mut destination_cup = decrement_cup_circular(current_cup, max_cup);
 
         while picked_up.contains(&destination_cup) {
             destination_cup = decrement_cup_circular(destination_cup, max_cup);
         }
 
-        // println!("destination cup: {destination_cup}");
+        observer::emit(|| Event::CupsMoved {
+            move_number,
+            current_cup,
+            picked_up: [picked_up[0], picked_up[1], picked_up[2]],
+            destination_cup,
+        });
 
         let destination_cup_right_neighbour = right_neighbour[destination_cup];
         right_neighbour[destination_cup] = picked_up[0];
@@ -66,42 +100,21 @@ fn simulate_moves(cups: &[usize], max_cup: usize, moves_count: usize) -> Vec<usi
     get_resulting_cups(&right_neighbour, max_cup)
 }
 
-pub fn part_1(cups_data: String) -> String {
-    let cups: Vec<usize> = cups_data
-        .chars()
-        .map(|c| c.to_digit(10).unwrap())
-        .map(|i| i as usize)
-        .collect();
-
-    let moved_cups = simulate_moves(&cups, 9, 100);
-    // println!("The cups are: {moved_cups:?}");
+pub fn part_1(cups: &[usize]) -> Result<String, SolveError> {
+    let moved_cups = simulate_moves(cups, 9, 100);
 
     let one_index = moved_cups.iter().position(|c| *c == 1).unwrap();
-    moved_cups
+    Ok(moved_cups
         .iter()
         .skip(one_index)
         .chain(moved_cups.iter().take(one_index))
         .skip(1)
-        .join("")
+        .join(""))
 }
 
-pub fn part_2(cups_data: String) -> i64 {
-    let mut cups: Vec<usize> = cups_data
-        .chars()
-        .map(|c| c.to_digit(10).unwrap())
-        .map(|i| i as usize)
-        .collect();
-
+pub fn part_2(cups: &[usize]) -> Result<i64, SolveError> {
+    let mut cups = cups.to_vec();
     cups.extend(10..=1_000_000);
-    // println!(
-    //     "I have {} cups and the max cup is {}",
-    //     cups.len(),
-    //     cups.iter().max().unwrap()
-    // );
-    // println!(
-    //     "The cups are: {:?} ...",
-    //     cups.iter().take(20).cloned().collect::<Vec<usize>>()
-    // );
 
     let moved_cups = simulate_moves(&cups, 1_000_000, 10_000_000);
 
@@ -117,25 +130,132 @@ pub fn part_2(cups_data: String) -> i64 {
         next_clockwise + 1
     };
 
-    (moved_cups[next_clockwise] as i64) * (moved_cups[next_next_clockwise] as i64)
+    Ok((moved_cups[next_clockwise] as i64) * (moved_cups[next_next_clockwise] as i64))
+}
+
+// The slow but obvious version of `simulate_moves`: the circle is a Vec that keeps the current cup at the front.
+fn simulate_moves_naive(cups: &[usize], moves_count: usize) -> Vec<usize> {
+    let max_cup = cups.len();
+    let mut circle = cups.to_vec();
+
+    for _ in 0..moves_count {
+        let current_cup = circle[0];
+        let picked_up: Vec<usize> = circle.drain(1..4).collect();
+
+        let mut destination_cup = decrement_cup_circular(current_cup, max_cup);
+        while picked_up.contains(&destination_cup) {
+            destination_cup = decrement_cup_circular(destination_cup, max_cup);
+        }
+        let position = circle
+            .iter()
+            .position(|cup| *cup == destination_cup)
+            .unwrap();
+
+        circle.splice(position + 1..position + 1, picked_up);
+        circle.rotate_left(1);
+    }
+
+    // Same order as `simulate_moves`: clockwise from the cup after cup 1.
+    let one_index = circle.iter().position(|cup| *cup == 1).unwrap();
+    circle.rotate_left(one_index + 1);
+    circle
+}
+
+// The size is fixed by the puzzle: the cups are always labelled 1 to 9.
+pub fn generate(rng: &mut Rng) -> Result<Generated, String> {
+    let mut cups: Vec<usize> = (1..=9).collect();
+    rng.shuffle(&mut cups);
+
+    let labels = simulate_moves_naive(&cups, 100)[..8].iter().join("");
+    let input = format!("{}\n", cups.iter().join(""));
+
+    Ok(Generated::new(input, Some(Answer::Text(labels)), None))
+}
+
+pub struct Day23;
+
+impl Solution for Day23 {
+    const DAY: u32 = 23;
+
+    type Input = Vec<usize>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_data(input)
+    }
+
+    fn part_1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_1(input)?.into())
+    }
+
+    fn part_2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_2(input)?.into())
+    }
+
+    fn encode(input: &Self::Input) -> Option<Vec<u8>> {
+        Some(to_bytes(input))
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self::Input> {
+        from_bytes(bytes)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utilities::property::{agree, shrink_number, Property};
+    use crate::utilities::random::Rng;
     use rstest::rstest;
 
+    fn random_game(rng: &mut Rng) -> (Vec<usize>, usize) {
+        let mut cups: Vec<usize> = (1..=rng.range(5..=12) as usize).collect();
+        rng.shuffle(&mut cups);
+        (cups, rng.range(0..=60) as usize)
+    }
+
+    // Dropping the highest cup keeps the labels running from 1 to the number of cups.
+    fn shrink_game((cups, moves_count): &(Vec<usize>, usize)) -> Vec<(Vec<usize>, usize)> {
+        let mut smaller = vec![];
+
+        if cups.len() > 5 {
+            let fewer = cups
+                .iter()
+                .copied()
+                .filter(|cup| *cup != cups.len())
+                .collect();
+            smaller.push((fewer, *moves_count));
+        }
+
+        smaller.extend(
+            shrink_number(*moves_count)
+                .into_iter()
+                .map(|moves_count| (cups.clone(), moves_count)),
+        );
+
+        smaller
+    }
+
+    #[test]
+    fn test_simulate_moves_matches_naive() {
+        Property::new().check(random_game, shrink_game, |(cups, moves_count)| {
+            agree(
+                simulate_moves(cups, cups.len(), *moves_count),
+                simulate_moves_naive(cups, *moves_count),
+            )
+        });
+    }
+
     #[rstest]
     #[case("389125467", "67384529")]
     #[case("157623984", "58427369")]
     fn test_part_1(#[case] input: String, #[case] expected: String) {
-        assert_eq!(expected, part_1(input));
+        assert_eq!(expected, part_1(&parse_data(&input).unwrap()).unwrap());
     }
 
     #[rstest]
     #[case("389125467", 149245887792)]
     #[case("157623984", 111057672960)]
     fn test_part_2(#[case] input: String, #[case] expected: i64) {
-        assert_eq!(expected, part_2(input));
+        assert_eq!(expected, part_2(&parse_data(&input).unwrap()).unwrap());
     }
 }
@@ -1,7 +1,20 @@
-use crate::utilities::file_utilities::read_lines;
-
+use crate::error::{parse_lines, ParseError, SolveError};
+use crate::generate::Generated;
+use crate::solution::{Answer, Solution};
+use crate::utilities::automaton::{Automaton, Rule};
+use crate::utilities::grammar::{parse_all, GResult};
+use crate::utilities::image::{self, Point, Scene, Shape};
+use crate::utilities::random::Rng;
+
+use itertools::Itertools;
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::combinator::value;
+use nom::error::context;
+use nom::multi::many1;
 use num::complex::Complex32;
 use rustc_hash::{FxHashMap, FxHashSet};
+use std::collections::BTreeMap;
 use std::hash::{Hash, Hasher};
 use std::ops;
 
@@ -44,8 +57,8 @@ enum Color {
     Black,
 }
 
-#[derive(Clone, Debug, Hash, PartialEq)]
-enum Neighbor {
+#[derive(Clone, Copy, Debug, Hash, PartialEq)]
+pub enum Neighbor {
     East,
     SouthEast,
     SouthWest,
@@ -65,39 +78,38 @@ fn get_neighbour_complex(direction: Neighbor) -> HexComplex {
     }
 }
 
-fn get_direction_enum(direction: &str) -> Option<Neighbor> {
-    match direction {
-        "e" => Some(Neighbor::East),
-        "se" => Some(Neighbor::SouthEast),
-        "sw" => Some(Neighbor::SouthWest),
-        "w" => Some(Neighbor::West),
-        "nw" => Some(Neighbor::NorthWest),
-        "ne" => Some(Neighbor::NorthEast),
-        _ => None,
-    }
+fn direction(input: &str) -> GResult<'_, Neighbor> {
+    context(
+        "one of e, se, sw, w, nw or ne",
+        alt((
+            value(Neighbor::East, tag("e")),
+            value(Neighbor::SouthEast, tag("se")),
+            value(Neighbor::SouthWest, tag("sw")),
+            value(Neighbor::West, tag("w")),
+            value(Neighbor::NorthWest, tag("nw")),
+            value(Neighbor::NorthEast, tag("ne")),
+        )),
+    )(input)
 }
 
-fn get_tile_configuration(flip_directions: Vec<String>) -> FxHashMap<HexComplex, Color> {
+fn parse_line(line: &str) -> Result<Vec<Neighbor>, ParseError> {
+    parse_all(line, many1(direction))
+}
+
+pub fn parse_data(input: &str) -> Result<Vec<Vec<Neighbor>>, ParseError> {
+    parse_lines(input, parse_line)
+}
+
+fn get_tile_configuration(flip_directions: &[Vec<Neighbor>]) -> FxHashMap<HexComplex, Color> {
     let mut tiles: FxHashMap<HexComplex, Color> = FxHashMap::default();
 
     tiles.insert(HexComplex::new(0, 0), Color::White);
 
-    for flip_direction in flip_directions.clone().into_iter() {
-        let mut index = 0;
+    for flip_direction in flip_directions.iter() {
         let mut current_tile = HexComplex::new(0, 0);
 
-        while index < flip_direction.len() {
-            let new_direction =
-                if let Some(direction) = get_direction_enum(&flip_direction[index..=index]) {
-                    index += 1;
-                    direction
-                } else {
-                    let direction = get_direction_enum(&flip_direction[index..=index + 1]).unwrap();
-                    index += 2;
-                    direction
-                };
-
-            current_tile = current_tile + get_neighbour_complex(new_direction);
+        for direction in flip_direction.iter() {
+            current_tile = current_tile + get_neighbour_complex(*direction);
             tiles.entry(current_tile).or_insert(Color::White);
         }
 
@@ -110,15 +122,15 @@ fn get_tile_configuration(flip_directions: Vec<String>) -> FxHashMap<HexComplex,
     tiles
 }
 
-pub fn part_1(file_path: String) -> i64 {
-    let flip_directions: Vec<String> = read_lines(file_path);
-
+pub fn part_1(flip_directions: &[Vec<Neighbor>]) -> Result<i64, SolveError> {
     let tile_configuration = get_tile_configuration(flip_directions);
 
-    tile_configuration
+    let black_tiles = tile_configuration
         .values()
         .filter(|v| **v == Color::Black)
-        .count() as i64
+        .count();
+
+    Ok(black_tiles as i64)
 }
 
 fn get_neighbours(tile: &HexComplex) -> Vec<HexComplex> {
@@ -135,58 +147,172 @@ fn get_neighbours(tile: &HexComplex) -> Vec<HexComplex> {
     .collect::<Vec<_>>()
 }
 
-pub fn part_2(file_path: String) -> i64 {
-    let flip_directions: Vec<String> = read_lines(file_path);
+const DAYS: usize = 100;
+
+fn art_exhibit(flip_directions: &[Vec<Neighbor>]) -> Automaton<'static, HexComplex> {
     let tile_configuration = get_tile_configuration(flip_directions);
 
-    let mut black_tiles: FxHashSet<HexComplex> = tile_configuration
+    let black_tiles: Vec<HexComplex> = tile_configuration
         .into_iter()
         .filter(|(_, v)| *v == Color::Black)
         .map(|(m, _)| m)
         .collect();
 
-    for _day in 0..100 {
-        // println!("Day {_day}: {}", black_tiles.len());
-
-        let tile_to_investigate: FxHashSet<HexComplex> = black_tiles
-            .iter()
-            .flat_map(get_neighbours)
-            .chain(black_tiles.iter().cloned())
-            .collect();
-
-        let still_black_tiles: Vec<_> = tile_to_investigate
-            .iter()
-            .filter(|tile| black_tiles.contains(tile))
-            .filter(|tile| {
-                let active_neighbours_count = get_neighbours(tile)
-                    .iter()
-                    .filter(|neighbour| black_tiles.contains(neighbour))
-                    .count();
-                (1..=2).contains(&active_neighbours_count)
-            })
-            .cloned()
-            .collect();
-
-        let new_black_tiles: Vec<_> = tile_to_investigate
-            .iter()
-            .filter(|tile| !black_tiles.contains(tile))
-            .filter(|tile| {
-                let active_neighbours_count = get_neighbours(tile)
-                    .iter()
-                    .filter(|neighbour| black_tiles.contains(neighbour))
-                    .count();
-                active_neighbours_count == 2
+    Automaton::new(
+        black_tiles,
+        get_neighbours,
+        Rule {
+            birth: 2..=2,
+            survival: 1..=2,
+        },
+    )
+}
+
+pub fn part_2(flip_directions: &[Vec<Neighbor>]) -> Result<i64, SolveError> {
+    let mut automaton = art_exhibit(flip_directions);
+
+    automaton.run(DAYS);
+
+    Ok(automaton.population() as i64)
+}
+
+const BLACK_TILE: image::Color = image::Color(30, 30, 30);
+
+// Pointy topped hexagons of radius one, with north up and the reference tile outlined.
+fn draw_floor(black_tiles: &FxHashSet<HexComplex>) -> Scene {
+    let centre = |tile: &HexComplex| {
+        let (east, north_east) = (tile.0.re.round() as f64, tile.0.im.round() as f64);
+        (3f64.sqrt() * (east + north_east / 2.0), -1.5 * north_east)
+    };
+    let hexagon = |(x, y): Point| -> Vec<Point> {
+        (0..6)
+            .map(|corner| {
+                let angle = (30.0 + 60.0 * corner as f64).to_radians();
+                (x + angle.cos(), y + angle.sin())
             })
-            .cloned()
-            .collect();
+            .collect()
+    };
+
+    let reference = centre(&HexComplex::new(0, 0));
+    let centres: Vec<Point> = black_tiles.iter().map(centre).collect();
+    let (left, right) = centres
+        .iter()
+        .chain([&reference])
+        .map(|point| point.0)
+        .minmax()
+        .into_option()
+        .unwrap();
+    let (top, bottom) = centres
+        .iter()
+        .chain([&reference])
+        .map(|point| point.1)
+        .minmax()
+        .into_option()
+        .unwrap();
+    let to_scene = |(x, y): Point| (x - left + 2.0, y - top + 2.0);
+
+    let mut scene = Scene::new(right - left + 4.0, bottom - top + 4.0, image::Color::WHITE);
+    for point in centres {
+        scene.push(Shape::Polygon {
+            points: hexagon(to_scene(point)),
+            color: BLACK_TILE,
+        });
+    }
+
+    let mut outline = hexagon(to_scene(reference));
+    outline.push(outline[0]);
+    scene.push(Shape::Polyline {
+        points: outline,
+        color: image::Color(200, 30, 30),
+    });
 
-        black_tiles = still_black_tiles
-            .into_iter()
-            .chain(new_black_tiles.into_iter())
-            .collect();
+    scene
+}
+
+// Part 1 draws the floor as laid, part 2 the exhibit after a hundred days, or every day of it.
+pub fn render(
+    flip_directions: &[Vec<Neighbor>],
+    part: u32,
+    frames: bool,
+) -> Result<Vec<Scene>, SolveError> {
+    let mut automaton = art_exhibit(flip_directions);
+
+    match (part, frames) {
+        (1, _) => Ok(vec![draw_floor(automaton.active())]),
+        (_, true) => Ok(automaton.frames(DAYS, draw_floor)),
+        (_, false) => {
+            automaton.run(DAYS);
+            Ok(vec![draw_floor(automaton.active())])
+        }
     }
+}
+
+const RADIUS: i64 = 10;
+
+// Each line walks to a random tile; cancelling pairs of steps and a shuffle hide the target.
+pub fn generate(rng: &mut Rng, size: usize) -> Result<Generated, String> {
+    let mut flipped: BTreeMap<(i64, i64), bool> = BTreeMap::new();
+
+    let input = (0..size)
+        .map(|_| {
+            let east = rng.range(-RADIUS..=RADIUS);
+            let north_east = rng.range(-RADIUS..=RADIUS);
+            *flipped.entry((east, north_east)).or_default() ^= true;
+
+            let mut steps = vec![];
+            let toward = |count: i64, forward: &'static str, backward: &'static str| {
+                vec![if count > 0 { forward } else { backward }; count.unsigned_abs() as usize]
+            };
+            steps.extend(toward(east, "e", "w"));
+            steps.extend(toward(north_east, "ne", "sw"));
+
+            for _ in 0..rng.range(0..=4) {
+                let (forward, backward) = *rng.choose(&[("e", "w"), ("ne", "sw"), ("se", "nw")]);
+                steps.extend([forward, backward]);
+            }
+
+            // se is e followed by sw, so trade such pairs now and then.
+            while let (true, Some(e), Some(sw)) = (
+                rng.chance(1, 2),
+                steps.iter().position(|step| *step == "e"),
+                steps.iter().position(|step| *step == "sw"),
+            ) {
+                steps[e] = "se";
+                steps.remove(sw);
+            }
+
+            if steps.is_empty() {
+                steps.extend(["e", "w"]);
+            }
+            rng.shuffle(&mut steps);
+
+            format!("{}\n", steps.concat())
+        })
+        .collect();
 
-    black_tiles.len() as i64
+    let black_tiles = flipped.values().filter(|is_black| **is_black).count();
+
+    Ok(Generated::new(input, Some(Answer::from(black_tiles)), None))
+}
+
+pub struct Day24;
+
+impl Solution for Day24 {
+    const DAY: u32 = 24;
+
+    type Input = Vec<Vec<Neighbor>>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_data(input)
+    }
+
+    fn part_1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_1(input)?.into())
+    }
+
+    fn part_2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_2(input)?.into())
+    }
 }
 
 #[cfg(test)]
@@ -194,19 +320,48 @@ mod tests {
     use super::*;
     use rstest::rstest;
 
-    use crate::utilities::file_utilities::get_file_path;
+    use crate::utilities::input::puzzle_input;
+
+    #[rstest]
+    #[case("esenee", Ok(vec![Neighbor::East, Neighbor::SouthEast, Neighbor::NorthEast, Neighbor::East]))]
+    #[case("nwwswee", Ok(vec![Neighbor::NorthWest, Neighbor::West, Neighbor::SouthWest, Neighbor::East, Neighbor::East]))]
+    #[case("esn", Err(2))]
+    #[case("x", Err(1))]
+    fn test_parse_line(#[case] line: &str, #[case] expected: Result<Vec<Neighbor>, usize>) {
+        assert_eq!(expected, parse_line(line).map_err(|error| error.column));
+    }
 
     #[rstest]
     #[case(true, 10)]
     #[case(false, 282)]
     fn test_part_1(#[case] is_test: bool, #[case] expected: i64) {
-        assert_eq!(expected, part_1(get_file_path(is_test, 24, None)));
+        let flip_directions = parse_data(&puzzle_input(is_test, 24, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_1(&flip_directions).unwrap());
     }
 
     #[rstest]
     #[case(true, 2208)]
     #[case(false, 3445)]
     fn test_part_2(#[case] is_test: bool, #[case] expected: i64) {
-        assert_eq!(expected, part_2(get_file_path(is_test, 24, None)));
+        let flip_directions = parse_data(&puzzle_input(is_test, 24, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_2(&flip_directions).unwrap());
+    }
+
+    #[test]
+    fn test_render_frames() {
+        let flip_directions = parse_data(&puzzle_input(true, 24, None).read().unwrap()).unwrap();
+        let scenes = render(&flip_directions, 2, true).unwrap();
+        let tiles = |scene: &Scene| {
+            scene
+                .shapes
+                .iter()
+                .filter(|shape| matches!(shape, Shape::Polygon { .. }))
+                .count()
+        };
+
+        assert_eq!(DAYS + 1, scenes.len());
+        assert_eq!(10, tiles(&scenes[0]));
+        assert_eq!(15, tiles(&scenes[1]));
+        assert_eq!(2208, tiles(&scenes[DAYS]));
     }
 }
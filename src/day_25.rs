@@ -1,5 +1,40 @@
+use crate::error::{parse_lines, ParseError, SolveError};
+use crate::generate::Generated;
+use crate::observer::{self, Event};
+use crate::solution::{Answer, Solution};
+use crate::utilities::binary::{from_bytes, to_bytes};
+use crate::utilities::grammar::{number, parse_all, ws};
+use crate::utilities::random::Rng;
+
 const MODULO: u64 = 20201227;
 
+pub fn parse_data(input: &str) -> Result<(u64, u64), ParseError> {
+    let keys: Vec<u64> = parse_lines(input, |line| {
+        let key: u64 = parse_all(line, ws(number))?;
+
+        // Anything outside of this range is never produced by the handshake, so cracking it would never end.
+        if !(1..MODULO).contains(&key) {
+            return Err(ParseError::at(
+                line,
+                line.trim(),
+                format!("Public keys lie between 1 and {}", MODULO - 1),
+            ));
+        }
+
+        Ok(key)
+    })?;
+
+    match keys[..] {
+        [card_public_key, door_public_key] => Ok((card_public_key, door_public_key)),
+        _ => Err(ParseError::new(
+            input.lines().last().unwrap_or_default(),
+            1,
+            format!("Expected 2 public keys, found {}", keys.len()),
+        )
+        .with_line(keys.len())),
+    }
+}
+
 fn transform_once(value: u64, subject_number: u64) -> u64 {
     (value * subject_number) % MODULO
 }
@@ -22,7 +57,10 @@ fn hack_loop_size(public_key: u64) -> u64 {
         transformation = transform_once(transformation, 7);
 
         if transformation == public_key {
-            // println!("Found loop size {loop_size} for key {public_key}");
+            observer::emit(|| Event::LoopSizeFound {
+                public_key,
+                loop_size,
+            });
             return loop_size;
         }
 
@@ -30,7 +68,7 @@ fn hack_loop_size(public_key: u64) -> u64 {
     }
 }
 
-pub fn part_1(input: (u64, u64)) -> u64 {
+pub fn part_1(input: (u64, u64)) -> Result<u64, SolveError> {
     let card_public_key = input.0;
     let door_public_key = input.1;
 
@@ -41,10 +79,71 @@ pub fn part_1(input: (u64, u64)) -> u64 {
     let door_encryption_key = transform(card_public_key, door_loop_size);
 
     if card_encryption_key != door_encryption_key {
-        panic!("I think something is off with your keys");
+        return Err(SolveError::NoSolution(String::from(
+            "The card and the door disagree on the encryption key",
+        )));
     }
 
-    card_encryption_key
+    Ok(card_encryption_key)
+}
+
+fn modpow(mut base: u64, mut exponent: u64) -> u64 {
+    let mut result = 1;
+
+    while exponent > 0 {
+        if exponent % 2 == 1 {
+            result = result * base % MODULO;
+        }
+        base = base * base % MODULO;
+        exponent /= 2;
+    }
+
+    result
+}
+
+pub fn generate(rng: &mut Rng, size: usize) -> Result<Generated, String> {
+    // 7 generates every key, so each loop size below the modulus gives a different one.
+    if !(2..MODULO as usize - 1).contains(&size) {
+        return Err(format!(
+            "The largest loop size must lie between 2 and {}",
+            MODULO - 2
+        ));
+    }
+
+    let loop_sizes = rng.distinct(1..=size as i64, 2);
+    let (card_loop_size, door_loop_size) = (loop_sizes[0] as u64, loop_sizes[1] as u64);
+    let (card_public_key, door_public_key) = (modpow(7, card_loop_size), modpow(7, door_loop_size));
+
+    Ok(Generated::new(
+        format!("{card_public_key}\n{door_public_key}\n"),
+        Some(Answer::from(modpow(door_public_key, card_loop_size))),
+        None,
+    ))
+}
+
+pub struct Day25;
+
+impl Solution for Day25 {
+    const DAY: u32 = 25;
+    const PARTS: u32 = 1;
+
+    type Input = (u64, u64);
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_data(input)
+    }
+
+    fn part_1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_1(*input)?.into())
+    }
+
+    fn encode(input: &Self::Input) -> Option<Vec<u8>> {
+        Some(to_bytes(input))
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self::Input> {
+        from_bytes(bytes)
+    }
 }
 
 #[cfg(test)]
@@ -56,6 +155,6 @@ mod tests {
     #[case((5764801, 17807724), 14897079)]
     #[case((9717666, 20089533), 19924389)]
     fn test_part_1(#[case] input: (u64, u64), #[case] expected: u64) {
-        assert_eq!(expected, part_1(input));
+        assert_eq!(expected, part_1(input).unwrap());
     }
 }
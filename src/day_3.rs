@@ -1,45 +1,298 @@
-use crate::utilities::file_utilities::read_lines;
+use rustc_hash::FxHashSet;
 
-fn tree_is_in_position(inputs: &Vec<String>, spot_row: usize, spot_column: usize) -> bool {
-    let spot_row = spot_row;
-    let spot_column = spot_column % inputs[0].len();
+use crate::error::{OrOverflow, ParseError, SolveError};
+use crate::generate::Generated;
+use crate::repl::Session;
+use crate::solution::{Answer, Solution};
+use crate::utilities::binary::{from_bytes, to_bytes};
+use crate::utilities::grid::Grid;
+use crate::utilities::random::Rng;
 
-    return inputs[spot_row][spot_column..=spot_column].eq("#");
+pub fn parse_data(input: &str) -> Result<Grid<bool>, ParseError> {
+    Grid::parse(input, |c| match c {
+        '#' => Some(true),
+        '.' => Some(false),
+        _ => None,
+    })
 }
 
-fn solve(trees: &Vec<String>, dx: usize, dy: usize) -> i64 {
-    let height = trees.len();
+// The pattern repeats to the right, so the column wraps around.
+fn tree_is_in_position(trees: &Grid<bool>, spot_row: usize, spot_column: usize) -> bool {
+    trees
+        .get_wrapping(spot_row as isize, spot_column as isize)
+        .is_some_and(|tree| *tree)
+}
 
-    let mut trees_in_slope = 0;
+// Where the toboggan lands after leaving the top left square, with columns counted past the edge of the map.
+fn path(trees: &Grid<bool>, dx: usize, dy: usize) -> impl Iterator<Item = (usize, usize)> {
+    let height = trees.height();
 
-    let mut row = 0;
-    let mut column = 0;
+    (1..)
+        .map_while(move |step: usize| Some((step.checked_mul(dy)?, step.checked_mul(dx)?)))
+        .take_while(move |(row, _)| *row < height)
+}
 
-    while row + dy < height {
-        column += dx;
-        row += dy;
+fn solve(trees: &Grid<bool>, dx: usize, dy: usize) -> i64 {
+    path(trees, dx, dy)
+        .filter(|(row, column)| tree_is_in_position(trees, *row, *column))
+        .count() as i64
+}
 
-        if tree_is_in_position(&trees, row, column) {
-            trees_in_slope += 1;
-        }
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Exploration {
+    // The trees hit on every slope, as ((dx, dy), trees).
+    pub slopes: Vec<((usize, usize), i64)>,
+}
+
+impl Exploration {
+    fn extreme(&self, trees: Option<i64>) -> Option<(i64, Vec<(usize, usize)>)> {
+        let trees = trees?;
+        let slopes = self
+            .slopes
+            .iter()
+            .filter(|(_, count)| *count == trees)
+            .map(|(slope, _)| *slope)
+            .collect();
+
+        Some((trees, slopes))
     }
 
-    trees_in_slope
+    // The fewest trees any slope hits, and every slope that hits that many.
+    pub fn fewest(&self) -> Option<(i64, Vec<(usize, usize)>)> {
+        self.extreme(self.slopes.iter().map(|(_, trees)| *trees).min())
+    }
+
+    pub fn most(&self) -> Option<(i64, Vec<(usize, usize)>)> {
+        self.extreme(self.slopes.iter().map(|(_, trees)| *trees).max())
+    }
 }
 
-pub fn part_1(file_path: String) -> i64 {
-    let trees: Vec<String> = read_lines(file_path);
+// Every slope with dx from 0 to `max_dx` and dy from 1 to `max_dy`.
+pub fn explore(
+    trees: &Grid<bool>,
+    max_dx: usize,
+    max_dy: usize,
+) -> Result<Exploration, SolveError> {
+    if max_dy == 0 {
+        return Err(SolveError::NoSolution(String::from(
+            "A slope has to go down at least one row",
+        )));
+    }
+
+    let slopes = (1..=max_dy)
+        .flat_map(|dy| (0..=max_dx).map(move |dx| (dx, dy)))
+        .map(|(dx, dy)| ((dx, dy), solve(trees, dx, dy)))
+        .collect();
 
-    solve(&trees, 3, 1)
+    Ok(Exploration { slopes })
 }
 
-pub fn part_2(file_path: String) -> i64 {
-    let trees: Vec<String> = read_lines(file_path);
+// The map repeated far enough to the right to hold the whole path, with an O where the toboggan lands on open snow and an X where it hits a tree.
+pub fn render_path(trees: &Grid<bool>, dx: usize, dy: usize) -> Result<String, SolveError> {
+    if dy == 0 {
+        return Err(SolveError::NoSolution(String::from(
+            "A slope has to go down at least one row",
+        )));
+    }
+
+    // The columns wrap, so a wider step lands on the same squares as a narrower one but would draw a far longer map.
+    if dx > trees.width() {
+        return Err(SolveError::NoSolution(format!(
+            "A path can be drawn moving at most {} columns at a time",
+            trees.width()
+        )));
+    }
+
+    let landings: FxHashSet<(usize, usize)> = path(trees, dx, dy).collect();
+    let last_column = landings
+        .iter()
+        .map(|(_, column)| *column)
+        .max()
+        .unwrap_or(0);
+    let repeats = last_column / trees.width().max(1) + 1;
+
+    let map = Grid::from_fn(
+        trees.height(),
+        trees.width() * repeats,
+        |(row, column)| match (
+            landings.contains(&(row, column)),
+            tree_is_in_position(trees, row, column),
+        ) {
+            (true, true) => 'X',
+            (true, false) => 'O',
+            (false, true) => '#',
+            (false, false) => '.',
+        },
+    );
 
-    vec![(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)]
+    Ok(map.render(|cell| *cell))
+}
+
+pub fn part_1(trees: &Grid<bool>) -> Result<i64, SolveError> {
+    Ok(solve(trees, 3, 1))
+}
+
+pub fn part_2(trees: &Grid<bool>) -> Result<i64, SolveError> {
+    [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)]
         .into_iter()
-        .map(|(dx, dy)| solve(&trees, dx, dy))
-        .product::<i64>()
+        .try_fold(1_i64, |product, (dx, dy)| {
+            product.checked_mul(solve(trees, dx, dy))
+        })
+        .or_overflow("multiplying the tree counts")
+}
+
+// A quarter of the squares hold a tree, on a map 31 squares wide like the real ones.
+pub fn generate(rng: &mut Rng, size: usize) -> Result<Generated, String> {
+    if size == 0 {
+        return Err(String::from("Day 3 needs at least one row"));
+    }
+
+    let rows: Vec<Vec<bool>> = (0..size)
+        .map(|_| (0..31).map(|_| rng.chance(1, 4)).collect())
+        .collect();
+
+    let trees = |dx: usize, dy: usize| {
+        (0..size)
+            .step_by(dy)
+            .enumerate()
+            .skip(1)
+            .filter(|(step, row)| rows[*row][step * dx % 31])
+            .count() as i64
+    };
+
+    let input = rows
+        .iter()
+        .map(|row| {
+            let mut line: String = row
+                .iter()
+                .map(|tree| if *tree { '#' } else { '.' })
+                .collect();
+            line.push('\n');
+            line
+        })
+        .collect();
+
+    Ok(Generated::new(
+        input,
+        Some(Answer::from(trees(3, 1))),
+        Some(Answer::from(
+            [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)]
+                .into_iter()
+                .map(|(dx, dy)| trees(dx, dy))
+                .product::<i64>(),
+        )),
+    ))
+}
+
+pub struct SlopeSession {
+    trees: Grid<bool>,
+}
+
+impl SlopeSession {
+    pub fn new(trees: &Grid<bool>) -> Self {
+        Self {
+            trees: trees.clone(),
+        }
+    }
+}
+
+fn slope_arguments(arguments: &[&str], usage: &str) -> Result<(usize, usize), String> {
+    match arguments {
+        [dx, dy] => {
+            let number = |text: &str| {
+                text.parse::<usize>()
+                    .map_err(|_| format!("Expected a number, got {text}"))
+            };
+            Ok((number(dx)?, number(dy)?))
+        }
+        _ => Err(format!("Usage: {usage}")),
+    }
+}
+
+fn slope_list(slopes: &[(usize, usize)]) -> String {
+    slopes
+        .iter()
+        .map(|(dx, dy)| format!("({dx}, {dy})"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl Session for SlopeSession {
+    fn commands(&self) -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("trees <dx> <dy>", "Count the trees hit on a slope"),
+            (
+                "explore <max dx> <max dy>",
+                "Try every slope up to these steps",
+            ),
+            ("path <dx> <dy>", "Draw the path taken down a slope"),
+        ]
+    }
+
+    fn execute(&mut self, command: &str, arguments: &[&str]) -> Option<Result<String, String>> {
+        let descent = || String::from("A slope has to go down at least one row");
+
+        Some(match command {
+            "trees" => {
+                slope_arguments(arguments, "trees <dx> <dy>").and_then(|(dx, dy)| match dy {
+                    0 => Err(descent()),
+                    _ => Ok(format!("{} trees", solve(&self.trees, dx, dy))),
+                })
+            }
+            "explore" => slope_arguments(arguments, "explore <max dx> <max dy>").and_then(
+                |(max_dx, max_dy)| {
+                    let exploration =
+                        explore(&self.trees, max_dx, max_dy).map_err(|_| descent())?;
+                    let (fewest, fewest_slopes) = exploration.fewest().ok_or_else(descent)?;
+                    let (most, most_slopes) = exploration.most().ok_or_else(descent)?;
+
+                    Ok(format!(
+                        "{} slopes\nfewest trees: {fewest} on {}\nmost trees: {most} on {}",
+                        exploration.slopes.len(),
+                        slope_list(&fewest_slopes),
+                        slope_list(&most_slopes)
+                    ))
+                },
+            ),
+            "path" => slope_arguments(arguments, "path <dx> <dy>").and_then(|(dx, dy)| {
+                render_path(&self.trees, dx, dy)
+                    .map(|path| path.trim_end().to_string())
+                    .map_err(|error| match error {
+                        SolveError::NoSolution(reason) => reason,
+                        error => error.to_string(),
+                    })
+            }),
+            _ => return None,
+        })
+    }
+}
+
+pub struct Day3;
+
+impl Solution for Day3 {
+    const DAY: u32 = 3;
+
+    type Input = Grid<bool>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_data(input)
+    }
+
+    fn part_1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_1(input)?.into())
+    }
+
+    fn part_2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_2(input)?.into())
+    }
+
+    fn encode(input: &Self::Input) -> Option<Vec<u8>> {
+        Some(to_bytes(input))
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self::Input> {
+        from_bytes(bytes)
+    }
 }
 
 #[cfg(test)]
@@ -47,19 +300,101 @@ mod tests {
     use super::*;
     use rstest::rstest;
 
-    use crate::utilities::file_utilities::get_file_path;
+    use crate::utilities::input::puzzle_input;
 
     #[rstest]
     #[case(true, 7)]
     #[case(false, 214)]
     fn test_part_1(#[case] is_test: bool, #[case] expected: i64) {
-        assert_eq!(expected, part_1(get_file_path(is_test, 3, None)));
+        let trees = parse_data(&puzzle_input(is_test, 3, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_1(&trees).unwrap());
     }
 
     #[rstest]
     #[case(true, 336)]
     #[case(false, 8336352024)]
     fn test_part_2(#[case] is_test: bool, #[case] expected: i64) {
-        assert_eq!(expected, part_2(get_file_path(is_test, 3, None)));
+        let trees = parse_data(&puzzle_input(is_test, 3, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_2(&trees).unwrap());
+    }
+
+    #[test]
+    fn test_part_2_overflow() {
+        let trees = parse_data(&"#\n".repeat(7500)).unwrap();
+
+        assert_eq!(
+            Err(SolveError::Overflow(String::from(
+                "multiplying the tree counts"
+            ))),
+            part_2(&trees)
+        );
+    }
+
+    #[test]
+    fn test_explore() {
+        let trees = parse_data(&puzzle_input(true, 3, None).read().unwrap()).unwrap();
+        let exploration = explore(&trees, 10, 5).unwrap();
+
+        assert_eq!(55, exploration.slopes.len());
+        assert!(exploration.slopes.contains(&((3, 1), 7)));
+        assert!(exploration.slopes.contains(&((1, 2), 2)));
+        let (fewest, slopes) = exploration.fewest().unwrap();
+        assert_eq!((0, 15), (fewest, slopes.len()));
+        assert!(slopes.contains(&(0, 5)));
+        assert_eq!(Some((7, vec![(3, 1)])), exploration.most());
+    }
+
+    #[test]
+    fn test_explore_needs_a_descent() {
+        let trees = parse_data("..#\n#..").unwrap();
+
+        assert_eq!(
+            Err(SolveError::NoSolution(String::from(
+                "A slope has to go down at least one row"
+            ))),
+            explore(&trees, 3, 0)
+        );
+    }
+
+    #[rstest]
+    #[case("trees", &["3", "1"], Ok("7 trees"))]
+    #[case("trees", &["3", "0"], Err("A slope has to go down at least one row"))]
+    #[case("trees", &["3"], Err("Usage: trees <dx> <dy>"))]
+    #[case(
+        "explore",
+        &["3", "1"],
+        Ok("4 slopes\nfewest trees: 1 on (2, 1)\nmost trees: 7 on (3, 1)")
+    )]
+    #[case("path", &["x", "1"], Err("Expected a number, got x"))]
+    #[case("path", &["1", "0"], Err("A slope has to go down at least one row"))]
+    #[case(
+        "path",
+        &["18446744073709551615", "1"],
+        Err("A path can be drawn moving at most 11 columns at a time")
+    )]
+    #[case("trees", &["18446744073709551615", "18446744073709551615"], Ok("0 trees"))]
+    fn test_slope_session(
+        #[case] command: &str,
+        #[case] arguments: &[&str],
+        #[case] expected: Result<&str, &str>,
+    ) {
+        let trees = parse_data(&puzzle_input(true, 3, None).read().unwrap()).unwrap();
+
+        assert_eq!(
+            expected.map(String::from).map_err(String::from),
+            SlopeSession::new(&trees)
+                .execute(command, arguments)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_render_path() {
+        let trees = parse_data("..##.\n#...#\n.#...\n..#.#").unwrap();
+
+        assert_eq!(
+            "..##...##.\n#..O##...#\n.#....X...\n..#.#..#.X\n",
+            render_path(&trees, 3, 1).unwrap()
+        );
     }
 }
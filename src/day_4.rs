@@ -1,8 +1,19 @@
-use itertools::Itertools;
+use nom::branch::alt;
+use nom::bytes::complete::{tag, take_while1};
+use nom::character::complete::char;
+use nom::error::context;
+use nom::multi::many1;
+use nom::sequence::separated_pair;
 
-use crate::utilities::file_utilities::read_lines;
+use crate::error::{parse_records, ParseError, SolveError};
+use crate::generate::Generated;
+use crate::solution::{Answer, Solution};
+use crate::utilities::file_utilities::Record;
+use crate::utilities::grammar::{parse_all, ws, GResult};
+use crate::utilities::random::Rng;
 
-enum PassportField {
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PassportField {
     BirthYear(String),
     IssueYear(String),
     ExpirationYear(String),
@@ -27,21 +38,21 @@ fn is_passport_field_valid(passport_field: &PassportField) -> bool {
             Err(_) => false,
         },
         PassportField::Height(height) => {
-            let (height_value, height_type) = height.split_at(height.len() - 2);
-            match (height_type, height_value.parse::<i32>()) {
-                ("cm", Ok(height)) => (150..=193).contains(&height),
-                ("in", Ok(height)) => (59..=76).contains(&height),
+            match (height.strip_suffix("cm"), height.strip_suffix("in")) {
+                (Some(centimeters), _) => centimeters
+                    .parse::<i32>()
+                    .is_ok_and(|height| (150..=193).contains(&height)),
+                (_, Some(inches)) => inches
+                    .parse::<i32>()
+                    .is_ok_and(|height| (59..=76).contains(&height)),
                 _ => false,
             }
         }
-        PassportField::HairColor(color) => {
-            let (hcl_hashtag, hair_color) = color.split_at(1);
-            return hcl_hashtag == "#"
-                && hair_color.len() == 6
-                && hair_color.chars().all(|c| c.is_ascii_hexdigit());
-        }
+        PassportField::HairColor(color) => color.strip_prefix('#').is_some_and(|hair_color| {
+            hair_color.len() == 6 && hair_color.chars().all(|c| c.is_ascii_hexdigit())
+        }),
         PassportField::EyeColor(color) => {
-            vec!["amb", "blu", "brn", "gry", "grn", "hzl", "oth"].contains(&color.as_str())
+            ["amb", "blu", "brn", "gry", "grn", "hzl", "oth"].contains(&color.as_str())
         }
         PassportField::PassportID(passport_id) => {
             passport_id.len() == 9 && passport_id.chars().all(|c| c.is_ascii_digit())
@@ -49,11 +60,11 @@ fn is_passport_field_valid(passport_field: &PassportField) -> bool {
     }
 }
 
-struct Passport {
-    #[allow(dead_code)]
-    passport_id: String,
-    keys: Vec<String>,
-    fields: Vec<PassportField>,
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Passport {
+    pub passport_id: String,
+    pub keys: Vec<String>,
+    pub fields: Vec<PassportField>,
 }
 
 fn required_fields() -> Vec<String> {
@@ -71,62 +82,72 @@ fn required_fields() -> Vec<String> {
     .collect()
 }
 
-fn parse_line(line: String) -> Passport {
+// ecl:gry pid:860033327 eyr:2020
+fn passport_fields(input: &str) -> GResult<'_, Vec<(&str, &str)>> {
+    let key = context(
+        "a passport field",
+        alt((
+            tag("byr"),
+            tag("iyr"),
+            tag("eyr"),
+            tag("hgt"),
+            tag("hcl"),
+            tag("ecl"),
+            tag("pid"),
+            tag("cid"),
+        )),
+    );
+    let value = context("a value", take_while1(|c: char| !c.is_whitespace()));
+
+    many1(ws(separated_pair(key, char(':'), value)))(input)
+}
+
+fn parse_record(record: &Record) -> Result<Passport, ParseError> {
     let mut keys = vec![];
     let mut passport_id = String::from("");
+    let mut fields = vec![];
+    let mut fields_in_record = vec![];
 
-    let fields: Vec<PassportField> = line
-        .split(' ')
-        .map(str::trim)
-        .map(|field| field.split(':').map(str::trim).collect::<Vec<&str>>())
-        .filter_map(|key_value| {
-            let key = String::from(key_value[0]);
-            let value = String::from(key_value[1]);
-
-            keys.push(key.clone());
-            match key.as_str() {
-                "byr" => Some(PassportField::BirthYear(value)),
-                "iyr" => Some(PassportField::IssueYear(value)),
-                "eyr" => Some(PassportField::ExpirationYear(value)),
-                "hgt" => Some(PassportField::Height(value)),
-                "hcl" => Some(PassportField::HairColor(value)),
-                "ecl" => Some(PassportField::EyeColor(value)),
-                "pid" => {
-                    passport_id = value.clone();
-                    Some(PassportField::PassportID(value))
-                }
-                "cid" => None,
-                &_ => None, // Should fail here?
+    for (number, line) in (record.line..).zip(record.lines.iter()) {
+        fields_in_record
+            .extend(parse_all(line, passport_fields).map_err(|error| error.with_line(number))?);
+    }
+
+    for (key, value) in fields_in_record {
+        keys.push(String::from(key));
+        let value = String::from(value);
+
+        let passport_field = match key {
+            "byr" => PassportField::BirthYear(value),
+            "iyr" => PassportField::IssueYear(value),
+            "eyr" => PassportField::ExpirationYear(value),
+            "hgt" => PassportField::Height(value),
+            "hcl" => PassportField::HairColor(value),
+            "ecl" => PassportField::EyeColor(value),
+            "pid" => {
+                passport_id = value.clone();
+                PassportField::PassportID(value)
             }
-        })
-        .collect();
+            // The grammar only lets `cid` through otherwise, and it is ignored.
+            _ => continue,
+        };
 
-    Passport {
+        fields.push(passport_field);
+    }
+
+    Ok(Passport {
         passport_id,
         keys,
         fields,
-    }
+    })
 }
 
-fn parse_data(file_path: String) -> Vec<Passport> {
-    read_lines(file_path)
-        .into_iter()
-        .coalesce(|x, y| {
-            if x.is_empty() == y.is_empty() {
-                Ok(format!("{} {}", x, y))
-            } else {
-                Err((x, y))
-            }
-        })
-        .filter(|s| !s.is_empty())
-        .map(parse_line)
-        .collect()
+pub fn parse_data(input: &str) -> Result<Vec<Passport>, ParseError> {
+    parse_records(input, parse_record)
 }
 
-pub fn part_1(file_path: String) -> i64 {
-    let passports = parse_data(file_path);
-
-    return passports
+pub fn part_1(passports: &[Passport]) -> Result<i64, SolveError> {
+    let valid = passports
         .iter()
         .filter(|passport| {
             for required_field in &required_fields() {
@@ -137,13 +158,13 @@ pub fn part_1(file_path: String) -> i64 {
 
             true
         })
-        .count() as i64;
-}
+        .count();
 
-pub fn part_2(file_path: String) -> i64 {
-    let passports = parse_data(file_path);
+    Ok(valid as i64)
+}
 
-    return passports
+pub fn part_2(passports: &[Passport]) -> Result<i64, SolveError> {
+    let valid = passports
         .iter()
         .filter(|passport| {
             for required_field in &required_fields() {
@@ -155,7 +176,169 @@ pub fn part_2(file_path: String) -> i64 {
             true
         })
         .filter(|passport| passport.fields.iter().all(is_passport_field_valid))
-        .count() as i64;
+        .count();
+
+    Ok(valid as i64)
+}
+
+fn hex_digits(rng: &mut Rng, length: usize) -> String {
+    (0..length)
+        .map(|_| {
+            *rng.choose(&[
+                '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', 'a', 'b', 'c', 'd', 'e', 'f',
+            ])
+        })
+        .collect()
+}
+
+fn digits(rng: &mut Rng, length: usize) -> String {
+    (0..length)
+        .map(|_| (b'0' + rng.below(10) as u8) as char)
+        .collect()
+}
+
+fn year(rng: &mut Rng, valid: (i64, i64), is_valid: bool) -> String {
+    let year = match (is_valid, rng.chance(1, 2)) {
+        (true, _) => rng.range(valid.0..=valid.1),
+        (false, true) => rng.range(valid.0 - 20..=valid.0 - 1),
+        (false, false) => rng.range(valid.1 + 1..=valid.1 + 20),
+    };
+
+    year.to_string()
+}
+
+fn field_value(rng: &mut Rng, key: &str, is_valid: bool) -> String {
+    match key {
+        "byr" => year(rng, (1920, 2002), is_valid),
+        "iyr" => year(rng, (2010, 2020), is_valid),
+        "eyr" => year(rng, (2020, 2030), is_valid),
+        "hgt" => match (is_valid, rng.index(3)) {
+            (true, 0) => format!("{}in", rng.range(59..=76)),
+            (true, _) => format!("{}cm", rng.range(150..=193)),
+            (false, 0) => format!("{}", rng.range(59..=193)),
+            (false, 1) => format!("{}in", rng.range(77..=99)),
+            (false, _) => format!("{}cm", rng.range(100..=149)),
+        },
+        "hcl" => match (is_valid, rng.chance(1, 2)) {
+            (true, _) => format!("#{}", hex_digits(rng, 6)),
+            (false, true) => hex_digits(rng, 6),
+            (false, false) => format!("#{}z", hex_digits(rng, 5)),
+        },
+        "ecl" => {
+            let colors: &[&str] = if is_valid {
+                &["amb", "blu", "brn", "gry", "grn", "hzl", "oth"]
+            } else {
+                &["xry", "red", "zzz", "gmt", "utc"]
+            };
+            String::from(*rng.choose(colors))
+        }
+        "pid" => match (is_valid, rng.chance(1, 2)) {
+            (true, _) => digits(rng, 9),
+            (false, true) => digits(rng, 8),
+            (false, false) => digits(rng, 10),
+        },
+        _ => rng.range(100..=350).to_string(),
+    }
+}
+
+// A third of the passports are valid, a third miss a required field and a third break one of `invalid_fields`, or any required field when none are given.
+pub fn generate(
+    rng: &mut Rng,
+    size: usize,
+    invalid_fields: &[String],
+) -> Result<Generated, String> {
+    let required = required_fields();
+
+    if let Some(field) = invalid_fields
+        .iter()
+        .find(|field| !required.contains(field))
+    {
+        return Err(format!(
+            "Unknown passport field {field}, expected one of {}",
+            required.join(", ")
+        ));
+    }
+
+    let breakable = if invalid_fields.is_empty() {
+        &required[..]
+    } else {
+        invalid_fields
+    };
+
+    let mut records = vec![];
+    let mut complete = 0;
+    let mut valid = 0;
+
+    for _ in 0..size {
+        let mut keys = required.clone();
+        let mut broken = None;
+
+        match rng.index(3) {
+            0 => {
+                complete += 1;
+                valid += 1;
+            }
+            1 => {
+                keys.remove(rng.index(keys.len()));
+            }
+            _ => {
+                complete += 1;
+                broken = Some(rng.choose(breakable).clone());
+            }
+        }
+
+        if rng.chance(1, 2) {
+            keys.push(String::from("cid"));
+        }
+        rng.shuffle(&mut keys);
+
+        let fields: Vec<String> = keys
+            .iter()
+            .map(|key| {
+                let is_valid = broken.as_ref() != Some(key);
+                format!("{key}:{}", field_value(rng, key, is_valid))
+            })
+            .collect();
+
+        let mut lines = vec![];
+        let mut rest = &fields[..];
+        while !rest.is_empty() {
+            let (line, remaining) = rest.split_at(rng.range(1..=rest.len().min(4) as i64) as usize);
+            lines.push(line.join(" "));
+            rest = remaining;
+        }
+
+        records.push(lines.join("\n"));
+    }
+
+    let mut input = records.join("\n\n");
+    input.push('\n');
+
+    Ok(Generated::new(
+        input,
+        Some(Answer::from(complete)),
+        Some(Answer::from(valid)),
+    ))
+}
+
+pub struct Day4;
+
+impl Solution for Day4 {
+    const DAY: u32 = 4;
+
+    type Input = Vec<Passport>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_data(input)
+    }
+
+    fn part_1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_1(input)?.into())
+    }
+
+    fn part_2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_2(input)?.into())
+    }
 }
 
 #[cfg(test)]
@@ -163,19 +346,31 @@ mod tests {
     use super::*;
     use rstest::rstest;
 
-    use crate::utilities::file_utilities::get_file_path;
+    use crate::utilities::input::puzzle_input;
 
     #[rstest]
     #[case(true, 2)]
     #[case(false, 216)]
     fn test_part_1(#[case] is_test: bool, #[case] expected: i64) {
-        assert_eq!(expected, part_1(get_file_path(is_test, 4, None)));
+        let passports = parse_data(&puzzle_input(is_test, 4, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_1(&passports).unwrap());
     }
 
     #[rstest]
     #[case(true, 2)]
     #[case(false, 150)]
     fn test_part_2(#[case] is_test: bool, #[case] expected: i64) {
-        assert_eq!(expected, part_2(get_file_path(is_test, 4, None)));
+        let passports = parse_data(&puzzle_input(is_test, 4, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_2(&passports).unwrap());
+    }
+
+    #[test]
+    fn test_parse_data_reports_line_in_record() {
+        let error =
+            parse_data("byr:1937 iyr:2017\n\necl:gry pid:860033327\nhcl #fffffd").unwrap_err();
+
+        assert_eq!(Some(4), error.line);
+        assert_eq!(4, error.column);
+        assert_eq!("hcl #fffffd", error.text);
     }
 }
@@ -1,17 +1,25 @@
-use crate::utilities::file_utilities::read_lines;
-use std::collections::HashSet;
+use nom::bytes::complete::take_while1;
+use nom::combinator::map;
+use nom::error::context;
+
+use crate::error::{parse_lines, ParseError, SolveError};
+use crate::generate::Generated;
+use crate::solution::{Answer, Solution};
+use crate::utilities::grammar::{parse_all, ws, GResult};
+use crate::utilities::grid::Grid;
+use crate::utilities::random::Rng;
 
 const NUM_ROWS: i64 = 128;
 const NUM_COLUMNS: i64 = 8;
 
-fn get_seat_number(boarding_pass: &String) -> i64 {
+fn get_seat_number(boarding_pass: &str) -> Result<i64, ParseError> {
     let mut minimal_row = 0;
     let mut maximal_row = NUM_ROWS;
 
     let mut minimal_column = 0;
     let mut maximal_column = NUM_COLUMNS;
 
-    for p in boarding_pass.chars() {
+    for (index, p) in boarding_pass.char_indices() {
         let rows_left = (maximal_row - minimal_row) / 2;
         let columns_left = (maximal_column - minimal_column) / 2;
 
@@ -20,35 +28,114 @@ fn get_seat_number(boarding_pass: &String) -> i64 {
             'B' => minimal_row = maximal_row - rows_left,
             'L' => maximal_column = minimal_column + columns_left,
             'R' => minimal_column = maximal_column - columns_left,
-            _ => todo!(),
+            _ => {
+                return Err(ParseError::at(
+                    boarding_pass,
+                    &boarding_pass[index..],
+                    format!("Unexpected `{p}` in boarding pass"),
+                ))
+            }
         }
     }
 
-    minimal_row * 8 + minimal_column
+    Ok(minimal_row * 8 + minimal_column)
 }
 
-pub fn part_1(file_path: String) -> i64 {
-    let boarding_passes: Vec<String> = read_lines(file_path);
+fn boarding_pass(input: &str) -> GResult<'_, String> {
+    let partitions = take_while1(|c: char| matches!(c, 'F' | 'B' | 'L' | 'R'));
 
-    boarding_passes.iter().map(get_seat_number).max().unwrap()
+    ws(context("a boarding pass", map(partitions, String::from)))(input)
 }
 
-pub fn part_2(file_path: String) -> i64 {
-    let boarding_passes: HashSet<i64> = read_lines(file_path).iter().map(get_seat_number).collect();
+pub fn parse_data(input: &str) -> Result<Vec<String>, ParseError> {
+    parse_lines(input, |line| parse_all(line, boarding_pass))
+}
 
-    for seat_id in 1..(NUM_ROWS * 8 + NUM_COLUMNS - 1) {
-        let below = seat_id - 1;
-        let above = seat_id + 1;
+pub fn part_1(boarding_passes: &[String]) -> Result<i64, SolveError> {
+    let mut highest_seat = None;
 
-        if boarding_passes.contains(&below)
-            && !boarding_passes.contains(&seat_id)
-            && boarding_passes.contains(&above)
-        {
-            return seat_id;
-        }
+    for boarding_pass in boarding_passes {
+        highest_seat = highest_seat.max(Some(get_seat_number(boarding_pass)?));
+    }
+
+    highest_seat.ok_or_else(|| SolveError::NoSolution(String::from("There are no boarding passes")))
+}
+
+pub fn part_2(boarding_passes: &[String]) -> Result<i64, SolveError> {
+    let mut plane = Grid::new(NUM_ROWS as usize, NUM_COLUMNS as usize, false);
+
+    for boarding_pass in boarding_passes {
+        let seat_id = get_seat_number(boarding_pass)?;
+        plane[(
+            (seat_id / NUM_COLUMNS) as usize,
+            (seat_id % NUM_COLUMNS) as usize,
+        )] = true;
+    }
+
+    // Seat ids run through the plane row by row, so the free seat sits between two taken ones.
+    plane
+        .cells()
+        .windows(3)
+        .position(|seats| seats == [true, false, true])
+        .map(|index| index as i64 + 1)
+        .ok_or_else(|| {
+            SolveError::NoSolution(String::from("No free seat sits between two taken ones"))
+        })
+}
+
+// Rows are halved with F and B, then columns with L and R, so a seat id is a 10 bit number written with those letters.
+fn encode_seat(seat_id: i64) -> String {
+    (0..10)
+        .rev()
+        .map(|bit| match (bit >= 3, seat_id >> bit & 1 == 1) {
+            (true, false) => 'F',
+            (true, true) => 'B',
+            (false, false) => 'L',
+            (false, true) => 'R',
+        })
+        .collect()
+}
+
+// A run of occupied seats somewhere in the plane, with yours missing from the middle.
+pub fn generate(rng: &mut Rng, size: usize) -> Result<Generated, String> {
+    if !(2..=1000).contains(&size) {
+        return Err(String::from(
+            "Day 5 needs between 2 and 1000 boarding passes",
+        ));
     }
 
-    -1
+    let first = rng.range(1..=(NUM_ROWS * NUM_COLUMNS - 2 - size as i64));
+    let last = first + size as i64;
+    let free = rng.range(first + 1..=last - 1);
+
+    let mut seats: Vec<i64> = (first..=last).filter(|seat| *seat != free).collect();
+    rng.shuffle(&mut seats);
+
+    Ok(Generated::new(
+        seats.iter().map(|seat| encode_seat(*seat) + "\n").collect(),
+        Some(Answer::from(last)),
+        Some(Answer::from(free)),
+    ))
+}
+
+pub struct Day5;
+
+impl Solution for Day5 {
+    const DAY: u32 = 5;
+
+    type Input = Vec<String>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_data(input)
+    }
+
+    fn part_1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_1(input)?.into())
+    }
+
+    fn part_2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_2(input)?.into())
+    }
 }
 
 #[cfg(test)]
@@ -56,19 +143,24 @@ mod tests {
     use super::*;
     use rstest::rstest;
 
-    use crate::utilities::file_utilities::get_file_path;
+    use crate::utilities::input::puzzle_input;
 
     #[rstest]
     #[case(true, 820)]
     #[case(false, 888)]
     fn test_part_1(#[case] is_test: bool, #[case] expected: i64) {
-        assert_eq!(expected, part_1(get_file_path(is_test, 5, None)));
+        let boarding_passes = parse_data(&puzzle_input(is_test, 5, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_1(&boarding_passes).unwrap());
     }
 
     #[rstest]
-    #[case(true, -1)]
-    #[case(false, 522)]
-    fn test_part_2(#[case] is_test: bool, #[case] expected: i64) {
-        assert_eq!(expected, part_2(get_file_path(is_test, 5, None)));
+    #[case(
+        true,
+        Err(SolveError::NoSolution(String::from("No free seat sits between two taken ones")))
+    )]
+    #[case(false, Ok(522))]
+    fn test_part_2(#[case] is_test: bool, #[case] expected: Result<i64, SolveError>) {
+        let boarding_passes = parse_data(&puzzle_input(is_test, 5, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_2(&boarding_passes));
     }
 }
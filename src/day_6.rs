@@ -1,39 +1,44 @@
-use itertools::Itertools;
-
-use crate::utilities::file_utilities::read_lines;
+use crate::error::{parse_records, ParseError, SolveError};
+use crate::generate::Generated;
+use crate::solution::{Answer, Solution};
+use crate::utilities::file_utilities::Record;
+use crate::utilities::grammar::{parse_all, ws, GResult};
+use crate::utilities::random::Rng;
+use nom::bytes::complete::take_while1;
+use nom::combinator::map;
+use nom::error::context;
 use std::collections::HashSet;
 
-fn parse_line(line: String) -> Vec<HashSet<char>> {
-    line.split(' ')
-        .map(|answer| HashSet::from_iter(answer.chars()))
-        .collect()
+fn answers(input: &str) -> GResult<'_, HashSet<char>> {
+    let questions = take_while1(|c: char| c.is_ascii_lowercase());
+
+    ws(context(
+        "the questions answered with yes",
+        map(questions, |questions: &str| questions.chars().collect()),
+    ))(input)
 }
 
-fn parse_data(file_path: String) -> Vec<Vec<HashSet<char>>> {
-    read_lines(file_path)
-        .into_iter()
-        .coalesce(|x, y| {
-            if x.is_empty() == y.is_empty() {
-                Ok(format!("{} {}", x, y))
-            } else {
-                Err((x, y))
-            }
-        })
-        .filter(|s| !s.is_empty())
-        .map(parse_line)
+fn parse_record(record: &Record) -> Result<Vec<HashSet<char>>, ParseError> {
+    (record.line..)
+        .zip(record.lines.iter())
+        .map(|(number, line)| parse_all(line, answers).map_err(|error| error.with_line(number)))
         .collect()
 }
 
-pub fn part_1(file_path: String) -> i64 {
-    parse_data(file_path)
+pub fn parse_data(input: &str) -> Result<Vec<Vec<HashSet<char>>>, ParseError> {
+    parse_records(input, parse_record)
+}
+
+pub fn part_1(groups: &[Vec<HashSet<char>>]) -> Result<i64, SolveError> {
+    Ok(groups
         .iter()
         .map(|answers| answers.iter().flatten().collect::<HashSet<_>>())
         .map(|s| s.len() as i64)
-        .sum()
+        .sum())
 }
 
-pub fn part_2(file_path: String) -> i64 {
-    parse_data(file_path)
+pub fn part_2(groups: &[Vec<HashSet<char>>]) -> Result<i64, SolveError> {
+    Ok(groups
         .iter()
         .map(|answers| {
             answers.iter().fold(answers[0].clone(), |acc, new| {
@@ -41,7 +46,80 @@ pub fn part_2(file_path: String) -> i64 {
             })
         })
         .map(|s| s.len() as i64)
-        .sum()
+        .sum())
+}
+
+// Groups of one to five people, who share a few answers and add some of their own.
+pub fn generate(rng: &mut Rng, size: usize) -> Result<Generated, String> {
+    let mut groups = vec![];
+    let mut anyone = 0;
+    let mut everyone = 0;
+
+    for _ in 0..size {
+        let shared: u32 = (0..26)
+            .filter(|_| rng.chance(1, 8))
+            .map(|bit| 1 << bit)
+            .sum();
+        let people: Vec<u32> = (0..rng.range(1..=5))
+            .map(|_| loop {
+                let own: u32 = (0..26)
+                    .filter(|_| rng.chance(1, 6))
+                    .map(|bit| 1 << bit)
+                    .sum();
+                if shared | own != 0 {
+                    break shared | own;
+                }
+            })
+            .collect();
+
+        anyone += people
+            .iter()
+            .fold(0, |union, person| union | person)
+            .count_ones();
+        everyone += people
+            .iter()
+            .fold(u32::MAX, |common, person| common & person)
+            .count_ones();
+
+        let lines: Vec<String> = people
+            .iter()
+            .map(|person| {
+                let mut questions: Vec<char> = (0..26)
+                    .filter(|bit| person >> bit & 1 == 1)
+                    .map(|bit| (b'a' + bit as u8) as char)
+                    .collect();
+                rng.shuffle(&mut questions);
+                questions.into_iter().collect()
+            })
+            .collect();
+        groups.push(lines.join("\n"));
+    }
+
+    Ok(Generated::new(
+        groups.join("\n\n") + "\n",
+        Some(Answer::from(anyone)),
+        Some(Answer::from(everyone)),
+    ))
+}
+
+pub struct Day6;
+
+impl Solution for Day6 {
+    const DAY: u32 = 6;
+
+    type Input = Vec<Vec<HashSet<char>>>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_data(input)
+    }
+
+    fn part_1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_1(input)?.into())
+    }
+
+    fn part_2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_2(input)?.into())
+    }
 }
 
 #[cfg(test)]
@@ -49,19 +127,21 @@ mod tests {
     use super::*;
     use rstest::rstest;
 
-    use crate::utilities::file_utilities::get_file_path;
+    use crate::utilities::input::puzzle_input;
 
     #[rstest]
     #[case(true, 11)]
     #[case(false, 6521)]
     fn test_part_1(#[case] is_test: bool, #[case] expected: i64) {
-        assert_eq!(expected, part_1(get_file_path(is_test, 6, None)));
+        let groups = parse_data(&puzzle_input(is_test, 6, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_1(&groups).unwrap());
     }
 
     #[rstest]
     #[case(true, 6)]
     #[case(false, 3305)]
     fn test_part_2(#[case] is_test: bool, #[case] expected: i64) {
-        assert_eq!(expected, part_2(get_file_path(is_test, 6, None)));
+        let groups = parse_data(&puzzle_input(is_test, 6, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_2(&groups).unwrap());
     }
 }
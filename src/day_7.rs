@@ -1,52 +1,76 @@
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::str;
 
-use crate::utilities::file_utilities::read_lines;
+use nom::branch::alt;
+use nom::character::complete::{alpha1, space1};
+use nom::combinator::{map, recognize};
+use nom::error::context;
+use nom::sequence::{separated_pair, tuple};
+use num::BigUint;
 
-struct BagRequirement {
+use crate::error::{parse_lines, ParseError, SolveError};
+use crate::generate::Generated;
+use crate::repl::Session;
+use crate::solution::{Answer, Solution};
+use crate::utilities::grammar::{list, number, parse_all, token, ws, GResult};
+use crate::utilities::random::Rng;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BagRequirement {
     // ... (contains) 2 shiny gold bags, 9 faded blue bags.
-    bag_color: String,
-    bag_number: usize,
+    pub bag_color: String,
+    pub bag_number: usize,
 }
 
-fn parse_line(line: &str) -> (String, Vec<BagRequirement>) {
-    // muted yellow bags contain 2 shiny gold bags, 9 faded blue bags.
-    let contain_split: Vec<&str> = line.split("contain").map(str::trim).collect();
+fn color(input: &str) -> GResult<'_, &str> {
+    ws(context(
+        "a color",
+        recognize(separated_pair(alpha1, space1, alpha1)),
+    ))(input)
+}
 
-    let bag_color = contain_split[0]
-        .split("bags")
-        .map(str::trim)
-        .next()
-        .unwrap();
+// 2 shiny gold bags
+fn bag_requirement(input: &str) -> GResult<'_, BagRequirement> {
+    map(
+        tuple((ws(number), color, alt((token("bags"), token("bag"))))),
+        |(bag_number, bag_color, _)| BagRequirement {
+            bag_color: String::from(bag_color),
+            bag_number,
+        },
+    )(input)
+}
 
-    let bag_requirements = contain_split[1]
-        .split(',')
-        .map(str::trim)
-        .map(|requirement| {
-            let substrings = requirement.split(' ').map(str::trim).collect::<Vec<_>>();
-            let number = substrings[0].parse::<usize>().unwrap_or(0);
-            let color = substrings[1..substrings.len() - 1].join(" ");
-
-            BagRequirement {
-                bag_color: color,
-                bag_number: number,
-            }
-        })
-        .collect::<Vec<_>>();
+// muted yellow bags contain 2 shiny gold bags, 9 faded blue bags.
+fn bag_rule(input: &str) -> GResult<'_, (String, Vec<BagRequirement>)> {
+    let no_bags = map(
+        tuple((token("no"), token("other"), token("bags"))),
+        |_| vec![],
+    );
 
-    (String::from(bag_color), bag_requirements)
+    map(
+        tuple((
+            color,
+            token("bags"),
+            token("contain"),
+            alt((no_bags, list(token(","), bag_requirement))),
+            token("."),
+        )),
+        |(bag_color, _, _, requirements, _)| (String::from(bag_color), requirements),
+    )(input)
 }
 
-fn parse_data(file_path: String) -> Vec<(String, Vec<BagRequirement>)> {
-    read_lines(file_path)
-        .into_iter()
-        .map(|line| parse_line(line.as_str()))
-        .collect()
+fn parse_line(line: &str) -> Result<(String, Vec<BagRequirement>), ParseError> {
+    parse_all(line, bag_rule)
+}
+
+pub fn parse_data(input: &str) -> Result<Vec<(String, Vec<BagRequirement>)>, ParseError> {
+    parse_lines(input, parse_line)
 }
 
-pub fn part_1(file_path: String) -> i32 {
-    let bag_policies: HashMap<_, Vec<_>> = parse_data(file_path).into_iter().collect();
+type BagPolicies = HashMap<String, Vec<BagRequirement>>;
 
+// Every other color that ends up holding a bag of this color, sorted.
+fn colors_holding(bag_policies: &BagPolicies, color: &str) -> Vec<String> {
     let all_bag_colors: HashSet<String> = bag_policies
         .keys()
         .cloned()
@@ -57,14 +81,15 @@ pub fn part_1(file_path: String) -> i32 {
         )
         .collect();
 
-    all_bag_colors
+    let mut containing_colors: Vec<String> = all_bag_colors
         .into_iter()
+        .filter(|bag_color| bag_color != color)
         .filter(|bag_color| {
             let mut queue: VecDeque<String> = [bag_color.clone()].into();
             let mut explored: HashSet<String> = HashSet::new();
 
             while let Some(to_explore) = queue.pop_back() {
-                if to_explore == *"shiny gold" {
+                if to_explore == color {
                     return true;
                 }
 
@@ -79,27 +104,259 @@ pub fn part_1(file_path: String) -> i32 {
 
             false
         })
-        .count() as i32
-        - 1
+        .collect();
+    containing_colors.sort();
+
+    containing_colors
+}
+
+pub fn part_1(bag_policies: &[(String, Vec<BagRequirement>)]) -> Result<usize, SolveError> {
+    let bag_policies: BagPolicies = bag_policies.iter().cloned().collect();
+
+    Ok(colors_holding(&bag_policies, "shiny gold").len())
+}
+
+// The bag itself and everything inside it. A color still `None` in `totals` is being counted further up.
+fn bags_total(
+    bag_policies: &BagPolicies,
+    color: &str,
+    totals: &mut HashMap<String, Option<BigUint>>,
+) -> Result<BigUint, SolveError> {
+    match totals.get(color) {
+        Some(Some(total)) => return Ok(total.clone()),
+        Some(None) => {
+            return Err(SolveError::NoSolution(format!(
+                "{color} bags end up inside themselves"
+            )))
+        }
+        None => {}
+    }
+
+    totals.insert(String::from(color), None);
+    let mut total = BigUint::from(1_u32);
+    for policy in bag_policies.get(color).unwrap_or(&vec![]).iter() {
+        total += bags_total(bag_policies, &policy.bag_color, totals)? * policy.bag_number;
+    }
+    totals.insert(String::from(color), Some(total.clone()));
+
+    Ok(total)
+}
+
+fn bags_inside_count(bag_policies: &BagPolicies, color: &str) -> Result<BigUint, SolveError> {
+    Ok(bags_total(bag_policies, color, &mut HashMap::new())? - 1_u32)
+}
+
+pub fn part_2(bag_policies: &[(String, Vec<BagRequirement>)]) -> Result<BigUint, SolveError> {
+    let bag_policies: BagPolicies = bag_policies.iter().cloned().collect();
+
+    bags_inside_count(&bag_policies, "shiny gold")
 }
 
-pub fn part_2(file_path: String) -> i32 {
-    let bag_policies: HashMap<_, Vec<_>> = parse_data(file_path).into_iter().collect();
+const ADJECTIVES: [&str; 16] = [
+    "bright", "clear", "dark", "dim", "dotted", "drab", "dull", "faded", "light", "mirrored",
+    "muted", "pale", "plaid", "posh", "striped", "vibrant",
+];
+
+const COLORS: [&str; 16] = [
+    "aqua", "beige", "black", "blue", "bronze", "coral", "crimson", "cyan", "fuchsia", "gray",
+    "green", "indigo", "lavender", "lime", "maroon", "olive",
+];
+
+fn pick_contents(rng: &mut Rng, colors: &[String], count: usize) -> Vec<(usize, String)> {
+    let mut colors = colors.to_vec();
+    rng.shuffle(&mut colors);
 
-    let mut number_of_bags = 0;
+    colors
+        .into_iter()
+        .take(count)
+        .map(|color| (rng.range(1..=3) as usize, color))
+        .collect()
+}
 
-    // TODO: Is there a way to cache this, or combine entries?
-    let mut queue: VecDeque<(String, usize)> = [(String::from("shiny gold"), 1)].into();
+fn bags_inside(color: &str, contents: &HashMap<String, Vec<(usize, String)>>) -> BigUint {
+    contents[color]
+        .iter()
+        .map(|(number, inner)| (bags_inside(inner, contents) + 1_u32) * *number)
+        .sum()
+}
+
+// Shiny gold sits in the middle: `depth` levels of bags inside it, as many levels of bags around it, and some bags that never hold it.
+pub fn generate(rng: &mut Rng, size: usize) -> Result<Generated, String> {
+    if !(1..=10).contains(&size) {
+        return Err(String::from("Day 7 needs a depth between 1 and 10"));
+    }
+
+    let mut names: Vec<String> = ADJECTIVES
+        .iter()
+        .flat_map(|adjective| {
+            COLORS
+                .iter()
+                .map(move |color| format!("{adjective} {color}"))
+        })
+        .collect();
+    rng.shuffle(&mut names);
+    let mut names = names.into_iter();
 
-    while let Some((bag_color, multiplier)) = queue.pop_back() {
-        number_of_bags += multiplier;
+    let mut level = |rng: &mut Rng| -> Vec<String> {
+        (0..rng.range(2..=3)).filter_map(|_| names.next()).collect()
+    };
 
-        for policy in bag_policies.get(&bag_color).unwrap_or(&vec![]).iter() {
-            queue.push_front((policy.bag_color.clone(), multiplier * policy.bag_number));
+    let below: Vec<Vec<String>> = (0..size).map(|_| level(rng)).collect();
+    let above: Vec<Vec<String>> = (0..size).map(|_| level(rng)).collect();
+    let unrelated: Vec<String> = (0..size + 2).flat_map(|_| level(rng)).collect();
+    let all_below: Vec<String> = below.iter().flatten().cloned().collect();
+
+    let mut contents: HashMap<String, Vec<(usize, String)>> = HashMap::new();
+    let shiny_gold = String::from("shiny gold");
+
+    let count = rng.range(1..=3) as usize;
+    contents.insert(shiny_gold.clone(), pick_contents(rng, &below[0], count));
+
+    for (index, colors) in below.iter().enumerate() {
+        for color in colors {
+            let inner = match below.get(index + 1) {
+                Some(next) => {
+                    let count = rng.range(1..=3) as usize;
+                    pick_contents(rng, next, count)
+                }
+                None => vec![],
+            };
+            contents.insert(color.clone(), inner);
+        }
+    }
+
+    // Every bag around shiny gold holds at least one bag from the level closer to it.
+    for (index, colors) in above.iter().enumerate() {
+        let closer = match index {
+            0 => vec![shiny_gold.clone()],
+            _ => above[index - 1].clone(),
+        };
+
+        for color in colors {
+            let count = rng.range(1..=closer.len() as i64) as usize;
+            let mut inner = pick_contents(rng, &closer, count);
+            if rng.chance(1, 2) {
+                inner.extend(pick_contents(rng, &all_below, 1));
+            }
+            contents.insert(color.clone(), inner);
         }
     }
 
-    number_of_bags as i32 - 1
+    for color in unrelated.iter() {
+        let count = rng.range(0..=2) as usize;
+        contents.insert(color.clone(), pick_contents(rng, &all_below, count));
+    }
+
+    let mut lines: Vec<String> = contents
+        .iter()
+        .map(|(color, inner)| {
+            let inner = match inner.len() {
+                0 => String::from("no other bags"),
+                _ => inner
+                    .iter()
+                    .map(|(number, color)| match number {
+                        1 => format!("1 {color} bag"),
+                        _ => format!("{number} {color} bags"),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            };
+            format!("{color} bags contain {inner}.\n")
+        })
+        .collect();
+    lines.sort();
+    rng.shuffle(&mut lines);
+
+    Ok(Generated::new(
+        lines.concat(),
+        Some(Answer::from(above.iter().flatten().count())),
+        Some(Answer::from(bags_inside(&shiny_gold, &contents))),
+    ))
+}
+
+// Answers questions about any color, not only shiny gold.
+pub struct BagSession {
+    bag_policies: BagPolicies,
+}
+
+impl BagSession {
+    pub fn new(bag_policies: &[(String, Vec<BagRequirement>)]) -> Self {
+        Self {
+            bag_policies: bag_policies.iter().cloned().collect(),
+        }
+    }
+
+    fn requirements(&self, color: &str) -> Result<&[BagRequirement], String> {
+        self.bag_policies
+            .get(color)
+            .map(Vec::as_slice)
+            .ok_or_else(|| format!("There is no rule for {color} bags"))
+    }
+}
+
+impl Session for BagSession {
+    fn commands(&self) -> Vec<(&'static str, &'static str)> {
+        vec![
+            (
+                "contents <color>",
+                "List the bags a bag must directly contain",
+            ),
+            ("inside <color>", "Count every bag inside a bag"),
+            (
+                "holders <color>",
+                "List the colors that eventually hold a bag",
+            ),
+        ]
+    }
+
+    fn execute(&mut self, command: &str, arguments: &[&str]) -> Option<Result<String, String>> {
+        let color = arguments.join(" ");
+
+        Some(match command {
+            "contents" => self
+                .requirements(&color)
+                .map(|requirements| match requirements {
+                    [] => String::from("no other bags"),
+                    _ => requirements
+                        .iter()
+                        .map(|requirement| {
+                            format!("{} {}", requirement.bag_number, requirement.bag_color)
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                }),
+            "inside" => self.requirements(&color).and_then(|_| {
+                bags_inside_count(&self.bag_policies, &color)
+                    .map(|count| count.to_string())
+                    .map_err(|error| error.to_string())
+            }),
+            "holders" => {
+                let holders = colors_holding(&self.bag_policies, &color);
+                Ok(format!("{} colors: {}", holders.len(), holders.join(", ")))
+            }
+            _ => return None,
+        })
+    }
+}
+
+pub struct Day7;
+
+impl Solution for Day7 {
+    const DAY: u32 = 7;
+
+    type Input = Vec<(String, Vec<BagRequirement>)>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_data(input)
+    }
+
+    fn part_1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_1(input)?.into())
+    }
+
+    fn part_2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_2(input)?.into())
+    }
 }
 
 #[cfg(test)]
@@ -107,19 +364,103 @@ mod tests {
     use super::*;
     use rstest::rstest;
 
-    use crate::utilities::file_utilities::get_file_path;
+    use crate::utilities::input::puzzle_input;
+
+    fn requirement(bag_number: usize, bag_color: &str) -> BagRequirement {
+        BagRequirement {
+            bag_color: String::from(bag_color),
+            bag_number,
+        }
+    }
+
+    #[rstest]
+    #[case("faded blue bags contain no other bags.", vec![])]
+    #[case(
+        "bright white bags contain 1 shiny gold bag.",
+        vec![requirement(1, "shiny gold")]
+    )]
+    #[case(
+        "muted yellow bags contain 2 shiny gold bags ,  9 faded blue bags .",
+        vec![requirement(2, "shiny gold"), requirement(9, "faded blue")]
+    )]
+    fn test_parse_line(#[case] line: &str, #[case] expected: Vec<BagRequirement>) {
+        assert_eq!(expected, parse_line(line).unwrap().1);
+    }
+
+    #[rstest]
+    #[case("faded blue bags contain", 24)]
+    #[case("muted yellow bags contain 2 shiny gold bags, x faded blue bags.", 46)]
+    fn test_parse_line_reports_span(#[case] line: &str, #[case] column: usize) {
+        assert_eq!(column, parse_line(line).unwrap_err().column);
+    }
 
     #[rstest]
     #[case(true, 4)]
     #[case(false, 248)]
-    fn test_part_1(#[case] is_test: bool, #[case] expected: i32) {
-        assert_eq!(expected, part_1(get_file_path(is_test, 7, None)));
+    fn test_part_1(#[case] is_test: bool, #[case] expected: usize) {
+        let bag_policies = parse_data(&puzzle_input(is_test, 7, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_1(&bag_policies).unwrap());
     }
 
     #[rstest]
     #[case(true, 32)]
     #[case(false, 57281)]
-    fn test_part_2(#[case] is_test: bool, #[case] expected: i32) {
-        assert_eq!(expected, part_2(get_file_path(is_test, 7, None)));
+    fn test_part_2(#[case] is_test: bool, #[case] expected: u32) {
+        let bag_policies = parse_data(&puzzle_input(is_test, 7, None).read().unwrap()).unwrap();
+        assert_eq!(BigUint::from(expected), part_2(&bag_policies).unwrap());
+    }
+
+    #[test]
+    fn test_part_2_large() {
+        // Each of the 40 levels holds 9 bags of the next, far more than a u64 can count.
+        let level = |level: usize| match level {
+            0 => String::from("shiny gold"),
+            _ => format!("deep {}", "x".repeat(level)),
+        };
+        let mut lines: Vec<String> = (0..40)
+            .map(|n| format!("{} bags contain 9 {} bags.", level(n), level(n + 1)))
+            .collect();
+        lines.push(format!("{} bags contain no other bags.", level(40)));
+        let bag_policies = parse_data(&lines.join("\n")).unwrap();
+
+        let expected = (BigUint::from(9_u32).pow(41) - 9_u32) / 8_u32;
+        assert_eq!(expected, part_2(&bag_policies).unwrap());
+    }
+
+    #[test]
+    fn test_part_2_cycle() {
+        let bag_policies = parse_data(
+            "shiny gold bags contain 1 dark red bag.\ndark red bags contain 2 shiny gold bags.",
+        )
+        .unwrap();
+
+        assert_eq!(
+            Err(SolveError::NoSolution(String::from(
+                "shiny gold bags end up inside themselves"
+            ))),
+            part_2(&bag_policies)
+        );
+    }
+
+    #[rstest]
+    #[case("contents", "muted yellow", Ok("2 shiny gold, 9 faded blue"))]
+    #[case("contents", "faded blue", Ok("no other bags"))]
+    #[case("inside", "dark olive", Ok("7"))]
+    #[case("holders", "muted yellow", Ok("2 colors: dark orange, light red"))]
+    #[case("inside", "plain red", Err("There is no rule for plain red bags"))]
+    fn test_bag_session(
+        #[case] command: &str,
+        #[case] color: &str,
+        #[case] expected: Result<&str, &str>,
+    ) {
+        let bag_policies = parse_data(&puzzle_input(true, 7, None).read().unwrap()).unwrap();
+        let arguments: Vec<&str> = color.split(' ').collect();
+
+        assert_eq!(
+            expected.map(String::from).map_err(String::from),
+            BagSession::new(&bag_policies)
+                .execute(command, &arguments)
+                .unwrap()
+        );
     }
 }
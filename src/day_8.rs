@@ -1,72 +1,117 @@
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
+use std::fmt;
 use std::str;
 
-use crate::utilities::file_utilities::read_lines;
+use nom::branch::alt;
+use nom::combinator::map;
+use nom::error::context;
+use nom::sequence::preceded;
 
-#[derive(Clone)]
-enum Instruction {
+use crate::error::{parse_lines, OrOverflow, ParseError, SolveError};
+use crate::generate::Generated;
+use crate::observer::{self, Event};
+use crate::repl::{count_argument, Session};
+use crate::solution::{Answer, Solution};
+use crate::utilities::grammar::{number, parse_all, token, ws, GResult};
+use crate::utilities::random::Rng;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Instruction {
     Accumulate(i32),
     Jump(i32),
     NoOp(i32),
 }
 
-fn parse_line(line: &str) -> Instruction {
-    let operation_number: Vec<&str> = line.split(' ').map(str::trim).collect();
-    let value = operation_number[1].parse::<i32>().unwrap();
-    match operation_number[0] {
-        "acc" => Instruction::Accumulate(value),
-        "jmp" => Instruction::Jump(value),
-        "nop" => Instruction::NoOp(value),
-        _ => panic!(),
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Instruction::Accumulate(value) => write!(f, "acc {value:+}"),
+            Instruction::Jump(value) => write!(f, "jmp {value:+}"),
+            Instruction::NoOp(value) => write!(f, "nop {value:+}"),
+        }
     }
 }
 
-fn parse_data(file_path: String) -> Vec<Instruction> {
-    read_lines(file_path)
-        .into_iter()
-        .map(|line| parse_line(line.as_str()))
-        .collect()
+// jmp -4
+fn instruction(input: &str) -> GResult<'_, Instruction> {
+    context(
+        "one of acc, jmp or nop",
+        alt((
+            map(preceded(token("acc"), ws(number)), Instruction::Accumulate),
+            map(preceded(token("jmp"), ws(number)), Instruction::Jump),
+            map(preceded(token("nop"), ws(number)), Instruction::NoOp),
+        )),
+    )(input)
 }
 
-fn simulate(instructions: Vec<Instruction>) -> (i32, bool) {
-    let mut handled: HashSet<usize> = HashSet::new();
+fn parse_line(line: &str) -> Result<Instruction, ParseError> {
+    parse_all(line, instruction)
+}
+
+pub fn parse_data(input: &str) -> Result<Vec<Instruction>, ParseError> {
+    parse_lines(input, parse_line)
+}
 
-    let mut instruction_index = 0;
-    let mut accumulator = 0;
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Console {
+    pub instruction_index: usize,
+    pub accumulator: i32,
+}
 
-    while instruction_index < instructions.len() {
-        if handled.contains(&instruction_index) {
-            return (accumulator, false);
-        }
+impl Console {
+    pub fn is_terminated(&self, instructions: &[Instruction]) -> bool {
+        self.instruction_index >= instructions.len()
+    }
 
-        handled.insert(instruction_index);
+    pub fn step(&mut self, instructions: &[Instruction]) -> Result<(), SolveError> {
+        let index = self.instruction_index;
 
-        match instructions[instruction_index] {
+        match instructions[index] {
             Instruction::Accumulate(value) => {
-                accumulator += value;
-                instruction_index += 1;
+                self.accumulator = self
+                    .accumulator
+                    .checked_add(value)
+                    .or_overflow("accumulating")?;
+                self.instruction_index += 1;
             }
             Instruction::Jump(value) => {
-                instruction_index = (instruction_index as i32 + value) as usize;
+                self.instruction_index = (self.instruction_index as i32 + value) as usize;
             }
             Instruction::NoOp(_) => {
-                instruction_index += 1;
+                self.instruction_index += 1;
             }
         }
-    }
 
-    (accumulator, true)
+        observer::emit(|| Event::InstructionExecuted {
+            index,
+            accumulator: self.accumulator,
+        });
+
+        Ok(())
+    }
 }
 
-pub fn part_1(file_path: String) -> i32 {
-    let instructions = parse_data(file_path);
-    let (accumulator, _) = simulate(instructions);
-    accumulator
+fn simulate(instructions: &[Instruction]) -> Result<(i32, bool), SolveError> {
+    let mut handled: HashSet<usize> = HashSet::new();
+    let mut console = Console::default();
+
+    while !console.is_terminated(instructions) {
+        if !handled.insert(console.instruction_index) {
+            return Ok((console.accumulator, false));
+        }
+
+        console.step(instructions)?;
+    }
+
+    Ok((console.accumulator, true))
 }
 
-pub fn part_2(file_path: String) -> i32 {
-    let instructions = parse_data(file_path);
+pub fn part_1(instructions: &[Instruction]) -> Result<i32, SolveError> {
+    let (accumulator, _) = simulate(instructions)?;
+    Ok(accumulator)
+}
 
+pub fn part_2(instructions: &[Instruction]) -> Result<i32, SolveError> {
     // Try to replace a jmp with a nop or vice-versa
     for instruction_index in 0..instructions.len() {
         let new_instruction = match instructions[instruction_index] {
@@ -75,17 +120,220 @@ pub fn part_2(file_path: String) -> i32 {
             Instruction::NoOp(value) => Instruction::Jump(value),
         };
 
-        let mut new_instructions = instructions.clone();
+        let mut new_instructions = instructions.to_vec();
         new_instructions[instruction_index] = new_instruction;
 
-        let (accumulator, successful_termination) = simulate(new_instructions);
+        let (accumulator, successful_termination) = simulate(&new_instructions)?;
 
         if successful_termination {
-            return accumulator;
+            return Ok(accumulator);
+        }
+    }
+
+    Err(SolveError::NoSolution(String::from(
+        "No single jmp/nop swap lets the program terminate",
+    )))
+}
+
+fn random_instruction(rng: &mut Rng, index: usize, furthest_landing: usize) -> Instruction {
+    match rng.index(10) {
+        0..=4 => Instruction::Accumulate(
+            rng.range(1..=50) as i32 * if rng.chance(1, 2) { 1 } else { -1 },
+        ),
+        5 | 6 => Instruction::NoOp(-(rng.range(0..=index.min(20) as i64) as i32)),
+        _ => {
+            let longest = (furthest_landing - index).min(10) as i64;
+            Instruction::Jump(rng.range(1..=longest) as i32)
         }
     }
+}
+
+// Every jump goes forward except one, which loops back. Every nop points backwards or at itself, so turning that one jump into a nop is the only fix.
+pub fn generate(rng: &mut Rng, size: usize) -> Result<Generated, String> {
+    if size < 4 {
+        return Err(String::from("Day 8 needs at least 4 instructions"));
+    }
+
+    let looping = rng.range(size as i64 / 4..=size as i64 * 3 / 4).max(1) as usize;
+    let mut instructions: Vec<Instruction> = (0..size)
+        .map(|index| {
+            if index < looping {
+                random_instruction(rng, index, looping)
+            } else {
+                random_instruction(rng, index, size)
+            }
+        })
+        .collect();
+
+    let mut path = vec![];
+    let mut index = 0;
+    while index < looping {
+        path.push(index);
+        index = match instructions[index] {
+            Instruction::Jump(offset) => index + offset as usize,
+            _ => index + 1,
+        };
+    }
+
+    let target = *rng.choose(&path);
+    instructions[looping] = Instruction::Jump(target as i32 - looping as i32);
+
+    let accumulated = |indices: &[usize]| -> i32 {
+        indices
+            .iter()
+            .map(|index| match instructions[*index] {
+                Instruction::Accumulate(value) => value,
+                _ => 0,
+            })
+            .sum()
+    };
+    let before_loop = accumulated(&path);
+
+    let mut index = looping + 1;
+    let mut rest = vec![];
+    while index < size {
+        rest.push(index);
+        index = match instructions[index] {
+            Instruction::Jump(offset) => index + offset as usize,
+            _ => index + 1,
+        };
+    }
+    let after_fix = before_loop + accumulated(&rest);
+
+    let input = instructions
+        .iter()
+        .map(|instruction| format!("{instruction}\n"))
+        .collect();
+
+    Ok(Generated::new(
+        input,
+        Some(Answer::from(before_loop)),
+        Some(Answer::from(after_fix)),
+    ))
+}
 
-    -1
+// Steps the handheld console by hand, stopping at breakpoints or the first repeated instruction.
+pub struct ConsoleSession {
+    instructions: Vec<Instruction>,
+    console: Console,
+    handled: HashSet<usize>,
+    breakpoints: BTreeSet<usize>,
+}
+
+impl ConsoleSession {
+    pub fn new(instructions: &[Instruction]) -> Self {
+        Self {
+            instructions: instructions.to_vec(),
+            console: Console::default(),
+            handled: HashSet::new(),
+            breakpoints: BTreeSet::new(),
+        }
+    }
+
+    fn state(&self) -> String {
+        let Console {
+            instruction_index,
+            accumulator,
+        } = self.console;
+
+        match self.instructions.get(instruction_index) {
+            Some(instruction) => {
+                format!("accumulator {accumulator}, next {instruction_index}: {instruction}")
+            }
+            None => format!("accumulator {accumulator}, terminated at {instruction_index}"),
+        }
+    }
+
+    // Stops before an instruction would run a second time, so the accumulator is the part 1 answer.
+    fn step(&mut self) -> Result<(), String> {
+        if self.console.is_terminated(&self.instructions) {
+            return Err(format!("The program has terminated, {}", self.state()));
+        }
+        if self.handled.contains(&self.console.instruction_index) {
+            return Err(format!(
+                "Instruction {} would run twice, {}",
+                self.console.instruction_index,
+                self.state()
+            ));
+        }
+
+        self.handled.insert(self.console.instruction_index);
+        self.console
+            .step(&self.instructions)
+            .map_err(|error| error.to_string())
+    }
+}
+
+impl Session for ConsoleSession {
+    fn commands(&self) -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("state", "Show the accumulator and the next instruction"),
+            ("step [count]", "Run one or more instructions"),
+            (
+                "run",
+                "Run until a breakpoint, a repeated instruction or the end",
+            ),
+            ("break <index>", "Set or clear a breakpoint"),
+            ("reset", "Start the program again, keeping the breakpoints"),
+        ]
+    }
+
+    fn execute(&mut self, command: &str, arguments: &[&str]) -> Option<Result<String, String>> {
+        Some(match command {
+            "state" => Ok(self.state()),
+            "step" => count_argument(arguments).and_then(|count| {
+                (0..count).try_for_each(|_| self.step())?;
+                Ok(self.state())
+            }),
+            "run" => (|| {
+                self.step()?;
+                while !self.breakpoints.contains(&self.console.instruction_index) {
+                    self.step()?;
+                }
+                Ok(format!("Breakpoint, {}", self.state()))
+            })(),
+            "break" => match arguments {
+                [index] => index
+                    .parse()
+                    .map_err(|_| format!("Expected an instruction index, got {index}"))
+                    .map(|index: usize| {
+                        if self.breakpoints.insert(index) {
+                            format!("Breakpoint set at {index}")
+                        } else {
+                            self.breakpoints.remove(&index);
+                            format!("Breakpoint cleared at {index}")
+                        }
+                    }),
+                _ => Err(String::from("Usage: break <index>")),
+            },
+            "reset" => {
+                self.console = Console::default();
+                self.handled.clear();
+                Ok(self.state())
+            }
+            _ => return None,
+        })
+    }
+}
+
+pub struct Day8;
+
+impl Solution for Day8 {
+    const DAY: u32 = 8;
+
+    type Input = Vec<Instruction>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_data(input)
+    }
+
+    fn part_1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_1(input)?.into())
+    }
+
+    fn part_2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(part_2(input)?.into())
+    }
 }
 
 #[cfg(test)]
@@ -93,19 +341,49 @@ mod tests {
     use super::*;
     use rstest::rstest;
 
-    use crate::utilities::file_utilities::get_file_path;
+    use crate::utilities::input::puzzle_input;
 
     #[rstest]
     #[case(true, 5)]
     #[case(false, 1475)]
     fn test_part_1(#[case] is_test: bool, #[case] expected: i32) {
-        assert_eq!(expected, part_1(get_file_path(is_test, 8, None)));
+        let instructions = parse_data(&puzzle_input(is_test, 8, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_1(&instructions).unwrap());
     }
 
     #[rstest]
     #[case(true, 8)]
     #[case(false, 1270)]
     fn test_part_2(#[case] is_test: bool, #[case] expected: i32) {
-        assert_eq!(expected, part_2(get_file_path(is_test, 8, None)));
+        let instructions = parse_data(&puzzle_input(is_test, 8, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_2(&instructions).unwrap());
+    }
+
+    #[rstest]
+    #[case(&["step 3"], "accumulator 1, next 6: acc +1")]
+    #[case(&["break 4", "run"], "Breakpoint, accumulator 5, next 4: jmp -3")]
+    #[case(&["run"], "Instruction 1 would run twice, accumulator 5, next 1: acc +1")]
+    #[case(&["step 2", "reset", "state"], "accumulator 0, next 0: nop +0")]
+    fn test_console_session(#[case] commands: &[&str], #[case] expected: &str) {
+        let instructions = parse_data(&puzzle_input(true, 8, None).read().unwrap()).unwrap();
+        let mut session = ConsoleSession::new(&instructions);
+
+        let mut reply = Ok(String::new());
+        for command in commands {
+            let words: Vec<&str> = command.split_whitespace().collect();
+            reply = session.execute(words[0], &words[1..]).unwrap();
+        }
+
+        assert_eq!(expected, reply.unwrap_or_else(|error| error));
+    }
+
+    #[test]
+    fn test_overflow() {
+        let instructions = parse_data("acc +2000000000\nacc +2000000000").unwrap();
+
+        assert_eq!(
+            Err(SolveError::Overflow(String::from("accumulating"))),
+            part_1(&instructions)
+        );
     }
 }
@@ -1,21 +1,58 @@
 use std::str;
+use std::sync::OnceLock;
 
-use crate::utilities::file_utilities::read_lines;
+use nom::branch::alt;
+use nom::combinator::map;
+use nom::sequence::preceded;
 
-fn parse_line(line: &str) -> usize {
-    line.parse::<usize>().unwrap()
+use crate::error::{ParseError, SolveError};
+use crate::generate::Generated;
+use crate::solution::{Answer, Solution};
+use crate::utilities::binary::{from_bytes, to_bytes, Binary};
+use crate::utilities::file_utilities::lines;
+use crate::utilities::grammar::{number, parse_all, token, ws};
+use crate::utilities::random::Rng;
+
+const PREAMBLE: usize = 25;
+
+enum Line {
+    Preamble(usize),
+    Number(usize),
 }
 
-fn parse_data(file_path: String) -> Vec<usize> {
-    read_lines(file_path)
-        .into_iter()
-        .map(|line| parse_line(line.as_str()))
-        .collect()
+// preamble: 5
+fn parse_line(line: &str) -> Result<Line, ParseError> {
+    parse_all(
+        line,
+        alt((
+            map(preceded(token("preamble:"), ws(number)), Line::Preamble),
+            map(ws(number), Line::Number),
+        )),
+    )
 }
 
-pub fn part_1(file_path: String, preamble: usize) -> usize {
-    let numbers = parse_data(file_path);
+// The example is too short for the real preamble, so it opens with a line giving its own.
+pub fn parse_data(input: &str) -> Result<Cipher, ParseError> {
+    let mut preamble = PREAMBLE;
+    let mut numbers = vec![];
+
+    for (index, (line_number, line)) in lines(input).enumerate() {
+        match parse_line(line).map_err(|error| error.with_line(line_number))? {
+            Line::Preamble(count) if index == 0 => preamble = count,
+            Line::Preamble(_) => {
+                return Err(
+                    ParseError::new(line, 1, "Expected the preamble on the first line")
+                        .with_line(line_number),
+                )
+            }
+            Line::Number(number) => numbers.push(number),
+        }
+    }
+
+    Ok(Cipher::new(numbers, preamble))
+}
 
+pub fn part_1(numbers: &[usize], preamble: usize) -> Result<usize, SolveError> {
     for (i, number) in numbers.iter().enumerate().skip(preamble) {
         let mut match_exists = false;
 
@@ -29,18 +66,16 @@ pub fn part_1(file_path: String, preamble: usize) -> usize {
         }
 
         if !match_exists {
-            return *number;
+            return Ok(*number);
         }
     }
 
-    0
+    Err(SolveError::NoSolution(String::from(
+        "Every number is the sum of two of its preceding numbers",
+    )))
 }
 
-pub fn part_2(file_path: String, preamble: usize) -> usize {
-    let invalid_number = part_1(file_path.clone(), preamble);
-
-    let numbers = parse_data(file_path);
-
+fn find_weakness(numbers: &[usize], invalid_number: usize) -> Result<usize, SolveError> {
     let contiguous_set = numbers
         .iter()
         .enumerate()
@@ -62,9 +97,156 @@ pub fn part_2(file_path: String, preamble: usize) -> usize {
             }
         })
         .next()
-        .unwrap();
+        .ok_or_else(|| {
+            SolveError::NoSolution(format!("No contiguous set sums to {invalid_number}"))
+        })?;
+
+    let smallest = contiguous_set.iter().min().unwrap_or(&0);
+    let largest = contiguous_set.iter().max().unwrap_or(&0);
+
+    Ok(smallest + largest)
+}
+
+pub fn part_2(numbers: &[usize], preamble: usize) -> Result<usize, SolveError> {
+    find_weakness(numbers, part_1(numbers, preamble)?)
+}
 
-    contiguous_set.iter().min().unwrap() + contiguous_set.iter().max().unwrap()
+// Part 2 starts from the answer to part 1, so it is worked out once and kept with the numbers.
+pub struct Cipher {
+    numbers: Vec<usize>,
+    preamble: usize,
+    invalid_number: OnceLock<Result<usize, SolveError>>,
+}
+
+impl Cipher {
+    pub fn new(numbers: Vec<usize>, preamble: usize) -> Self {
+        Self {
+            numbers,
+            preamble,
+            invalid_number: OnceLock::new(),
+        }
+    }
+
+    fn invalid_number(&self) -> Result<usize, SolveError> {
+        self.invalid_number
+            .get_or_init(|| part_1(&self.numbers, self.preamble))
+            .clone()
+    }
+}
+
+impl Binary for Cipher {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.numbers.encode(bytes);
+        self.preamble.encode(bytes);
+        let invalid_number = self
+            .invalid_number
+            .get()
+            .and_then(|result| result.clone().ok());
+        invalid_number.encode(bytes);
+    }
+
+    fn decode(bytes: &mut &[u8]) -> Option<Self> {
+        let cipher = Self {
+            numbers: Vec::decode(bytes)?,
+            preamble: usize::decode(bytes)?,
+            invalid_number: OnceLock::new(),
+        };
+        if let Some(invalid_number) = Option::decode(bytes)? {
+            let _ = cipher.invalid_number.set(Ok(invalid_number));
+        }
+        Some(cipher)
+    }
+}
+
+fn is_sum_of_two(window: &[usize], number: usize) -> bool {
+    window
+        .iter()
+        .any(|first| window.iter().any(|second| first + second == number))
+}
+
+// Each number adds two of the smaller numbers in its window, which keeps the values from growing too fast. One number is replaced by the sum of an earlier contiguous run instead.
+pub fn generate(rng: &mut Rng, size: usize) -> Result<Generated, String> {
+    if !(50..=2000).contains(&size) {
+        return Err(String::from("Day 9 needs between 50 and 2000 numbers"));
+    }
+
+    let mut numbers: Vec<usize> = rng
+        .distinct(1..=50, PREAMBLE)
+        .into_iter()
+        .map(|number| number as usize)
+        .collect();
+    let invalid_index = rng.range(size as i64 / 2..=size as i64 - 1) as usize;
+
+    while numbers.len() < size {
+        let window = &numbers[numbers.len() - PREAMBLE..];
+
+        if numbers.len() == invalid_index {
+            let length = rng.range(2..=5) as usize;
+            let earliest = (invalid_index as i64 - 40).max(0);
+            let start = rng.range(earliest..=(invalid_index - length) as i64) as usize;
+            let invalid: usize = numbers[start..start + length].iter().sum();
+
+            if !is_sum_of_two(window, invalid) && !numbers.contains(&invalid) {
+                numbers.push(invalid);
+            }
+            continue;
+        }
+
+        let mut smallest = window.to_vec();
+        smallest.sort();
+        let first = rng.index(10);
+        let second = (first + 1 + rng.index(9)) % 10;
+        numbers.push(smallest[first] + smallest[second]);
+    }
+
+    let invalid = numbers[invalid_index];
+    let weakness = (0..invalid_index).find_map(|start| {
+        let mut sum = 0;
+        numbers[start..invalid_index]
+            .iter()
+            .enumerate()
+            .find_map(|(length, number)| {
+                sum += number;
+                (sum == invalid).then(|| {
+                    let run = &numbers[start..=start + length];
+                    run.iter().min().unwrap() + run.iter().max().unwrap()
+                })
+            })
+    });
+
+    Ok(Generated::new(
+        numbers.iter().map(|number| format!("{number}\n")).collect(),
+        Some(Answer::from(invalid)),
+        weakness.map(Answer::from),
+    ))
+}
+
+pub struct Day9;
+
+impl Solution for Day9 {
+    const DAY: u32 = 9;
+
+    type Input = Cipher;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_data(input)
+    }
+
+    fn part_1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(input.invalid_number()?.into())
+    }
+
+    fn part_2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(find_weakness(&input.numbers, input.invalid_number()?)?.into())
+    }
+
+    fn encode(input: &Self::Input) -> Option<Vec<u8>> {
+        Some(to_bytes(input))
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self::Input> {
+        from_bytes(bytes)
+    }
 }
 
 #[cfg(test)]
@@ -72,21 +254,39 @@ mod tests {
     use super::*;
     use rstest::rstest;
 
-    use crate::utilities::file_utilities::get_file_path;
+    use crate::utilities::input::puzzle_input;
 
     #[rstest]
     #[case(true, 127)]
     #[case(false, 217430975)]
     fn test_part_1(#[case] is_test: bool, #[case] expected: usize) {
-        let preamble = if is_test { 5 } else { 25 };
-        assert_eq!(expected, part_1(get_file_path(is_test, 9, None), preamble));
+        let cipher = parse_data(&puzzle_input(is_test, 9, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_1(&cipher.numbers, cipher.preamble).unwrap());
     }
 
     #[rstest]
     #[case(true, 62)]
     #[case(false, 28509180)]
     fn test_part_2(#[case] is_test: bool, #[case] expected: usize) {
-        let preamble = if is_test { 5 } else { 25 };
-        assert_eq!(expected, part_2(get_file_path(is_test, 9, None), preamble));
+        let cipher = parse_data(&puzzle_input(is_test, 9, None).read().unwrap()).unwrap();
+        assert_eq!(expected, part_2(&cipher.numbers, cipher.preamble).unwrap());
+    }
+
+    #[rstest]
+    #[case("preamble: 5\n35\n20", Ok((vec![35, 20], 5)))]
+    #[case("35\n20", Ok((vec![35, 20], 25)))]
+    #[case("35\npreamble: 5", Err((2, String::from("Expected the preamble on the first line"))))]
+    fn test_parse_data(
+        #[case] input: &str,
+        #[case] expected: Result<(Vec<usize>, usize), (usize, String)>,
+    ) {
+        let cipher = parse_data(input);
+
+        assert_eq!(
+            expected,
+            cipher
+                .map(|cipher| (cipher.numbers, cipher.preamble))
+                .map_err(|error| (error.line.unwrap(), error.message))
+        );
     }
 }
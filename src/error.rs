@@ -0,0 +1,238 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::utilities::file_utilities::{lines, records, Record};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseError {
+    pub file: Option<String>,
+    pub line: Option<usize>,
+    pub column: usize,
+    pub text: String,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(text: &str, column: usize, message: impl Into<String>) -> Self {
+        Self {
+            file: None,
+            line: None,
+            column,
+            text: text.to_string(),
+            message: message.into(),
+        }
+    }
+
+    // The column is worked out from where `token` sits inside `text`, so `token` should be a slice of it.
+    pub fn at(text: &str, token: &str, message: impl Into<String>) -> Self {
+        Self::new(text, column_of(text, token), message)
+    }
+
+    pub fn with_line(mut self, line: usize) -> Self {
+        self.line = Some(line);
+        self
+    }
+
+    pub fn with_file(mut self, file: impl Into<String>) -> Self {
+        self.file = Some(file.into());
+        self
+    }
+}
+
+fn column_of(text: &str, token: &str) -> usize {
+    let text_start = text.as_ptr() as usize;
+    let token_start = token.as_ptr() as usize;
+
+    if (text_start..=text_start + text.len()).contains(&token_start) {
+        text[..token_start - text_start].chars().count() + 1
+    } else {
+        1
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let file = self.file.as_deref().unwrap_or("<input>");
+        match self.line {
+            Some(line) => write!(f, "{file}:{line}:{}: {}", self.column, self.message)?,
+            None => write!(f, "{file}:?:{}: {}", self.column, self.message)?,
+        }
+
+        let caret_padding = " ".repeat(self.column.saturating_sub(1));
+        write!(f, "\n    {}\n    {caret_padding}^", self.text)
+    }
+}
+
+impl Error for ParseError {}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SolveError {
+    Io { file: String, message: String },
+    Parse(ParseError),
+    NoSolution(String),
+    MissingPart { day: u32, part: u32 },
+    // The answer does not fit the integers the solver works with.
+    Overflow(String),
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolveError::Io { file, message } => write!(f, "Cannot read {file}: {message}"),
+            SolveError::Parse(error) => write!(f, "{error}"),
+            SolveError::NoSolution(reason) => write!(f, "No solution: {reason}"),
+            SolveError::MissingPart { day, part } => write!(f, "Day {day} has no part {part}"),
+            SolveError::Overflow(operation) => write!(f, "Overflow while {operation}"),
+        }
+    }
+}
+
+impl SolveError {
+    pub fn with_file(self, file: impl Into<String>) -> Self {
+        match self {
+            SolveError::Parse(error) => SolveError::Parse(error.with_file(file)),
+            error => error,
+        }
+    }
+}
+
+impl Error for SolveError {}
+
+// For checked arithmetic: `a.checked_mul(b).or_overflow("multiplying the entries")?`.
+pub trait OrOverflow<T> {
+    fn or_overflow(self, operation: &str) -> Result<T, SolveError>;
+}
+
+impl<T> OrOverflow<T> for Option<T> {
+    fn or_overflow(self, operation: &str) -> Result<T, SolveError> {
+        self.ok_or_else(|| SolveError::Overflow(String::from(operation)))
+    }
+}
+
+impl From<ParseError> for SolveError {
+    fn from(error: ParseError) -> Self {
+        SolveError::Parse(error)
+    }
+}
+
+pub fn parse_number<T: FromStr>(text: &str, token: &str) -> Result<T, ParseError> {
+    token
+        .parse::<T>()
+        .map_err(|_| ParseError::at(text, token, format!("Expected a number, found `{token}`")))
+}
+
+pub fn check_characters(text: &str, allowed: &[char]) -> Result<(), ParseError> {
+    match text.char_indices().find(|(_, c)| !allowed.contains(c)) {
+        Some((index, character)) => Err(ParseError::at(
+            text,
+            &text[index..],
+            format!("Unexpected `{character}`"),
+        )),
+        None => Ok(()),
+    }
+}
+
+pub fn parse_lines<T>(
+    input: &str,
+    parse_line: impl Fn(&str) -> Result<T, ParseError>,
+) -> Result<Vec<T>, ParseError> {
+    lines(input)
+        .map(|(number, line)| parse_line(line).map_err(|error| error.with_line(number)))
+        .collect()
+}
+
+// The whole input is one line, such as a list of starting numbers.
+pub fn parse_single_line<T>(
+    input: &str,
+    parse_line: impl Fn(&str) -> Result<T, ParseError>,
+) -> Result<T, ParseError> {
+    let mut lines = lines(input);
+    let (number, line) = lines.next().unwrap_or((1, ""));
+    let parsed = parse_line(line).map_err(|error| error.with_line(number))?;
+
+    match lines.next() {
+        Some((number, line)) => {
+            Err(ParseError::new(line, 1, "Expected a single line").with_line(number))
+        }
+        None => Ok(parsed),
+    }
+}
+
+// Errors without a line of their own are reported on the first line of their record.
+pub fn parse_records<T>(
+    input: &str,
+    parse_record: impl Fn(&Record) -> Result<T, ParseError>,
+) -> Result<Vec<T>, ParseError> {
+    records(input)
+        .map(|record| {
+            parse_record(&record).map_err(|error| match error.line {
+                Some(_) => error,
+                None => error.with_line(record.line),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("1-3 a: abcde", 0, 1)]
+    #[case("1-3 a: abcde", 4, 5)]
+    #[case("1-3 a: abcde", 12, 13)]
+    fn test_column_of(#[case] text: &str, #[case] offset: usize, #[case] expected: usize) {
+        assert_eq!(expected, column_of(text, &text[offset..]));
+    }
+
+    #[test]
+    fn test_parse_lines_reports_line_and_column() {
+        let error = parse_lines("12\n34\n5x6", |line| parse_number::<u32>(line, &line[1..]))
+            .unwrap_err()
+            .with_file("day1.txt");
+
+        assert_eq!(Some(3), error.line);
+        assert_eq!(2, error.column);
+        assert_eq!(
+            "day1.txt:3:2: Expected a number, found `x6`\n    5x6\n     ^",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn test_parse_lines_skips_surrounding_blank_lines() {
+        let numbers = parse_lines("\n1721\n979\n\n  \n", |line| {
+            parse_number::<u32>(line, line)
+        });
+
+        assert_eq!(Ok(vec![1721, 979]), numbers);
+    }
+
+    #[rstest]
+    #[case("\n12\n", Ok(12))]
+    #[case("", Err((Some(1), String::from("Expected a number, found ``"))))]
+    #[case("\n1x\n", Err((Some(2), String::from("Expected a number, found `1x`"))))]
+    #[case("12\n34", Err((Some(2), String::from("Expected a single line"))))]
+    fn test_parse_single_line(
+        #[case] input: &str,
+        #[case] expected: Result<u32, (Option<usize>, String)>,
+    ) {
+        let number = parse_single_line(input, |line| parse_number::<u32>(line, line));
+
+        assert_eq!(
+            expected,
+            number.map_err(|error| (error.line, error.message))
+        );
+    }
+
+    #[test]
+    fn test_parse_records_skips_surrounding_blank_lines() {
+        let records = parse_records("\nPlayer 1:\n9\n\n \n", |record| {
+            Ok((record.line, record.lines.len()))
+        });
+
+        assert_eq!(Ok(vec![(2, 2)]), records);
+    }
+}
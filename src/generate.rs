@@ -0,0 +1,187 @@
+use crate::solution::Answer;
+use crate::utilities::random::{Rng, DEFAULT_SEED};
+use crate::{
+    day_1, day_10, day_11, day_12, day_13, day_14, day_15, day_16, day_17, day_18, day_19, day_2,
+    day_20, day_21, day_22, day_23, day_24, day_25, day_3, day_4, day_5, day_6, day_7, day_8,
+    day_9,
+};
+
+// What `size` counts for each day and its default, or `None` where the puzzle fixes the size.
+pub const SIZES: [Option<(&str, usize)>; 25] = [
+    Some(("expense entries", 200)),
+    Some(("passwords", 1000)),
+    Some(("rows of the map", 323)),
+    Some(("passports", 250)),
+    Some(("boarding passes", 800)),
+    Some(("groups", 450)),
+    Some(("levels of bags below shiny gold", 4)),
+    Some(("instructions", 600)),
+    Some(("numbers", 1000)),
+    Some(("adapters", 100)),
+    Some(("rows and columns of seats", 90)),
+    Some(("navigation instructions", 780)),
+    Some(("busses", 9)),
+    Some(("instructions", 500)),
+    Some(("starting numbers", 6)),
+    Some(("ticket fields", 20)),
+    Some(("rows and columns of the starting slice", 8)),
+    Some(("expressions", 370)),
+    Some(("messages", 400)),
+    Some(("tiles along each side of the image", 12)),
+    Some(("foods", 40)),
+    Some(("cards in each deck", 25)),
+    None,
+    Some(("tiles to flip", 300)),
+    Some(("largest loop size", 10_000_000)),
+];
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Options {
+    pub seed: u64,
+    pub size: Option<usize>,
+    // Only used by day 4, where invalid passports break one of these fields.
+    pub invalid_fields: Vec<String>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            seed: DEFAULT_SEED,
+            size: None,
+            invalid_fields: vec![],
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Generated {
+    pub input: String,
+    // Known answers for each part, `None` where finding one means solving the puzzle.
+    pub answers: [Option<Answer>; 2],
+}
+
+impl Generated {
+    pub fn new(input: String, part_1: Option<Answer>, part_2: Option<Answer>) -> Self {
+        Self {
+            input,
+            answers: [part_1, part_2],
+        }
+    }
+}
+
+pub fn generate(day: u32, options: &Options) -> Result<Generated, String> {
+    if !(1..=25).contains(&day) {
+        return Err(format!("There is no day {day}"));
+    }
+
+    let size = match (SIZES[day as usize - 1], options.size) {
+        (Some(_), Some(size)) => size,
+        (Some((_, default)), None) => default,
+        (None, Some(_)) => return Err(format!("The size of day {day} cannot be changed")),
+        (None, None) => 0,
+    };
+
+    if !options.invalid_fields.is_empty() && day != 4 {
+        return Err(String::from("Only day 4 has fields to invalidate"));
+    }
+
+    let rng = &mut Rng::new(options.seed);
+
+    match day {
+        1 => day_1::generate(rng, size),
+        2 => day_2::generate(rng, size),
+        3 => day_3::generate(rng, size),
+        4 => day_4::generate(rng, size, &options.invalid_fields),
+        5 => day_5::generate(rng, size),
+        6 => day_6::generate(rng, size),
+        7 => day_7::generate(rng, size),
+        8 => day_8::generate(rng, size),
+        9 => day_9::generate(rng, size),
+        10 => day_10::generate(rng, size),
+        11 => day_11::generate(rng, size),
+        12 => day_12::generate(rng, size),
+        13 => day_13::generate(rng, size),
+        14 => day_14::generate(rng, size),
+        15 => day_15::generate(rng, size),
+        16 => day_16::generate(rng, size),
+        17 => day_17::generate(rng, size),
+        18 => day_18::generate(rng, size),
+        19 => day_19::generate(rng, size),
+        20 => day_20::generate(rng, size),
+        21 => day_21::generate(rng, size),
+        22 => day_22::generate(rng, size),
+        23 => day_23::generate(rng),
+        24 => day_24::generate(rng, size),
+        _ => day_25::generate(rng, size),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    use crate::registry;
+
+    fn small(day: u32, seed: u64) -> Options {
+        let size = match day {
+            7 => 3,
+            9 => 60,
+            13 => 5,
+            15 => 3,
+            16 => 8,
+            17 => 3,
+            20 => 3,
+            25 => 5000,
+            _ => 30,
+        };
+
+        Options {
+            seed,
+            size: SIZES[day as usize - 1].map(|_| size),
+            invalid_fields: vec![],
+        }
+    }
+
+    // Every generated input parses, and the solvers agree with every answer the generator knows.
+    #[rstest]
+    fn test_generated_answers(#[values(1, 2, 3)] seed: u64) {
+        for solution in registry::DAYS.iter() {
+            let day = solution.day();
+            let generated = generate(day, &small(day, seed)).unwrap();
+            let input = solution
+                .parse(&generated.input)
+                .unwrap_or_else(|error| panic!("Day {day}: {error}"));
+
+            for (part, answer) in (1..).zip(generated.answers.iter()) {
+                if let Some(answer) = answer {
+                    assert_eq!(
+                        Ok(answer.clone()),
+                        solution.solve(&input, part),
+                        "Day {day} part {part} with seed {seed}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_seed_is_reproducible() {
+        let options = Options::default();
+        let other_seed = Options {
+            seed: 7,
+            ..Options::default()
+        };
+
+        assert_eq!(generate(2, &options), generate(2, &options));
+        assert_ne!(generate(2, &options), generate(2, &other_seed));
+    }
+
+    #[rstest]
+    #[case(26, Options::default())]
+    #[case(23, Options { size: Some(4), ..Options::default() })]
+    #[case(3, Options { invalid_fields: vec![String::from("byr")], ..Options::default() })]
+    fn test_generate_errors(#[case] day: u32, #[case] options: Options) {
+        assert!(generate(day, &options).is_err());
+    }
+}
@@ -0,0 +1,283 @@
+use std::panic::{self, AssertUnwindSafe};
+
+use itertools::Itertools;
+use rustc_hash::FxHashSet;
+
+use crate::error::ParseError;
+use crate::registry;
+use crate::solution::Answer;
+use crate::utilities::input::{puzzle_input, InputSource};
+use crate::utilities::toml;
+
+pub const DEFAULT_LEDGER: &str = "answers.toml";
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Expectation {
+    pub day: u32,
+    pub input: String,
+    pub part: u32,
+    pub answer: String,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Verdict {
+    Pass,
+    Fail { actual: String },
+    MissingInput(String),
+    Error(String),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Check {
+    pub expectation: Expectation,
+    pub verdict: Verdict,
+}
+
+// Tables are named `[day<N>.<input>]` and hold `part_1` and `part_2` answers.
+pub fn parse_ledger(text: &str) -> Result<Vec<Expectation>, ParseError> {
+    toml::parse(text)?
+        .into_iter()
+        .map(|entry| {
+            let error =
+                |message: &str| ParseError::new(entry.text, 1, message).with_line(entry.line);
+
+            let (day, input) = match &entry.table[..] {
+                [day, input] => (day, input),
+                _ => {
+                    return Err(error(
+                        "Expected the answer to be in a `[day<N>.<input>]` table",
+                    ))
+                }
+            };
+
+            let day = day
+                .strip_prefix("day")
+                .and_then(|day| day.parse::<u32>().ok())
+                .filter(|day| (1..=25).contains(day))
+                .ok_or_else(|| error("Expected a table named after a day, like `day1`"))?;
+
+            let part = match entry.key.as_str() {
+                "part_1" => 1,
+                "part_2" => 2,
+                _ => return Err(error("Expected `part_1` or `part_2`")),
+            };
+
+            Ok(Expectation {
+                day,
+                input: input.clone(),
+                part,
+                answer: entry.value.to_string(),
+            })
+        })
+        .collect()
+}
+
+// `example`, `example<suffix>` and `real` follow the usual layout, anything else is a path.
+pub fn input_source(day: u32, input: &str) -> InputSource {
+    match input {
+        "real" => puzzle_input(false, day, None),
+        "example" => puzzle_input(true, day, None),
+        _ => match input.strip_prefix("example") {
+            Some(suffix) => puzzle_input(true, day, Some(suffix)),
+            None => InputSource::Path(String::from(input)),
+        },
+    }
+}
+
+fn check_input(day: u32, input: &str, expectations: &[&Expectation]) -> Vec<Verdict> {
+    let fail_all = |verdict: Verdict| vec![verdict; expectations.len()];
+
+    let Some(solution) = registry::find(day) else {
+        return fail_all(Verdict::Error(format!("Day {day} is not registered")));
+    };
+
+    let source = input_source(day, input);
+    let text = match source.read() {
+        Ok(text) => text,
+        Err(error) => return fail_all(Verdict::MissingInput(error.to_string())),
+    };
+
+    let parsed_input = match panic::catch_unwind(AssertUnwindSafe(|| solution.parse(&text))) {
+        Ok(Ok(parsed_input)) => parsed_input,
+        Ok(Err(error)) => {
+            return fail_all(Verdict::Error(error.with_file(source.name()).to_string()))
+        }
+        Err(_) => return fail_all(Verdict::Error(String::from("Parsing panicked"))),
+    };
+
+    expectations
+        .iter()
+        .map(|expectation| {
+            let part = expectation.part;
+
+            match panic::catch_unwind(AssertUnwindSafe(|| solution.solve(&parsed_input, part))) {
+                Ok(Ok(answer)) if answer.to_string() == expectation.answer => Verdict::Pass,
+                Ok(Ok(answer)) => Verdict::Fail {
+                    actual: answer.to_string(),
+                },
+                Ok(Err(error)) => Verdict::Error(error.to_string()),
+                Err(_) => Verdict::Error(String::from("Solving panicked")),
+            }
+        })
+        .collect()
+}
+
+// Each input is read and parsed once, however many of its parts have answers.
+pub fn verify(expectations: &[Expectation]) -> Vec<Check> {
+    let mut checks = vec![];
+
+    for ((day, input), group) in &expectations
+        .iter()
+        .group_by(|expectation| (expectation.day, expectation.input.clone()))
+    {
+        let group: Vec<&Expectation> = group.collect();
+        let verdicts = check_input(day, &input, &group);
+
+        checks.extend(
+            group
+                .into_iter()
+                .zip(verdicts)
+                .map(|(expectation, verdict)| Check {
+                    expectation: expectation.clone(),
+                    verdict,
+                }),
+        );
+    }
+
+    checks
+}
+
+// The `(day, part)` pairs with a known answer for the plain `example` input, which is what `run --all --test` reads.
+pub fn example_parts(expectations: &[Expectation]) -> FxHashSet<(u32, u32)> {
+    expectations
+        .iter()
+        .filter(|expectation| expectation.input == "example")
+        .map(|expectation| (expectation.day, expectation.part))
+        .collect()
+}
+
+// The `[day<N>."<input>"]` table holding whichever answers are known, ready to append to the ledger.
+pub fn table(day: u32, input: &str, answers: &[Option<Answer>]) -> String {
+    let mut table = format!("[day{day}.{}]\n", toml::quote(input));
+
+    for (part, answer) in (1..).zip(answers) {
+        match answer {
+            Some(Answer::Number(number)) => table.push_str(&format!("part_{part} = {number}\n")),
+            Some(Answer::Text(text)) => {
+                table.push_str(&format!("part_{part} = {}\n", toml::quote(text)))
+            }
+            None => {}
+        }
+    }
+
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn expectation(day: u32, input: &str, part: u32, answer: &str) -> Expectation {
+        Expectation {
+            day,
+            input: String::from(input),
+            part,
+            answer: String::from(answer),
+        }
+    }
+
+    #[test]
+    fn test_parse_ledger() {
+        let ledger = "[day1.example]\npart_1 = 514579\n\n[day21.real]\npart_2 = \"mxmxvkd\"\n";
+
+        assert_eq!(
+            vec![
+                expectation(1, "example", 1, "514579"),
+                expectation(21, "real", 2, "mxmxvkd"),
+            ],
+            parse_ledger(ledger).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_example_parts() {
+        let expectations = [
+            expectation(5, "example", 1, "820"),
+            expectation(5, "real", 2, "522"),
+            expectation(16, "example-b", 2, "1716"),
+        ];
+
+        assert_eq!(FxHashSet::from_iter([(5, 1)]), example_parts(&expectations));
+    }
+
+    #[rstest]
+    #[case("part_1 = 1", 1)]
+    #[case("[day1]\npart_1 = 1", 2)]
+    #[case("[day26.example]\npart_1 = 1", 2)]
+    #[case("[day1.example]\npart_3 = 1", 2)]
+    fn test_parse_ledger_errors(#[case] ledger: &str, #[case] line: usize) {
+        assert_eq!(Some(line), parse_ledger(ledger).unwrap_err().line);
+    }
+
+    #[test]
+    fn test_table_round_trips() {
+        let answers = [None, Some(Answer::Text(String::from("mxmxvkd")))];
+        let table = table(21, "inputs/day21.txt", &answers);
+
+        assert_eq!(
+            "[day21.\"inputs/day21.txt\"]\npart_2 = \"mxmxvkd\"\n",
+            table
+        );
+        assert_eq!(
+            vec![expectation(21, "inputs/day21.txt", 2, "mxmxvkd")],
+            parse_ledger(&table).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_table_round_trips_escaped_text() {
+        let answers = [Some(Answer::Text(String::from(r#"a "b" \c"#))), None];
+        let table = table(21, r"inputs\day21.txt", &answers);
+
+        assert_eq!(
+            "[day21.\"inputs\\\\day21.txt\"]\npart_1 = \"a \\\"b\\\" \\\\c\"\n",
+            table
+        );
+        assert_eq!(
+            vec![expectation(21, r"inputs\day21.txt", 1, r#"a "b" \c"#)],
+            parse_ledger(&table).unwrap()
+        );
+    }
+
+    #[rstest]
+    #[case(16, "example-b", "day16-b.txt")]
+    #[case(1, "example", "day1.txt")]
+    #[case(1, "real", "./data/real/day1.txt")]
+    #[case(1, "inputs/other.txt", "inputs/other.txt")]
+    fn test_input_source(#[case] day: u32, #[case] input: &str, #[case] expected: &str) {
+        assert_eq!(expected, input_source(day, input).name());
+    }
+
+    #[test]
+    fn test_verify() {
+        let expectations = vec![
+            expectation(1, "example", 1, "514579"),
+            expectation(1, "example", 2, "1"),
+            expectation(1, "inputs/missing.txt", 1, "1"),
+        ];
+        let verdicts: Vec<_> = verify(&expectations)
+            .into_iter()
+            .map(|check| check.verdict)
+            .collect();
+
+        assert_eq!(Verdict::Pass, verdicts[0]);
+        assert_eq!(
+            Verdict::Fail {
+                actual: String::from("241861950")
+            },
+            verdicts[1]
+        );
+        assert!(matches!(verdicts[2], Verdict::MissingInput(_)));
+    }
+}
@@ -0,0 +1,42 @@
+#![allow(non_snake_case)]
+pub mod day_1;
+pub mod day_10;
+pub mod day_11;
+pub mod day_12;
+pub mod day_13;
+pub mod day_14;
+pub mod day_15;
+pub mod day_16;
+pub mod day_17;
+pub mod day_18;
+pub mod day_19;
+pub mod day_2;
+pub mod day_20;
+pub mod day_21;
+pub mod day_22;
+pub mod day_23;
+pub mod day_24;
+pub mod day_25;
+pub mod day_3;
+pub mod day_4;
+pub mod day_5;
+pub mod day_6;
+pub mod day_7;
+pub mod day_8;
+pub mod day_9;
+
+pub mod bench;
+pub mod cache;
+pub mod cli;
+pub mod commands;
+pub mod error;
+pub mod generate;
+pub mod ledger;
+pub mod observer;
+pub mod output;
+pub mod registry;
+pub mod render;
+pub mod repl;
+pub mod runner;
+pub mod solution;
+pub mod utilities;
@@ -1,77 +1,42 @@
 #![allow(non_snake_case)]
-#[cfg(test)]
-mod day_1;
-#[cfg(test)]
-mod day_10;
-#[cfg(test)]
-mod day_11;
-#[cfg(test)]
-mod day_12;
-#[cfg(test)]
-mod day_13;
-#[cfg(test)]
-mod day_14;
-#[cfg(test)]
-mod day_15;
-#[cfg(test)]
-mod day_16;
-#[cfg(test)]
-mod day_17;
-#[cfg(test)]
-mod day_18;
-#[cfg(test)]
-mod day_19;
-#[cfg(test)]
-mod day_2;
-#[cfg(test)]
-mod day_20;
-#[cfg(test)]
-mod day_21;
-#[cfg(test)]
-mod day_22;
-#[cfg(test)]
-mod day_23;
-#[cfg(test)]
-mod day_24;
-mod day_25;
-#[cfg(test)]
-mod day_3;
-#[cfg(test)]
-mod day_4;
-#[cfg(test)]
-mod day_5;
-#[cfg(test)]
-mod day_6;
-#[cfg(test)]
-mod day_7;
-#[cfg(test)]
-mod day_8;
-#[cfg(test)]
-mod day_9;
 
-mod utilities;
+use std::env;
+use std::process::ExitCode;
 
-use crate::day_25::part_1;
+use Advent2020::cli::{self, USAGE};
+use Advent2020::commands;
 
-// use crate::day_24::{part_1, part_2};
-// use crate::utilities::file_utilities::get_file_path;
-
-fn main() {
-    let day = 25;
-    let is_test = false;
+// Arguments that cannot be parsed get the usage and exit code 2, parsed ones go to their command.
+fn dispatch<A>(
+    arguments: &[String],
+    parse: fn(&[String]) -> Result<A, String>,
+    command: fn(A) -> ExitCode,
+) -> ExitCode {
+    match parse(arguments) {
+        Ok(arguments) => command(arguments),
+        Err(message) => {
+            eprintln!("{message}\n{USAGE}");
+            ExitCode::from(2)
+        }
+    }
+}
 
-    let data = if is_test {
-        (5764801, 17807724)
-    } else {
-        (9717666, 20089533)
-    };
+fn main() -> ExitCode {
+    let arguments: Vec<String> = env::args().skip(1).collect();
+    let rest = arguments.get(1..).unwrap_or_default();
 
-    println!(
-        "Day {day} Part 1: {}",
-        part_1(data), // get_file_path(is_test, day, None))
-    );
-    // println!(
-    //     "Day {day} Part 2: {}",
-    //     get_file_path(is_test, day, None)),
-    // );
+    match arguments.first().map(String::as_str) {
+        Some("run") => dispatch(rest, cli::parse_run_arguments, commands::run),
+        Some("bench") => dispatch(rest, cli::parse_bench_arguments, commands::bench),
+        Some("verify") => dispatch(rest, cli::parse_verify_arguments, commands::verify),
+        Some("generate") => dispatch(rest, cli::parse_generate_arguments, commands::generate),
+        Some("repl") => dispatch(rest, cli::parse_repl_arguments, commands::repl),
+        Some("render") => dispatch(rest, cli::parse_render_arguments, commands::render),
+        Some("audit") => dispatch(rest, cli::parse_audit_arguments, commands::audit),
+        Some("passwords") => dispatch(rest, cli::parse_passwords_arguments, commands::passwords),
+        _ => {
+            eprintln!("{USAGE}");
+            ExitCode::from(2)
+        }
+    }
 }
@@ -0,0 +1,276 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::Write;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Event {
+    GenerationCompleted {
+        generation: usize,
+        population: usize,
+    },
+    InstructionExecuted {
+        index: usize,
+        accumulator: i32,
+    },
+    NumberSpoken {
+        turn: usize,
+        number: usize,
+    },
+    FieldIdentified {
+        field: String,
+        column: usize,
+    },
+    GroupEvaluated {
+        start: usize,
+        end: usize,
+        value: u64,
+    },
+    ExpressionEvaluated {
+        line: usize,
+        value: u64,
+    },
+    CornerTried {
+        tile: isize,
+        orientation: String,
+    },
+    TilePlaced {
+        tile: isize,
+        row: usize,
+        column: usize,
+    },
+    MonsterFound {
+        row: usize,
+        column: usize,
+    },
+    AllergenIdentified {
+        allergen: String,
+        ingredient: String,
+    },
+    RoundPlayed {
+        game_level: usize,
+        round: usize,
+        cards: [usize; 2],
+        winner: usize,
+    },
+    GameWon {
+        game_level: usize,
+        winner: usize,
+        rounds: usize,
+    },
+    CupsMoved {
+        move_number: usize,
+        current_cup: usize,
+        picked_up: [usize; 3],
+        destination_cup: usize,
+    },
+    LoopSizeFound {
+        public_key: u64,
+        loop_size: u64,
+    },
+}
+
+impl Event {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Event::GenerationCompleted { .. } => "generation completed",
+            Event::InstructionExecuted { .. } => "instruction executed",
+            Event::NumberSpoken { .. } => "number spoken",
+            Event::FieldIdentified { .. } => "field identified",
+            Event::GroupEvaluated { .. } => "group evaluated",
+            Event::ExpressionEvaluated { .. } => "expression evaluated",
+            Event::CornerTried { .. } => "corner tried",
+            Event::TilePlaced { .. } => "tile placed",
+            Event::MonsterFound { .. } => "monster found",
+            Event::AllergenIdentified { .. } => "allergen identified",
+            Event::RoundPlayed { .. } => "round played",
+            Event::GameWon { .. } => "game won",
+            Event::CupsMoved { .. } => "cups moved",
+            Event::LoopSizeFound { .. } => "loop size found",
+        }
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::GenerationCompleted {
+                generation,
+                population,
+            } => write!(f, "Generation {generation} has {population} active cells"),
+            Event::InstructionExecuted { index, accumulator } => {
+                write!(f, "Executed instruction {index}, accumulator is {accumulator}")
+            }
+            Event::NumberSpoken { turn, number } => write!(f, "{number} in turn {turn}"),
+            Event::FieldIdentified { field, column } => write!(f, "{field} is column {column}"),
+            Event::GroupEvaluated { start, end, value } => {
+                write!(f, "Parentheses from {start} to {end} are {value}")
+            }
+            Event::ExpressionEvaluated { line, value } => write!(f, "Line {line} is {value}"),
+            Event::CornerTried { tile, orientation } => {
+                write!(f, "Trying {tile} as first corner with orientation {orientation}")
+            }
+            Event::TilePlaced { tile, row, column } => {
+                write!(f, "Placed tile {tile} at ({row}, {column})")
+            }
+            Event::MonsterFound { row, column } => write!(f, "Monster at ({row}, {column})"),
+            Event::AllergenIdentified {
+                allergen,
+                ingredient,
+            } => write!(f, "{ingredient} contains {allergen}"),
+            Event::RoundPlayed {
+                game_level,
+                round,
+                cards: [player_1_card, player_2_card],
+                winner,
+            } => write!(
+                f,
+                "Game {game_level} round {round}: {player_1_card} against {player_2_card}, player {winner} wins"
+            ),
+            Event::GameWon {
+                game_level,
+                winner,
+                rounds,
+            } => write!(f, "Player {winner} wins game {game_level} after {rounds} rounds"),
+            Event::CupsMoved {
+                move_number,
+                current_cup,
+                picked_up: [first, second, third],
+                destination_cup,
+            } => write!(
+                f,
+                "Move {move_number}: cup {current_cup} moves {first}, {second}, {third} after {destination_cup}"
+            ),
+            Event::LoopSizeFound {
+                public_key,
+                loop_size,
+            } => write!(f, "Found loop size {loop_size} for key {public_key}"),
+        }
+    }
+}
+
+pub trait Observer: Any {
+    fn observe(&mut self, event: &Event);
+
+    // Printed once the observed run is over.
+    fn summary(&self) -> Option<String> {
+        None
+    }
+}
+
+thread_local! {
+    static OBSERVER: RefCell<Option<Box<dyn Observer>>> = RefCell::new(None);
+}
+
+// Solvers call this at interesting points. The event is only built when someone is listening.
+pub fn emit(event: impl FnOnce() -> Event) {
+    OBSERVER.with(|observer| {
+        if let Some(observer) = observer.borrow_mut().as_mut() {
+            observer.observe(&event());
+        }
+    });
+}
+
+// Everything `run` emits on this thread goes to the observer, which is handed back afterwards.
+pub fn observed<O: Observer, R>(observer: O, run: impl FnOnce() -> R) -> (R, O) {
+    let previous = OBSERVER.with(|slot| slot.borrow_mut().replace(Box::new(observer)));
+    let result = run();
+    let observer = OBSERVER.with(|slot| std::mem::replace(&mut *slot.borrow_mut(), previous));
+
+    let observer: Box<dyn Any> = observer.expect("The observer was removed while in use");
+    match observer.downcast::<O>() {
+        Ok(observer) => (result, *observer),
+        Err(_) => panic!("The observer was replaced while in use"),
+    }
+}
+
+pub struct TextLogger<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> TextLogger<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+}
+
+impl<W: Write + 'static> Observer for TextLogger<W> {
+    fn observe(&mut self, event: &Event) {
+        // Tracing must never take the solver down with it.
+        let _ = writeln!(self.writer, "{event}");
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Counter {
+    pub counts: BTreeMap<&'static str, usize>,
+}
+
+impl Observer for Counter {
+    fn observe(&mut self, event: &Event) {
+        *self.counts.entry(event.kind()).or_default() += 1;
+    }
+
+    fn summary(&self) -> Option<String> {
+        let width = self.counts.keys().map(|kind| kind.len()).max().unwrap_or(0);
+
+        Some(
+            self.counts
+                .iter()
+                .map(|(kind, count)| format!("{kind:<width$}  {count}\n"))
+                .collect(),
+        )
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Recorder {
+    pub events: Vec<Event>,
+}
+
+impl Observer for Recorder {
+    fn observe(&mut self, event: &Event) {
+        self.events.push(event.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_only_reach_the_attached_observer() {
+        emit(|| panic!("Nobody is listening, so the event is never built"));
+
+        let ((), recorder) = observed(Recorder::default(), || {
+            emit(|| Event::NumberSpoken { turn: 4, number: 0 });
+            emit(|| Event::NumberSpoken { turn: 5, number: 3 });
+        });
+
+        assert_eq!(
+            vec![
+                Event::NumberSpoken { turn: 4, number: 0 },
+                Event::NumberSpoken { turn: 5, number: 3 }
+            ],
+            recorder.events
+        );
+    }
+
+    #[test]
+    fn test_counter_summary() {
+        let ((), counter) = observed(Counter::default(), || {
+            emit(|| Event::MonsterFound { row: 2, column: 2 });
+            emit(|| Event::MonsterFound { row: 16, column: 1 });
+            emit(|| Event::LoopSizeFound {
+                public_key: 5764801,
+                loop_size: 8,
+            });
+        });
+
+        assert_eq!(
+            Some(String::from("loop size found  1\nmonster found    2\n")),
+            counter.summary()
+        );
+    }
+}
@@ -0,0 +1,203 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::solution::Answer;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            _ => Err(format!(
+                "Unknown format {format}, expected text, json or csv"
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Outcome {
+    pub day: u32,
+    pub part: u32,
+    pub input: String,
+    pub result: Result<Answer, String>,
+    pub elapsed: Duration,
+}
+
+pub fn json_string(text: &str) -> String {
+    let mut json = String::from('"');
+
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+
+    json.push('"');
+    json
+}
+
+// Fields are only quoted when they contain a separator, a quote or a line break.
+pub fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        String::from(text)
+    }
+}
+
+impl Outcome {
+    fn to_text(&self) -> String {
+        match &self.result {
+            Ok(answer) => format!("Day {} Part {}: {answer}", self.day, self.part),
+            Err(error) => format!("Day {} Part {} failed: {error}", self.day, self.part),
+        }
+    }
+
+    fn to_json(&self) -> String {
+        let (answer, error) = match &self.result {
+            Ok(answer) => (answer.to_json(), String::from("null")),
+            Err(error) => (String::from("null"), json_string(error)),
+        };
+
+        format!(
+            "{{\"day\":{},\"part\":{},\"input\":{},\"answer\":{answer},\"elapsed_ns\":{},\"error\":{error}}}",
+            self.day,
+            self.part,
+            json_string(&self.input),
+            self.elapsed.as_nanos(),
+        )
+    }
+
+    fn to_csv(&self) -> String {
+        let (answer, error) = match &self.result {
+            Ok(answer) => (answer.to_string(), String::new()),
+            Err(error) => (String::new(), error.clone()),
+        };
+
+        format!(
+            "{},{},{},{},{},{}",
+            self.day,
+            self.part,
+            csv_field(&self.input),
+            csv_field(&answer),
+            self.elapsed.as_nanos(),
+            csv_field(&error),
+        )
+    }
+}
+
+// JSON is a single array and CSV has a header, so both stay well formed for any number of outcomes.
+pub fn render(outcomes: &[Outcome], format: Format) -> String {
+    match format {
+        Format::Text => outcomes
+            .iter()
+            .map(|outcome| outcome.to_text() + "\n")
+            .collect(),
+        Format::Json => {
+            let records: Vec<String> = outcomes.iter().map(Outcome::to_json).collect();
+            format!("[{}]\n", records.join(","))
+        }
+        Format::Csv => {
+            let mut csv = String::from("day,part,input,answer,elapsed_ns,error\n");
+            for outcome in outcomes {
+                csv.push_str(&outcome.to_csv());
+                csv.push('\n');
+            }
+            csv
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn outcome(result: Result<Answer, String>) -> Outcome {
+        Outcome {
+            day: 23,
+            part: 1,
+            input: String::from("day23.txt"),
+            result,
+            elapsed: Duration::from_nanos(1500),
+        }
+    }
+
+    #[rstest]
+    #[case("plain", "\"plain\"")]
+    #[case("say \"hi\"", "\"say \\\"hi\\\"\"")]
+    #[case("a\\b\nc", "\"a\\\\b\\nc\"")]
+    #[case("\u{1}", "\"\\u0001\"")]
+    fn test_json_string(#[case] text: &str, #[case] expected: &str) {
+        assert_eq!(expected, json_string(text));
+    }
+
+    #[rstest]
+    #[case("plain", "plain")]
+    #[case("mxmxvkd,sqjhc", "\"mxmxvkd,sqjhc\"")]
+    #[case("say \"hi\"", "\"say \"\"hi\"\"\"")]
+    fn test_csv_field(#[case] text: &str, #[case] expected: &str) {
+        assert_eq!(expected, csv_field(text));
+    }
+
+    #[rstest]
+    #[case(Format::Text, "Day 23 Part 1: 67384529\n")]
+    #[case(
+        Format::Json,
+        "[{\"day\":23,\"part\":1,\"input\":\"day23.txt\",\"answer\":\"67384529\",\"elapsed_ns\":1500,\"error\":null}]\n"
+    )]
+    #[case(
+        Format::Csv,
+        "day,part,input,answer,elapsed_ns,error\n23,1,day23.txt,67384529,1500,\n"
+    )]
+    fn test_render_answer(#[case] format: Format, #[case] expected: &str) {
+        let outcomes = vec![outcome(Ok(Answer::from(String::from("67384529"))))];
+
+        assert_eq!(expected, render(&outcomes, format));
+    }
+
+    #[rstest]
+    #[case(Format::Text, "Day 23 Part 1 failed: No cups\n")]
+    #[case(
+        Format::Json,
+        "[{\"day\":23,\"part\":1,\"input\":\"day23.txt\",\"answer\":null,\"elapsed_ns\":1500,\"error\":\"No cups\"}]\n"
+    )]
+    #[case(
+        Format::Csv,
+        "day,part,input,answer,elapsed_ns,error\n23,1,day23.txt,,1500,No cups\n"
+    )]
+    fn test_render_error(#[case] format: Format, #[case] expected: &str) {
+        let outcomes = vec![outcome(Err(String::from("No cups")))];
+
+        assert_eq!(expected, render(&outcomes, format));
+    }
+
+    #[rstest]
+    #[case("text", Ok(Format::Text))]
+    #[case("json", Ok(Format::Json))]
+    #[case("csv", Ok(Format::Csv))]
+    #[case(
+        "xml",
+        Err(String::from("Unknown format xml, expected text, json or csv"))
+    )]
+    fn test_parse_format(#[case] format: &str, #[case] expected: Result<Format, String>) {
+        assert_eq!(expected, format.parse::<Format>());
+    }
+}
@@ -0,0 +1,56 @@
+use crate::solution::DynSolution;
+use crate::{
+    day_1, day_10, day_11, day_12, day_13, day_14, day_15, day_16, day_17, day_18, day_19, day_2,
+    day_20, day_21, day_22, day_23, day_24, day_25, day_3, day_4, day_5, day_6, day_7, day_8,
+    day_9,
+};
+
+pub static DAYS: [&dyn DynSolution; 25] = [
+    &day_1::Day1,
+    &day_2::Day2,
+    &day_3::Day3,
+    &day_4::Day4,
+    &day_5::Day5,
+    &day_6::Day6,
+    &day_7::Day7,
+    &day_8::Day8,
+    &day_9::Day9,
+    &day_10::Day10,
+    &day_11::Day11,
+    &day_12::Day12,
+    &day_13::Day13,
+    &day_14::Day14,
+    &day_15::Day15,
+    &day_16::Day16,
+    &day_17::Day17,
+    &day_18::Day18,
+    &day_19::Day19,
+    &day_20::Day20,
+    &day_21::Day21,
+    &day_22::Day22,
+    &day_23::Day23,
+    &day_24::Day24,
+    &day_25::Day25,
+];
+
+pub fn find(day: u32) -> Option<&'static dyn DynSolution> {
+    DAYS.iter().copied().find(|solution| solution.day() == day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_days_are_registered_in_order() {
+        for (index, solution) in DAYS.iter().enumerate() {
+            assert_eq!(index as u32 + 1, solution.day());
+        }
+    }
+
+    #[test]
+    fn test_find() {
+        assert_eq!(Some(7), find(7).map(|solution| solution.day()));
+        assert!(find(26).is_none());
+    }
+}
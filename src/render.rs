@@ -0,0 +1,138 @@
+use std::path::Path;
+
+use crate::error::SolveError;
+use crate::utilities::image::Scene;
+use crate::{day_11, day_12, day_17, day_20, day_24};
+
+// The days with something to draw, and those among them that can draw each generation.
+pub const DAYS: [u32; 5] = [11, 12, 17, 20, 24];
+pub const SIMULATIONS: [u32; 3] = [11, 17, 24];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Options {
+    pub part: u32,
+    pub frames: bool,
+    // Pixels per unit, replacing the scale each day picks for its scenes.
+    pub scale: Option<f64>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            part: 1,
+            frames: false,
+            scale: None,
+        }
+    }
+}
+
+pub fn render(day: u32, text: &str, options: &Options) -> Result<Vec<Scene>, SolveError> {
+    let part = options.part;
+    if !(1..=2).contains(&part) {
+        return Err(SolveError::MissingPart { day, part });
+    }
+
+    let mut scenes = match day {
+        11 => day_11::render(&day_11::parse_data(text)?, part, options.frames),
+        12 => day_12::render(&day_12::parse_data(text)?, part),
+        17 => day_17::render(&day_17::parse_data(text)?, part, options.frames),
+        20 => day_20::render(&day_20::parse_data(text)?, part),
+        24 => day_24::render(&day_24::parse_data(text)?, part, options.frames),
+        _ => Err(SolveError::NoSolution(format!(
+            "Day {day} has nothing to draw"
+        ))),
+    }?;
+
+    if let Some(scale) = options.scale {
+        for scene in scenes.iter_mut() {
+            scene.scale = scale;
+        }
+    }
+
+    Ok(scenes)
+}
+
+// Frames are numbered just before the extension, so seats.svg becomes seats-000.svg, seats-001.svg, ...
+pub fn frame_path(path: &str, frame: usize) -> String {
+    let path = Path::new(path);
+    let stem = path.with_extension("");
+    let number = format!("{}-{frame:03}", stem.display());
+
+    match path.extension() {
+        Some(extension) => format!("{number}.{}", extension.to_string_lossy()),
+        None => number,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    use crate::utilities::input::puzzle_input;
+
+    #[rstest]
+    #[case(11, false, 1)]
+    #[case(11, true, 6)]
+    #[case(12, false, 1)]
+    #[case(17, true, 7)]
+    #[case(20, false, 1)]
+    #[case(24, false, 1)]
+    fn test_render(#[case] day: u32, #[case] frames: bool, #[case] expected: usize) {
+        let text = puzzle_input(true, day, None).read().unwrap();
+        let options = Options {
+            frames,
+            scale: Some(2.0),
+            ..Options::default()
+        };
+        let scenes = render(day, &text, &options).unwrap();
+
+        assert_eq!(expected, scenes.len());
+        assert!(scenes.iter().all(|scene| scene.scale == 2.0));
+    }
+
+    // Every scene has to come out as pixels too, not only as SVG.
+    #[rstest]
+    #[case(12)]
+    #[case(24)]
+    fn test_rasterize(#[case] day: u32) {
+        let text = puzzle_input(true, day, None).read().unwrap();
+        let scenes = render(day, &text, &Options::default()).unwrap();
+
+        for scene in scenes {
+            let canvas = scene.rasterize();
+            assert!(canvas
+                .cells()
+                .iter()
+                .any(|color| *color != scene.background));
+        }
+    }
+
+    #[rstest]
+    #[case(
+        3,
+        1,
+        Err(SolveError::NoSolution(String::from("Day 3 has nothing to draw")))
+    )]
+    #[case(11, 3, Err(SolveError::MissingPart { day: 11, part: 3 }))]
+    fn test_render_errors(
+        #[case] day: u32,
+        #[case] part: u32,
+        #[case] expected: Result<Vec<Scene>, SolveError>,
+    ) {
+        let options = Options {
+            part,
+            ..Options::default()
+        };
+
+        assert_eq!(expected, render(day, "", &options));
+    }
+
+    #[rstest]
+    #[case("seats.svg", 7, "seats-007.svg")]
+    #[case("out/floor.ppm", 12, "out/floor-012.ppm")]
+    #[case("frame", 0, "frame-000")]
+    fn test_frame_path(#[case] path: &str, #[case] frame: usize, #[case] expected: &str) {
+        assert_eq!(expected, frame_path(path, frame));
+    }
+}
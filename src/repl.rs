@@ -0,0 +1,171 @@
+use crate::day_17::{CubeSession, Day17};
+use crate::day_18::{Day18, ExpressionSession};
+use crate::day_22::{CombatSession, Day22};
+use crate::day_3::{Day3, SlopeSession};
+use crate::day_7::{BagSession, Day7};
+use crate::day_8::{ConsoleSession, Day8};
+use crate::error::ParseError;
+use crate::solution::{typed, DynSolution, ParsedInput};
+
+// Day specific commands, run against state that lives as long as the REPL.
+pub trait Session {
+    // Usage and description of each command.
+    fn commands(&self) -> Vec<(&'static str, &'static str)>;
+
+    // `None` when the command is not one of this session's.
+    fn execute(&mut self, command: &str, arguments: &[&str]) -> Option<Result<String, String>>;
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Reply {
+    Output(String),
+    Quit,
+}
+
+const COMMANDS: [(&str, &str); 3] = [
+    ("help", "List the commands"),
+    ("solve <1|2>", "Solve a part of the puzzle"),
+    ("quit", "Leave the REPL"),
+];
+
+pub struct Repl {
+    solution: &'static dyn DynSolution,
+    input: ParsedInput,
+    session: Option<Box<dyn Session>>,
+}
+
+impl Repl {
+    // The input is parsed once, every command then works from the parsed structure.
+    pub fn new(solution: &'static dyn DynSolution, text: &str) -> Result<Self, ParseError> {
+        let input = solution.parse(text)?;
+        let session = open_session(solution.day(), &input);
+
+        Ok(Self {
+            solution,
+            input,
+            session,
+        })
+    }
+
+    pub fn prompt(&self) -> String {
+        format!("day{}> ", self.solution.day())
+    }
+
+    pub fn execute(&mut self, line: &str) -> Result<Reply, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some((command, arguments)) = words.split_first() else {
+            return Ok(Reply::Output(String::new()));
+        };
+
+        match *command {
+            "help" => Ok(Reply::Output(self.help())),
+            "quit" | "exit" => Ok(Reply::Quit),
+            "solve" => {
+                let part = match arguments {
+                    [part] => part
+                        .parse()
+                        .map_err(|_| format!("Expected a part, got {part}"))?,
+                    _ => return Err(String::from("Usage: solve <1|2>")),
+                };
+
+                self.solution
+                    .solve(&self.input, part)
+                    .map(|answer| Reply::Output(answer.to_string()))
+                    .map_err(|error| error.to_string())
+            }
+            _ => match &mut self.session {
+                Some(session) => session.execute(command, arguments),
+                None => None,
+            }
+            .unwrap_or_else(|| Err(format!("Unknown command {command}, try help")))
+            .map(Reply::Output),
+        }
+    }
+
+    fn help(&self) -> String {
+        let session_commands = self
+            .session
+            .as_ref()
+            .map_or(vec![], |session| session.commands());
+        let commands: Vec<_> = COMMANDS.iter().copied().chain(session_commands).collect();
+        let width = commands
+            .iter()
+            .map(|(usage, _)| usage.len())
+            .max()
+            .unwrap_or(0);
+
+        commands
+            .iter()
+            .map(|(usage, description)| format!("{usage:<width$}  {description}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn open_session(day: u32, input: &ParsedInput) -> Option<Box<dyn Session>> {
+    match day {
+        3 => Some(Box::new(SlopeSession::new(typed::<Day3>(input)))),
+        7 => Some(Box::new(BagSession::new(typed::<Day7>(input)))),
+        8 => Some(Box::new(ConsoleSession::new(typed::<Day8>(input)))),
+        17 => Some(Box::new(CubeSession::new(typed::<Day17>(input)))),
+        18 => Some(Box::new(ExpressionSession::new(typed::<Day18>(input)))),
+        22 => Some(Box::new(CombatSession::new(typed::<Day22>(input)))),
+        _ => None,
+    }
+}
+
+// The optional repeat count taken by stepping commands.
+pub fn count_argument(arguments: &[&str]) -> Result<usize, String> {
+    match arguments {
+        [] => Ok(1),
+        [count] => count
+            .parse()
+            .map_err(|_| format!("Expected a count, got {count}")),
+        _ => Err(String::from("Expected at most one count")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    use crate::registry;
+
+    fn repl(day: u32, text: &str) -> Repl {
+        Repl::new(registry::find(day).unwrap(), text).unwrap()
+    }
+
+    #[rstest]
+    #[case("solve 1", Ok(Reply::Output(String::from("5"))))]
+    #[case("", Ok(Reply::Output(String::new())))]
+    #[case("exit", Ok(Reply::Quit))]
+    #[case("solve", Err(String::from("Usage: solve <1|2>")))]
+    #[case("fly", Err(String::from("Unknown command fly, try help")))]
+    fn test_execute(#[case] line: &str, #[case] expected: Result<Reply, String>) {
+        let mut repl = repl(
+            8,
+            "nop +0\nacc +1\njmp +4\nacc +3\njmp -3\nacc -99\nacc +1\njmp -4\nacc +6",
+        );
+
+        assert_eq!(expected, repl.execute(line));
+    }
+
+    #[test]
+    fn test_help_lists_session_commands() {
+        let Ok(Reply::Output(help)) = repl(18, "1 + 2").execute("help") else {
+            panic!("help should print the commands")
+        };
+
+        assert!(help.contains("solve <1|2>"));
+        assert!(help.contains("eval <expression>"));
+    }
+
+    #[rstest]
+    #[case(&[], Ok(1))]
+    #[case(&["12"], Ok(12))]
+    #[case(&["many"], Err(String::from("Expected a count, got many")))]
+    fn test_count_argument(#[case] arguments: &[&str], #[case] expected: Result<usize, String>) {
+        assert_eq!(expected, count_argument(arguments));
+    }
+}
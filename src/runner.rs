@@ -0,0 +1,217 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::cache::InputCache;
+use crate::error::SolveError;
+use crate::observer::{self, Observer};
+use crate::output::Outcome;
+use crate::solution::DynSolution;
+use crate::utilities::input::{puzzle_input, InputSource};
+
+#[derive(Clone)]
+pub struct Job {
+    pub solution: &'static dyn DynSolution,
+    pub part: u32,
+    pub source: InputSource,
+}
+
+// Every registered part of every day, each reading its own copy of the input.
+pub fn all_jobs(solutions: &[&'static dyn DynSolution], is_test: bool) -> Vec<Job> {
+    solutions
+        .iter()
+        .flat_map(|solution| {
+            (1..=solution.parts()).map(move |part| Job {
+                solution: *solution,
+                part,
+                source: puzzle_input(is_test, solution.day(), None),
+            })
+        })
+        .collect()
+}
+
+pub fn run(job: &Job) -> Outcome {
+    run_cached(job, InputCache::shared())
+}
+
+// The parsed input comes from the cache, and goes back to it afterwards with whatever the solver worked out.
+pub fn run_cached(job: &Job, cache: &InputCache) -> Outcome {
+    let start = Instant::now();
+    let result = job.source.read().and_then(|text| {
+        let solve = || {
+            let (input, _) = cache.parsed(job.solution, &text)?;
+            let answer = job.solution.solve(&input, job.part);
+            cache.save(job.solution, &text, &input);
+            answer
+        };
+
+        match panic::catch_unwind(AssertUnwindSafe(solve)) {
+            Ok(result) => result.map_err(|error| error.with_file(job.source.name())),
+            Err(_) => Err(SolveError::NoSolution(String::from("panicked"))),
+        }
+    });
+
+    Outcome {
+        day: job.solution.day(),
+        part: job.part,
+        input: job.source.name(),
+        result: result.map_err(|error| error.to_string()),
+        elapsed: start.elapsed(),
+    }
+}
+
+// Whatever the solver emits while running the job reaches the observer, which is handed back.
+pub fn run_observed<O: Observer>(job: &Job, observer: O) -> (Outcome, O) {
+    observer::observed(observer, || run(job))
+}
+
+// A solver cannot be stopped from outside, so one that overruns is left detached and reported as timed out.
+// The detached thread no longer holds a worker, so it runs on top of the `--jobs` limit until it finishes or the process exits.
+pub fn run_with_timeout(job: Job, timeout: Duration) -> Outcome {
+    let (sender, receiver) = mpsc::channel();
+    let timed_out = Outcome {
+        day: job.solution.day(),
+        part: job.part,
+        input: job.source.name(),
+        result: Err(format!("Timed out after {timeout:.2?}")),
+        elapsed: timeout,
+    };
+
+    thread::spawn(move || sender.send(run(&job)));
+
+    receiver.recv_timeout(timeout).unwrap_or(timed_out)
+}
+
+// Outcomes come back in the order of the jobs, whichever worker finished them.
+pub fn run_all(jobs: Vec<Job>, workers: usize, timeout: Duration) -> Vec<Outcome> {
+    let count = jobs.len();
+    let queue = Mutex::new(jobs.into_iter().enumerate());
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..workers.clamp(1, count.max(1)) {
+            let sender = sender.clone();
+            let queue = &queue;
+
+            scope.spawn(move || loop {
+                let Some((index, job)) = queue.lock().unwrap().next() else {
+                    break;
+                };
+                sender
+                    .send((index, run_with_timeout(job, timeout)))
+                    .unwrap();
+            });
+        }
+    });
+    drop(sender);
+
+    let mut outcomes: Vec<_> = receiver.into_iter().collect();
+    outcomes.sort_by_key(|(index, _)| *index);
+    outcomes.into_iter().map(|(_, outcome)| outcome).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    use crate::cache::Lookup;
+    use crate::error::ParseError;
+    use crate::observer::Counter;
+    use crate::registry;
+    use crate::solution::{Answer, Solution};
+
+    struct Slow;
+
+    impl Solution for Slow {
+        const DAY: u32 = 26;
+        const PARTS: u32 = 1;
+
+        type Input = u64;
+
+        fn parse(input: &str) -> Result<Self::Input, ParseError> {
+            Ok(input.len() as u64)
+        }
+
+        fn part_1(input: &Self::Input) -> Result<Answer, SolveError> {
+            thread::sleep(Duration::from_millis(*input));
+            Ok(Answer::from(*input))
+        }
+    }
+
+    fn slow_job(millis: usize) -> Job {
+        Job {
+            solution: &Slow,
+            part: 1,
+            source: InputSource::from("x".repeat(millis).as_str()),
+        }
+    }
+
+    #[rstest]
+    #[case(1, Ok(Answer::from(1_u64)))]
+    #[case(500, Err(String::from("Timed out after 50.00ms")))]
+    fn test_run_with_timeout(#[case] millis: usize, #[case] expected: Result<Answer, String>) {
+        let outcome = run_with_timeout(slow_job(millis), Duration::from_millis(50));
+
+        assert_eq!(expected, outcome.result);
+    }
+
+    #[test]
+    fn test_run_observed() {
+        let job = Job {
+            solution: registry::find(22).unwrap(),
+            part: 1,
+            source: puzzle_input(true, 22, None),
+        };
+
+        let (outcome, counter) = run_observed(&job, Counter::default());
+
+        assert_eq!(Ok(Answer::from(306_i64)), outcome.result);
+        assert_eq!(
+            vec![("game won", 1), ("round played", 29)],
+            counter.counts.into_iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_run_cached_shares_the_parse_between_parts() {
+        let cache = InputCache::new();
+        let job = |part| Job {
+            solution: registry::find(9).unwrap(),
+            part,
+            source: puzzle_input(true, 9, None),
+        };
+
+        assert_eq!(Ok(Answer::from(127)), run_cached(&job(1), &cache).result);
+        assert_eq!(Ok(Answer::from(62)), run_cached(&job(2), &cache).result);
+
+        let text = job(1).source.read().unwrap();
+        let (_, lookup) = cache.parsed(job(1).solution, &text).unwrap();
+        assert_eq!(Lookup::Memory, lookup);
+    }
+
+    #[test]
+    fn test_run_reports_missing_input() {
+        let job = Job {
+            solution: registry::find(1).unwrap(),
+            part: 1,
+            source: InputSource::Path(String::from("./missing/day1.txt")),
+        };
+
+        assert!(run(&job).result.unwrap_err().contains("./missing/day1.txt"));
+    }
+
+    #[test]
+    fn test_run_all_keeps_job_order() {
+        let jobs = all_jobs(&registry::DAYS[..3], true);
+        let outcomes = run_all(jobs, 4, Duration::from_secs(10));
+
+        let parts: Vec<_> = outcomes
+            .iter()
+            .map(|outcome| (outcome.day, outcome.part))
+            .collect();
+        assert_eq!(vec![(1, 1), (1, 2), (2, 1), (2, 2), (3, 1), (3, 2)], parts);
+        assert_eq!(Ok(Answer::from(514579)), outcomes[0].result);
+    }
+}
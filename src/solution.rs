@@ -0,0 +1,166 @@
+use std::any::Any;
+use std::fmt;
+
+use num::{BigInt, BigUint};
+
+use crate::error::{ParseError, SolveError};
+use crate::output::json_string;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Answer {
+    Number(BigInt),
+    Text(String),
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Number(number) => write!(f, "{number}"),
+            Answer::Text(text) => write!(f, "{text}"),
+        }
+    }
+}
+
+impl Answer {
+    // Numbers stay bare so dashboards can sort on them, text answers become JSON strings.
+    pub fn to_json(&self) -> String {
+        match self {
+            Answer::Number(number) => number.to_string(),
+            Answer::Text(text) => json_string(text),
+        }
+    }
+}
+
+macro_rules! answer_from_number {
+    ($($number_type:ty),*) => {
+        $(
+            impl From<$number_type> for Answer {
+                fn from(number: $number_type) -> Self {
+                    Answer::Number(BigInt::from(number))
+                }
+            }
+        )*
+    };
+}
+
+answer_from_number!(i32, i64, i128, u32, u64, u128, usize, BigInt, BigUint);
+
+impl From<String> for Answer {
+    fn from(text: String) -> Self {
+        Answer::Text(text)
+    }
+}
+
+pub trait Solution {
+    const DAY: u32;
+    const PARTS: u32 = 2;
+
+    type Input: Send + Sync + 'static;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError>;
+
+    fn part_1(input: &Self::Input) -> Result<Answer, SolveError>;
+
+    fn part_2(_input: &Self::Input) -> Result<Answer, SolveError> {
+        Err(SolveError::MissingPart {
+            day: Self::DAY,
+            part: 2,
+        })
+    }
+
+    // Days that can store their parsed input in the on-disk cache override both of these.
+    fn encode(_input: &Self::Input) -> Option<Vec<u8>> {
+        None
+    }
+
+    fn decode(_bytes: &[u8]) -> Option<Self::Input> {
+        None
+    }
+}
+
+pub type ParsedInput = Box<dyn Any + Send + Sync>;
+
+// Object-safe view of a Solution, so days with different input types can share a registry.
+pub trait DynSolution: Sync {
+    fn day(&self) -> u32;
+
+    fn parts(&self) -> u32;
+
+    fn parse(&self, input: &str) -> Result<ParsedInput, ParseError>;
+
+    fn solve(&self, input: &ParsedInput, part: u32) -> Result<Answer, SolveError>;
+
+    fn encode(&self, input: &ParsedInput) -> Option<Vec<u8>>;
+
+    fn decode(&self, bytes: &[u8]) -> Option<ParsedInput>;
+
+    fn run(&self, input: &str, part: u32) -> Result<Answer, SolveError> {
+        self.solve(&self.parse(input)?, part)
+    }
+}
+
+impl<S: Solution + Sync> DynSolution for S {
+    fn day(&self) -> u32 {
+        S::DAY
+    }
+
+    fn parts(&self) -> u32 {
+        S::PARTS
+    }
+
+    fn parse(&self, input: &str) -> Result<ParsedInput, ParseError> {
+        Ok(Box::new(S::parse(input)?))
+    }
+
+    fn solve(&self, input: &ParsedInput, part: u32) -> Result<Answer, SolveError> {
+        match part {
+            1 => S::part_1(typed::<S>(input)),
+            2 if S::PARTS >= 2 => S::part_2(typed::<S>(input)),
+            _ => Err(SolveError::MissingPart { day: S::DAY, part }),
+        }
+    }
+
+    fn encode(&self, input: &ParsedInput) -> Option<Vec<u8>> {
+        S::encode(typed::<S>(input))
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Option<ParsedInput> {
+        Some(Box::new(S::decode(bytes)?))
+    }
+}
+
+// The concrete input of a day, for code that knows which day it holds.
+pub fn typed<S: Solution>(input: &ParsedInput) -> &S::Input {
+    input
+        .downcast_ref()
+        .unwrap_or_else(|| panic!("Day {} was given another day's input", S::DAY))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(Answer::from(514579_i32), "514579")]
+    #[case(Answer::from(-1_i64), "-1")]
+    #[case(Answer::from(usize::MAX), "18446744073709551615")]
+    #[case(
+        Answer::from(String::from("mxmxvkd,sqjhc,fvjkl")),
+        "mxmxvkd,sqjhc,fvjkl"
+    )]
+    fn test_answer_display(#[case] answer: Answer, #[case] expected: &str) {
+        assert_eq!(expected, answer.to_string());
+    }
+
+    #[rstest]
+    #[case(Answer::from(-1_i64), "-1")]
+    #[case(Answer::from(String::from("67384529")), "\"67384529\"")]
+    #[case(
+        Answer::from(String::from("mxmxvkd,sqjhc,fvjkl")),
+        "\"mxmxvkd,sqjhc,fvjkl\""
+    )]
+    fn test_answer_to_json(#[case] answer: Answer, #[case] expected: &str) {
+        assert_eq!(expected, answer.to_json());
+    }
+}
@@ -1 +1,11 @@
+pub mod automaton;
+pub mod binary;
 pub mod file_utilities;
+pub mod grammar;
+pub mod grid;
+pub mod image;
+pub mod input;
+#[cfg(test)]
+pub mod property;
+pub mod random;
+pub mod toml;
@@ -0,0 +1,274 @@
+use crate::error::SolveError;
+use crate::observer::{self, Event};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::ops::RangeInclusive;
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+pub trait Cell: Clone + Eq + Hash {}
+
+impl<T: Clone + Eq + Hash> Cell for T {}
+
+// A cell is born or survives when its count of active neighbours falls in the matching range.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Rule {
+    pub birth: RangeInclusive<usize>,
+    pub survival: RangeInclusive<usize>,
+}
+
+pub type Neighbourhood<'a, C> = Box<dyn Fn(&C) -> Vec<C> + 'a>;
+
+// Only active cells are stored. Rules that give birth with no active neighbours need a bounded universe.
+pub struct Automaton<'a, C: Cell> {
+    active: FxHashSet<C>,
+    universe: Option<FxHashSet<C>>,
+    neighbours: Neighbourhood<'a, C>,
+    rule: Rule,
+    generation: usize,
+}
+
+impl<'a, C: Cell> Automaton<'a, C> {
+    pub fn new(
+        active: impl IntoIterator<Item = C>,
+        neighbours: impl Fn(&C) -> Vec<C> + 'a,
+        rule: Rule,
+    ) -> Self {
+        Self {
+            active: active.into_iter().collect(),
+            universe: None,
+            neighbours: Box::new(neighbours),
+            rule,
+            generation: 0,
+        }
+    }
+
+    pub fn bounded(mut self, universe: impl IntoIterator<Item = C>) -> Self {
+        let universe: FxHashSet<C> = universe.into_iter().collect();
+        self.active.retain(|cell| universe.contains(cell));
+        self.universe = Some(universe);
+        self
+    }
+
+    pub fn active(&self) -> &FxHashSet<C> {
+        &self.active
+    }
+
+    pub fn population(&self) -> usize {
+        self.active.len()
+    }
+
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    fn neighbour_counts(&self) -> FxHashMap<C, usize> {
+        let mut counts = FxHashMap::default();
+
+        for cell in self.active.iter() {
+            for neighbour in (self.neighbours)(cell) {
+                *counts.entry(neighbour).or_insert(0) += 1;
+            }
+        }
+
+        counts
+    }
+
+    fn is_alive_next(&self, cell: &C, counts: &FxHashMap<C, usize>) -> bool {
+        let count = counts.get(cell).copied().unwrap_or(0);
+
+        if self.active.contains(cell) {
+            self.rule.survival.contains(&count)
+        } else {
+            self.rule.birth.contains(&count)
+        }
+    }
+
+    // Returns whether any cell changed state.
+    pub fn step(&mut self) -> bool {
+        let counts = self.neighbour_counts();
+
+        let next: FxHashSet<C> = match &self.universe {
+            Some(universe) => universe
+                .iter()
+                .filter(|cell| self.is_alive_next(cell, &counts))
+                .cloned()
+                .collect(),
+            None => counts
+                .keys()
+                .chain(self.active.iter())
+                .filter(|cell| self.is_alive_next(cell, &counts))
+                .cloned()
+                .collect(),
+        };
+
+        let changed = next != self.active;
+        self.active = next;
+        self.generation += 1;
+
+        observer::emit(|| Event::GenerationCompleted {
+            generation: self.generation,
+            population: self.active.len(),
+        });
+
+        changed
+    }
+
+    pub fn run(&mut self, generations: usize) {
+        for _ in 0..generations {
+            self.step();
+        }
+    }
+
+    // The current generation and each one after it, until `generations` have run or nothing changes any more.
+    pub fn frames<T>(&mut self, generations: usize, draw: impl Fn(&FxHashSet<C>) -> T) -> Vec<T> {
+        let mut frames = vec![draw(&self.active)];
+
+        for _ in 0..generations {
+            if !self.step() {
+                break;
+            }
+            frames.push(draw(&self.active));
+        }
+
+        frames
+    }
+
+    // The cells are hashed one by one and summed, so the order of the set does not matter.
+    fn fingerprint(&self) -> u64 {
+        self.active.iter().fold(0, |sum: u64, cell| {
+            let mut hasher = DefaultHasher::new();
+            cell.hash(&mut hasher);
+            sum.wrapping_add(hasher.finish())
+        })
+    }
+
+    // Steps until nothing changes, or fails once a generation repeats an earlier one and the cells cycle forever.
+    fn step_to_fixed_point(&mut self, mut on_change: impl FnMut(&Self)) -> Result<(), SolveError> {
+        let mut seen = FxHashSet::default();
+        seen.insert(self.fingerprint());
+
+        while self.step() {
+            if !seen.insert(self.fingerprint()) {
+                return Err(SolveError::NoSolution(format!(
+                    "Generation {} repeats an earlier one, so the cells never settle",
+                    self.generation
+                )));
+            }
+            on_change(self);
+        }
+
+        Ok(())
+    }
+
+    // Every generation up to the one where nothing changes any more.
+    pub fn frames_to_fixed_point<T>(
+        &mut self,
+        draw: impl Fn(&FxHashSet<C>) -> T,
+    ) -> Result<Vec<T>, SolveError> {
+        let mut frames = vec![draw(&self.active)];
+        self.step_to_fixed_point(|automaton| frames.push(draw(&automaton.active)))?;
+        Ok(frames)
+    }
+
+    // The generation that confirmed nothing changes any more is counted too.
+    pub fn run_to_fixed_point(&mut self) -> Result<usize, SolveError> {
+        self.step_to_fixed_point(|_| {})?;
+        Ok(self.generation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn square_neighbours(&(x, y): &(i32, i32)) -> Vec<(i32, i32)> {
+        (-1..=1)
+            .flat_map(|dx| (-1..=1).map(move |dy| (dx, dy)))
+            .filter(|&delta| delta != (0, 0))
+            .map(|(dx, dy)| (x + dx, y + dy))
+            .collect()
+    }
+
+    fn life() -> Rule {
+        Rule {
+            birth: 3..=3,
+            survival: 2..=3,
+        }
+    }
+
+    #[rstest]
+    #[case(0, vec![(0, 1), (1, 1), (2, 1)])]
+    #[case(1, vec![(1, 0), (1, 1), (1, 2)])]
+    #[case(2, vec![(0, 1), (1, 1), (2, 1)])]
+    fn test_blinker(#[case] generations: usize, #[case] expected: Vec<(i32, i32)>) {
+        let mut automaton = Automaton::new([(0, 1), (1, 1), (2, 1)], square_neighbours, life());
+        automaton.run(generations);
+
+        assert_eq!(generations, automaton.generation());
+        assert_eq!(
+            expected.into_iter().collect::<FxHashSet<_>>(),
+            *automaton.active()
+        );
+    }
+
+    #[test]
+    fn test_frames_stop_at_fixed_point() {
+        let mut automaton = Automaton::new([(0, 0), (0, 1), (1, 0)], square_neighbours, life());
+
+        assert_eq!(vec![3, 4], automaton.frames(10, |active| active.len()));
+        assert_eq!(2, automaton.generation());
+    }
+
+    #[test]
+    fn test_block_is_a_fixed_point() {
+        let mut automaton =
+            Automaton::new([(0, 0), (0, 1), (1, 0), (1, 1)], square_neighbours, life());
+
+        assert_eq!(Ok(1), automaton.run_to_fixed_point());
+        assert_eq!(4, automaton.population());
+    }
+
+    #[test]
+    fn test_blinker_never_settles() {
+        let mut automaton = Automaton::new([(0, 1), (1, 1), (2, 1)], square_neighbours, life());
+
+        assert_eq!(
+            Err(SolveError::NoSolution(String::from(
+                "Generation 2 repeats an earlier one, so the cells never settle"
+            ))),
+            automaton.run_to_fixed_point()
+        );
+        assert!(automaton
+            .frames_to_fixed_point(|active| active.len())
+            .is_err());
+    }
+
+    #[test]
+    fn test_frames_to_fixed_point() {
+        let mut automaton = Automaton::new([(0, 0), (0, 1), (1, 0)], square_neighbours, life());
+
+        assert_eq!(
+            Ok(vec![3, 4]),
+            automaton.frames_to_fixed_point(|active| active.len())
+        );
+        assert_eq!(2, automaton.generation());
+    }
+
+    #[test]
+    fn test_bounded_birth_without_neighbours() {
+        let universe = [(0, 0), (0, 1), (5, 5)];
+        let rule = Rule {
+            birth: 0..=0,
+            survival: 0..=0,
+        };
+        let mut automaton = Automaton::new([(9, 9)], square_neighbours, rule).bounded(universe);
+
+        assert_eq!(0, automaton.population());
+        automaton.step();
+        assert_eq!(3, automaton.population());
+        automaton.step();
+        assert_eq!(1, automaton.population());
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::{BuildHasher, Hash};
+
+use crate::utilities::grid::Grid;
+
+// A compact little endian encoding for the on-disk input cache. Lengths and sizes are stored as u64.
+pub trait Binary: Sized {
+    fn encode(&self, bytes: &mut Vec<u8>);
+
+    // Consumes what it reads from the front of `bytes`, `None` when they run out or make no sense.
+    fn decode(bytes: &mut &[u8]) -> Option<Self>;
+}
+
+pub fn to_bytes<T: Binary>(value: &T) -> Vec<u8> {
+    let mut bytes = vec![];
+    value.encode(&mut bytes);
+    bytes
+}
+
+// Leftover bytes mean the data was written for some other type.
+pub fn from_bytes<T: Binary>(mut bytes: &[u8]) -> Option<T> {
+    let value = T::decode(&mut bytes)?;
+    bytes.is_empty().then_some(value)
+}
+
+fn take<const N: usize>(bytes: &mut &[u8]) -> Option<[u8; N]> {
+    let (head, rest) = bytes.split_first_chunk::<N>()?;
+    *bytes = rest;
+    Some(*head)
+}
+
+macro_rules! binary_number {
+    ($($number_type:ty),*) => {
+        $(
+            impl Binary for $number_type {
+                fn encode(&self, bytes: &mut Vec<u8>) {
+                    bytes.extend(self.to_le_bytes());
+                }
+
+                fn decode(bytes: &mut &[u8]) -> Option<Self> {
+                    take(bytes).map(<$number_type>::from_le_bytes)
+                }
+            }
+        )*
+    };
+}
+
+binary_number!(u8, u32, u64, i32, i64);
+
+impl Binary for usize {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        (*self as u64).encode(bytes);
+    }
+
+    fn decode(bytes: &mut &[u8]) -> Option<Self> {
+        u64::decode(bytes)?.try_into().ok()
+    }
+}
+
+impl Binary for isize {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        (*self as i64).encode(bytes);
+    }
+
+    fn decode(bytes: &mut &[u8]) -> Option<Self> {
+        i64::decode(bytes)?.try_into().ok()
+    }
+}
+
+impl Binary for bool {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        bytes.push(*self as u8);
+    }
+
+    fn decode(bytes: &mut &[u8]) -> Option<Self> {
+        match u8::decode(bytes)? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+}
+
+impl Binary for char {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        (*self as u32).encode(bytes);
+    }
+
+    fn decode(bytes: &mut &[u8]) -> Option<Self> {
+        char::from_u32(u32::decode(bytes)?)
+    }
+}
+
+impl Binary for String {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.len().encode(bytes);
+        bytes.extend(self.as_bytes());
+    }
+
+    fn decode(bytes: &mut &[u8]) -> Option<Self> {
+        let length = usize::decode(bytes)?;
+        if length > bytes.len() {
+            return None;
+        }
+
+        let (text, rest) = bytes.split_at(length);
+        *bytes = rest;
+        String::from_utf8(text.to_vec()).ok()
+    }
+}
+
+impl<T: Binary> Binary for Option<T> {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.is_some().encode(bytes);
+        if let Some(value) = self {
+            value.encode(bytes);
+        }
+    }
+
+    fn decode(bytes: &mut &[u8]) -> Option<Self> {
+        if bool::decode(bytes)? {
+            T::decode(bytes).map(Some)
+        } else {
+            Some(None)
+        }
+    }
+}
+
+fn encode_items<'a, T: Binary + 'a>(
+    length: usize,
+    items: impl Iterator<Item = &'a T>,
+    bytes: &mut Vec<u8>,
+) {
+    length.encode(bytes);
+    for item in items {
+        item.encode(bytes);
+    }
+}
+
+// The length is not trusted for preallocation, a corrupt file could claim anything.
+fn decode_items<T: Binary, C: FromIterator<T>>(bytes: &mut &[u8]) -> Option<C> {
+    let length = usize::decode(bytes)?;
+    (0..length).map(|_| T::decode(bytes)).collect()
+}
+
+impl<T: Binary> Binary for Vec<T> {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        encode_items(self.len(), self.iter(), bytes);
+    }
+
+    fn decode(bytes: &mut &[u8]) -> Option<Self> {
+        decode_items(bytes)
+    }
+}
+
+impl<T: Binary + Eq + Hash, S: BuildHasher + Default> Binary for HashSet<T, S> {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        encode_items(self.len(), self.iter(), bytes);
+    }
+
+    fn decode(bytes: &mut &[u8]) -> Option<Self> {
+        decode_items(bytes)
+    }
+}
+
+impl<K: Binary + Eq + Hash, V: Binary, S: BuildHasher + Default> Binary for HashMap<K, V, S> {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.len().encode(bytes);
+        for (key, value) in self {
+            key.encode(bytes);
+            value.encode(bytes);
+        }
+    }
+
+    fn decode(bytes: &mut &[u8]) -> Option<Self> {
+        let length = usize::decode(bytes)?;
+        (0..length)
+            .map(|_| Some((K::decode(bytes)?, V::decode(bytes)?)))
+            .collect()
+    }
+}
+
+impl<A: Binary, B: Binary> Binary for (A, B) {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.0.encode(bytes);
+        self.1.encode(bytes);
+    }
+
+    fn decode(bytes: &mut &[u8]) -> Option<Self> {
+        Some((A::decode(bytes)?, B::decode(bytes)?))
+    }
+}
+
+impl<T: Binary> Binary for Grid<T> {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.height().encode(bytes);
+        self.width().encode(bytes);
+        for cell in self.cells() {
+            cell.encode(bytes);
+        }
+    }
+
+    fn decode(bytes: &mut &[u8]) -> Option<Self> {
+        let (height, width) = (usize::decode(bytes)?, usize::decode(bytes)?);
+        let cells: Vec<T> = (0..height.checked_mul(width)?)
+            .map(|_| T::decode(bytes))
+            .collect::<Option<_>>()?;
+        let mut cells = cells.into_iter();
+
+        Some(Grid::from_fn(height, width, |_| cells.next().unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use rustc_hash::FxHashMap;
+    use std::fmt::Debug;
+
+    fn round_trip<T: Binary + Debug + PartialEq>(value: T) {
+        assert_eq!(Some(&value), from_bytes::<T>(&to_bytes(&value)).as_ref());
+    }
+
+    #[test]
+    fn test_round_trips() {
+        round_trip(vec![1721_i32, -979, 366]);
+        round_trip((vec![35_usize, 20, 15], 5_usize));
+        round_trip(vec![Some(7_usize), None, Some(59)]);
+        round_trip(String::from("mxmxvkd,sqjhc"));
+        round_trip(['a', 'b', 'c'].into_iter().collect::<HashSet<char>>());
+        round_trip(FxHashMap::from_iter([(1951_isize, vec![2311_isize, 2729])]));
+        round_trip(Grid::parse("#..\n.#.", |c| Some(c == '#')).unwrap());
+    }
+
+    #[test]
+    fn test_encoding() {
+        assert_eq!(
+            vec![2, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 255, 255, 255, 255],
+            to_bytes(&vec![1_i32, -1])
+        );
+    }
+
+    #[rstest]
+    #[case(vec![2, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0])]
+    #[case(vec![0, 0, 0, 0, 0, 0, 0, 0, 1])]
+    #[case(vec![255, 255, 255, 255, 255, 255, 255, 255])]
+    fn test_rejects_bad_bytes(#[case] bytes: Vec<u8>) {
+        assert_eq!(None, from_bytes::<Vec<i32>>(&bytes));
+    }
+}
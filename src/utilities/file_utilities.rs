@@ -1,6 +1,8 @@
-use std::fs::File;
-use std::io::{self, BufRead};
-use std::path::Path;
+use std::collections::VecDeque;
+use std::fs;
+use std::iter;
+
+use crate::error::SolveError;
 
 pub fn get_file_path(is_test: bool, day: u32, suffix: Option<&str>) -> String {
     let sub_folder = if is_test { "test" } else { "real" };
@@ -8,13 +10,89 @@ pub fn get_file_path(is_test: bool, day: u32, suffix: Option<&str>) -> String {
     format!("./data/{sub_folder}/day{day}{suffix}.txt")
 }
 
-pub fn read_lines<P>(filename: P) -> Vec<String>
-where
-    P: AsRef<Path>,
-{
-    let file = File::open(filename).unwrap();
-    io::BufReader::new(file)
+pub fn read_input(file_path: String) -> Result<String, SolveError> {
+    fs::read_to_string(&file_path).map_err(|error| SolveError::Io {
+        file: file_path,
+        message: error.to_string(),
+    })
+}
+
+pub struct Record<'a> {
+    pub line: usize,
+    pub lines: Vec<&'a str>,
+}
+
+// Blank lines before the first line and after the last one with content are left out, the numbers still count them.
+pub fn lines(input: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut lines = input
         .lines()
-        .map(|line| line.unwrap())
-        .collect()
+        .enumerate()
+        .skip_while(|(_, line)| line.trim().is_empty());
+    // Blank lines are held back until a line with content follows them.
+    let mut held = VecDeque::new();
+    let mut ready = 0;
+
+    iter::from_fn(move || {
+        while ready == 0 {
+            let (index, line) = lines.next()?;
+            held.push_back((index + 1, line));
+            if !line.trim().is_empty() {
+                ready = held.len();
+            }
+        }
+
+        ready -= 1;
+        held.pop_front()
+    })
+}
+
+// Blank lines separate the records, and runs of them never produce empty records.
+pub fn records(input: &str) -> impl Iterator<Item = Record<'_>> {
+    let mut lines = lines(input).peekable();
+
+    iter::from_fn(move || {
+        while lines.next_if(|(_, line)| line.trim().is_empty()).is_some() {}
+
+        let (line, first_line) = lines.next()?;
+        let mut record = Record {
+            line,
+            lines: vec![first_line],
+        };
+
+        while let Some((_, next_line)) = lines.next_if(|(_, line)| !line.trim().is_empty()) {
+            record.lines.push(next_line);
+        }
+
+        Some(record)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines() {
+        let numbered: Vec<_> = lines("a\n\nb").collect();
+        assert_eq!(vec![(1, "a"), (2, ""), (3, "b")], numbered);
+    }
+
+    #[test]
+    fn test_lines_skip_surrounding_blank_lines() {
+        let numbered: Vec<_> = lines("\n  \na\n\nb\n\n \t\n").collect();
+        assert_eq!(vec![(3, "a"), (4, ""), (5, "b")], numbered);
+    }
+
+    #[test]
+    fn test_records() {
+        let input = "\nPlayer 1:\n9 2\n\n\nPlayer 2:\n5\n  \n";
+        let records: Vec<_> = records(input)
+            .map(|record| (record.line, record.lines))
+            .collect();
+
+        assert_eq!(
+            vec![(2, vec!["Player 1:", "9 2"]), (6, vec!["Player 2:", "5"])],
+            records
+        );
+    }
 }
@@ -0,0 +1,206 @@
+use std::fmt;
+use std::str::FromStr;
+
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::character::complete::{char, digit1, space0};
+use nom::combinator::{cut, map_res, opt, recognize};
+use nom::error::{ContextError, ErrorKind, FromExternalError};
+use nom::sequence::{delimited, pair};
+use nom::{Err, IResult, Parser};
+
+use crate::error::ParseError;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Expected {
+    Literal(&'static str),
+    Description(&'static str),
+}
+
+impl fmt::Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expected::Literal(literal) => write!(f, "`{literal}`"),
+            Expected::Description(description) => write!(f, "{description}"),
+        }
+    }
+}
+
+// Keeps the input that was left when parsing failed, which is the span reported to the user.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct GrammarError<'a> {
+    pub at: &'a str,
+    pub expected: Option<Expected>,
+}
+
+impl<'a> nom::error::ParseError<&'a str> for GrammarError<'a> {
+    fn from_error_kind(input: &'a str, _kind: ErrorKind) -> Self {
+        Self {
+            at: input,
+            expected: None,
+        }
+    }
+
+    fn append(_input: &'a str, _kind: ErrorKind, other: Self) -> Self {
+        other
+    }
+
+    // Of two failed alternatives, the one that got further explains the problem best.
+    fn or(self, other: Self) -> Self {
+        if self.at.len() < other.at.len() {
+            self
+        } else {
+            other
+        }
+    }
+}
+
+// A context describes the whole construct when it failed right at its start, otherwise the inner error is more precise.
+impl<'a> ContextError<&'a str> for GrammarError<'a> {
+    fn add_context(input: &'a str, context: &'static str, other: Self) -> Self {
+        if other.expected.is_none() || input.len() == other.at.len() {
+            Self {
+                at: other.at,
+                expected: Some(Expected::Description(context)),
+            }
+        } else {
+            other
+        }
+    }
+}
+
+impl<'a, E> FromExternalError<&'a str, E> for GrammarError<'a> {
+    fn from_external_error(input: &'a str, _kind: ErrorKind, _error: E) -> Self {
+        Self {
+            at: input,
+            expected: None,
+        }
+    }
+}
+
+pub type GResult<'a, T> = IResult<&'a str, T, GrammarError<'a>>;
+
+// The whole text has to match, apart from trailing whitespace.
+pub fn parse_all<'a, T>(
+    text: &'a str,
+    mut parser: impl Parser<&'a str, T, GrammarError<'a>>,
+) -> Result<T, ParseError> {
+    match parser.parse(text) {
+        Ok((rest, value)) => {
+            let rest = rest.trim_start();
+
+            match rest.chars().next() {
+                None => Ok(value),
+                Some(c) => Err(ParseError::at(text, rest, format!("Unexpected `{c}`"))),
+            }
+        }
+        Err(Err::Error(error) | Err::Failure(error)) => {
+            let message = match (error.expected, error.at.chars().next()) {
+                (Some(expected), _) => format!("Expected {expected}"),
+                (None, Some(c)) => format!("Unexpected `{c}`"),
+                (None, None) => String::from("Unexpected end of line"),
+            };
+
+            Err(ParseError::at(text, error.at, message))
+        }
+        Err(Err::Incomplete(_)) => Err(ParseError::new(
+            text,
+            text.chars().count() + 1,
+            "Unexpected end of line",
+        )),
+    }
+}
+
+pub fn ws<'a, T>(
+    parser: impl Parser<&'a str, T, GrammarError<'a>>,
+) -> impl FnMut(&'a str) -> GResult<'a, T> {
+    let mut parser = delimited(space0, parser, space0);
+    move |input| parser.parse(input)
+}
+
+pub fn token<'a>(literal: &'static str) -> impl FnMut(&'a str) -> GResult<'a, &'a str> {
+    move |input: &'a str| {
+        ws(tag(literal))(input).map_err(|error| {
+            error.map(|error: GrammarError<'a>| GrammarError {
+                at: error.at,
+                expected: Some(Expected::Literal(literal)),
+            })
+        })
+    }
+}
+
+pub fn number<'a, T: FromStr>(input: &'a str) -> GResult<'a, T> {
+    let digits = recognize(pair(opt(alt((char('-'), char('+')))), digit1));
+
+    nom::error::context("a number", map_res(digits, str::parse))(input)
+}
+
+// Once a separator has been read, the next element is required.
+pub fn list<'a, T, S>(
+    separator: impl Parser<&'a str, S, GrammarError<'a>>,
+    element: impl Parser<&'a str, T, GrammarError<'a>>,
+) -> impl FnMut(&'a str) -> GResult<'a, Vec<T>> {
+    let mut separator = separator;
+    let mut element = element;
+
+    move |input| {
+        let (mut input, first) = element.parse(input)?;
+        let mut elements = vec![first];
+
+        while let Ok((rest, _)) = separator.parse(input) {
+            let (rest, next) = cut(|input| element.parse(input))(rest)?;
+            elements.push(next);
+            input = rest;
+        }
+
+        Ok((input, elements))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    use nom::sequence::separated_pair;
+
+    fn range(input: &str) -> GResult<'_, (u32, u32)> {
+        separated_pair(ws(number), token("-"), ws(number))(input)
+    }
+
+    #[rstest]
+    #[case("1-3", (1, 3))]
+    #[case("  1 -  3 ", (1, 3))]
+    fn test_parse_all(#[case] text: &str, #[case] expected: (u32, u32)) {
+        assert_eq!(expected, parse_all(text, range).unwrap());
+    }
+
+    #[rstest]
+    #[case("1+3", 2, "Expected `-`")]
+    #[case("1-x", 3, "Expected a number")]
+    #[case("1-3 x", 5, "Unexpected `x`")]
+    #[case("1-", 3, "Expected a number")]
+    fn test_parse_all_errors(#[case] text: &str, #[case] column: usize, #[case] message: &str) {
+        let error = parse_all(text, range).unwrap_err();
+
+        assert_eq!(column, error.column);
+        assert_eq!(message, error.message);
+    }
+
+    #[rstest]
+    #[case("1,2,3", Ok(vec![1, 2, 3]))]
+    #[case("1, 2 ,3", Ok(vec![1, 2, 3]))]
+    #[case("1,x", Err(3))]
+    fn test_list(#[case] text: &str, #[case] expected: Result<Vec<i32>, usize>) {
+        let parsed = parse_all(text, list(token(","), ws(number)));
+
+        assert_eq!(expected, parsed.map_err(|error| error.column));
+    }
+
+    #[rstest]
+    #[case("-12", -12)]
+    #[case("+7", 7)]
+    fn test_number(#[case] text: &str, #[case] expected: i64) {
+        assert_eq!(expected, parse_all(text, number::<i64>).unwrap());
+    }
+}
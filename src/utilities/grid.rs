@@ -0,0 +1,382 @@
+use std::iter;
+use std::ops::{Index, IndexMut};
+
+use crate::error::ParseError;
+use crate::utilities::file_utilities::lines;
+
+pub type Position = (usize, usize);
+pub type Direction = (isize, isize);
+
+pub const ORTHOGONAL: [Direction; 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+
+pub const ALL_DIRECTIONS: [Direction; 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+// Cells are stored row by row, and positions are `(row, column)` from the top left.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Grid<T> {
+    height: usize,
+    width: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn from_fn(height: usize, width: usize, mut cell: impl FnMut(Position) -> T) -> Self {
+        let cells = (0..height)
+            .flat_map(|row| (0..width).map(move |column| (row, column)))
+            .map(&mut cell)
+            .collect();
+
+        Self {
+            height,
+            width,
+            cells,
+        }
+    }
+
+    // There must be a row, every row must be as wide as the first one, none may be empty, and every character must map to a cell.
+    pub fn parse_lines<'a>(
+        lines: impl IntoIterator<Item = (usize, &'a str)>,
+        cell: impl Fn(char) -> Option<T>,
+    ) -> Result<Self, ParseError> {
+        let mut grid = Self {
+            height: 0,
+            width: 0,
+            cells: vec![],
+        };
+
+        for (number, line) in lines {
+            if line.is_empty() {
+                return Err(ParseError::new(line, 1, "Expected a row of cells").with_line(number));
+            }
+
+            if grid.height == 0 {
+                grid.width = line.chars().count();
+            }
+
+            let start = grid.cells.len();
+
+            for (index, c) in line.char_indices() {
+                let value = cell(c).ok_or_else(|| {
+                    ParseError::at(line, &line[index..], format!("Unexpected `{c}`"))
+                        .with_line(number)
+                })?;
+                grid.cells.push(value);
+            }
+
+            let row_width = grid.cells.len() - start;
+            if row_width != grid.width {
+                return Err(ParseError::new(
+                    line,
+                    line.len() + 1,
+                    format!("Expected a row of width {}", grid.width),
+                )
+                .with_line(number));
+            }
+
+            grid.height += 1;
+        }
+
+        if grid.height == 0 {
+            return Err(ParseError::new("", 1, "Expected at least one row").with_line(1));
+        }
+
+        Ok(grid)
+    }
+
+    pub fn parse(input: &str, cell: impl Fn(char) -> Option<T>) -> Result<Self, ParseError> {
+        Self::parse_lines(lines(input), cell)
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    pub fn row(&self, row: usize) -> &[T] {
+        &self.cells[row * self.width..(row + 1) * self.width]
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = Position> {
+        let width = self.width;
+        (0..self.height).flat_map(move |row| (0..width).map(move |column| (row, column)))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Position, &T)> {
+        self.positions().zip(self.cells.iter())
+    }
+
+    pub fn offset(&self, (row, column): Position, (dr, dc): Direction) -> Option<Position> {
+        let row = row.checked_add_signed(dr)?;
+        let column = column.checked_add_signed(dc)?;
+
+        (row < self.height && column < self.width).then_some((row, column))
+    }
+
+    pub fn get(&self, row: isize, column: isize) -> Option<&T> {
+        let row = usize::try_from(row).ok()?;
+        let column = usize::try_from(column).ok()?;
+
+        (row < self.height && column < self.width).then(|| &self[(row, column)])
+    }
+
+    // Wraps around both edges, so the grid repeats endlessly in every direction. An empty grid has nothing to repeat.
+    pub fn get_wrapping(&self, row: isize, column: isize) -> Option<&T> {
+        if self.height == 0 || self.width == 0 {
+            return None;
+        }
+
+        let row = row.rem_euclid(self.height as isize) as usize;
+        let column = column.rem_euclid(self.width as isize) as usize;
+
+        Some(&self[(row, column)])
+    }
+
+    pub fn neighbours_4(&self, position: Position) -> impl Iterator<Item = Position> + '_ {
+        ORTHOGONAL
+            .iter()
+            .filter_map(move |direction| self.offset(position, *direction))
+    }
+
+    pub fn neighbours_8(&self, position: Position) -> impl Iterator<Item = Position> + '_ {
+        ALL_DIRECTIONS
+            .iter()
+            .filter_map(move |direction| self.offset(position, *direction))
+    }
+
+    // Every position from the one next to `position` up to the edge of the grid.
+    pub fn ray(
+        &self,
+        position: Position,
+        direction: Direction,
+    ) -> impl Iterator<Item = Position> + '_ {
+        let mut current = position;
+
+        iter::from_fn(move || {
+            current = self.offset(current, direction)?;
+            Some(current)
+        })
+    }
+
+    pub fn map<U>(&self, f: impl Fn(&T) -> U) -> Grid<U> {
+        Grid {
+            height: self.height,
+            width: self.width,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    pub fn render(&self, cell: impl Fn(&T) -> char) -> String {
+        let mut text = String::with_capacity(self.height * (self.width + 1));
+
+        for row in 0..self.height {
+            text.extend(self.row(row).iter().map(&cell));
+            text.push('\n');
+        }
+
+        text
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(height: usize, width: usize, fill: T) -> Self {
+        Self {
+            height,
+            width,
+            cells: vec![fill; height * width],
+        }
+    }
+
+    pub fn rotate_clockwise(&self) -> Self {
+        Grid::from_fn(self.width, self.height, |(row, column)| {
+            self[(self.height - 1 - column, row)].clone()
+        })
+    }
+
+    // Mirrors left to right.
+    pub fn flip_horizontal(&self) -> Self {
+        Grid::from_fn(self.height, self.width, |(row, column)| {
+            self[(row, self.width - 1 - column)].clone()
+        })
+    }
+
+    // Mirrors top to bottom.
+    pub fn flip_vertical(&self) -> Self {
+        Grid::from_fn(self.height, self.width, |(row, column)| {
+            self[(self.height - 1 - row, column)].clone()
+        })
+    }
+
+    // The four rotations of the grid followed by the four rotations of its mirror image.
+    pub fn orientations(&self) -> Vec<Self> {
+        let mut orientations = Vec::with_capacity(8);
+
+        for start in [self.clone(), self.flip_horizontal()] {
+            let mut grid = start;
+            for _ in 0..4 {
+                let next = grid.rotate_clockwise();
+                orientations.push(grid);
+                grid = next;
+            }
+        }
+
+        orientations
+    }
+}
+
+impl<T> Index<Position> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (row, column): Position) -> &T {
+        assert!(
+            row < self.height && column < self.width,
+            "({row}, {column}) is outside a {}x{} grid",
+            self.height,
+            self.width
+        );
+        &self.cells[row * self.width + column]
+    }
+}
+
+impl<T> IndexMut<Position> for Grid<T> {
+    fn index_mut(&mut self, (row, column): Position) -> &mut T {
+        assert!(
+            row < self.height && column < self.width,
+            "({row}, {column}) is outside a {}x{} grid",
+            self.height,
+            self.width
+        );
+        &mut self.cells[row * self.width + column]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn digits(input: &str) -> Grid<u32> {
+        Grid::parse(input, |c| c.to_digit(10)).unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_render() {
+        let grid = digits("123\n456\n");
+
+        assert_eq!((2, 3), (grid.height(), grid.width()));
+        assert_eq!(6, grid[(1, 2)]);
+        assert_eq!(
+            "123\n456\n",
+            grid.render(|digit| char::from_digit(*digit, 10).unwrap())
+        );
+    }
+
+    #[rstest]
+    #[case("12\n3x", 2, 2)]
+    #[case("12\n345", 2, 4)]
+    #[case("123\n45", 2, 3)]
+    #[case("\n\n", 1, 1)]
+    #[case("12\n\n34", 2, 1)]
+    fn test_parse_errors(#[case] input: &str, #[case] line: usize, #[case] column: usize) {
+        let error = Grid::parse(input, |c| c.to_digit(10)).unwrap_err();
+
+        assert_eq!(Some(line), error.line);
+        assert_eq!(column, error.column);
+    }
+
+    #[rstest]
+    #[case(0, 0, Some(1))]
+    #[case(1, 2, Some(6))]
+    #[case(-1, 0, None)]
+    #[case(0, 3, None)]
+    fn test_get(#[case] row: isize, #[case] column: isize, #[case] expected: Option<u32>) {
+        assert_eq!(expected, digits("123\n456").get(row, column).copied());
+    }
+
+    #[rstest]
+    #[case(2, 3, 1)]
+    #[case(-1, -1, 6)]
+    #[case(5, 7, 5)]
+    fn test_get_wrapping(#[case] row: isize, #[case] column: isize, #[case] expected: u32) {
+        assert_eq!(
+            Some(&expected),
+            digits("123\n456").get_wrapping(row, column)
+        );
+    }
+
+    #[test]
+    fn test_get_wrapping_empty() {
+        assert_eq!(None, Grid::<u32>::new(0, 3, 0).get_wrapping(1, 1));
+        assert_eq!(None, Grid::<u32>::new(2, 0, 0).get_wrapping(1, 1));
+    }
+
+    #[rstest]
+    #[case((0, 0), vec![(0, 1), (1, 0)], vec![(0, 1), (1, 0), (1, 1)])]
+    #[case((1, 1), vec![(0, 1), (1, 2), (2, 1), (1, 0)], vec![(0, 0), (0, 1), (0, 2), (1, 0), (1, 2), (2, 0), (2, 1), (2, 2)])]
+    fn test_neighbours(
+        #[case] position: Position,
+        #[case] expected_4: Vec<Position>,
+        #[case] expected_8: Vec<Position>,
+    ) {
+        let grid = digits("123\n456\n789");
+
+        assert_eq!(expected_4, grid.neighbours_4(position).collect::<Vec<_>>());
+        assert_eq!(expected_8, grid.neighbours_8(position).collect::<Vec<_>>());
+    }
+
+    #[rstest]
+    #[case((0, 0), (1, 1), vec![(1, 1), (2, 2)])]
+    #[case((1, 2), (0, -1), vec![(1, 1), (1, 0)])]
+    #[case((0, 0), (-1, 0), vec![])]
+    fn test_ray(
+        #[case] position: Position,
+        #[case] direction: Direction,
+        #[case] expected: Vec<Position>,
+    ) {
+        let grid = digits("123\n456\n789");
+
+        assert_eq!(expected, grid.ray(position, direction).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_transforms() {
+        let grid = digits("123\n456");
+
+        assert_eq!(digits("41\n52\n63"), grid.rotate_clockwise());
+        assert_eq!(digits("321\n654"), grid.flip_horizontal());
+        assert_eq!(digits("456\n123"), grid.flip_vertical());
+        assert_eq!(
+            grid,
+            grid.rotate_clockwise()
+                .rotate_clockwise()
+                .rotate_clockwise()
+                .rotate_clockwise()
+        );
+    }
+
+    #[test]
+    fn test_orientations_are_distinct() {
+        let orientations = digits("12\n34").orientations();
+
+        assert_eq!(8, orientations.len());
+        assert!(orientations
+            .iter()
+            .enumerate()
+            .all(|(index, grid)| !orientations[..index].contains(grid)));
+    }
+}
@@ -0,0 +1,414 @@
+use std::fmt::Write;
+use std::str::FromStr;
+
+use crate::utilities::grid::Grid;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Color(pub u8, pub u8, pub u8);
+
+impl Color {
+    pub const WHITE: Color = Color(255, 255, 255);
+    pub const BLACK: Color = Color(0, 0, 0);
+
+    fn hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+
+    // Bitmaps keep only the dark colours.
+    fn is_dark(&self) -> bool {
+        let [red, green, blue] = [self.0, self.1, self.2].map(u32::from);
+        299 * red + 587 * green + 114 * blue < 128_000
+    }
+}
+
+pub type Point = (f64, f64);
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shape {
+    Rectangle {
+        corner: Point,
+        size: Point,
+        color: Color,
+    },
+    Polygon {
+        points: Vec<Point>,
+        color: Color,
+    },
+    Polyline {
+        points: Vec<Point>,
+        color: Color,
+    },
+}
+
+// Shapes are placed in scene units with y growing downwards, and `scale` pixels make up a unit.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scene {
+    pub width: f64,
+    pub height: f64,
+    pub scale: f64,
+    pub background: Color,
+    pub shapes: Vec<Shape>,
+}
+
+impl Scene {
+    pub const DEFAULT_SCALE: f64 = 8.0;
+
+    pub fn new(width: f64, height: f64, background: Color) -> Self {
+        Self {
+            width,
+            height,
+            scale: Self::DEFAULT_SCALE,
+            background,
+            shapes: vec![],
+        }
+    }
+
+    // Scales the scene so that its longer side is `pixels` wide.
+    pub fn fit(mut self, pixels: f64) -> Self {
+        self.scale = pixels / self.width.max(self.height).max(1.0);
+        self
+    }
+
+    pub fn push(&mut self, shape: Shape) {
+        self.shapes.push(shape);
+    }
+
+    pub fn rectangle(&mut self, corner: Point, size: Point, color: Color) {
+        self.push(Shape::Rectangle {
+            corner,
+            size,
+            color,
+        });
+    }
+
+    // A unit square, for scenes laid out like a grid.
+    pub fn cell(&mut self, row: usize, column: usize, color: Color) {
+        self.rectangle((column as f64, row as f64), (1.0, 1.0), color);
+    }
+
+    fn pixel_size(&self) -> (usize, usize) {
+        let pixels = |length: f64| ((length * self.scale).ceil() as usize).max(1);
+        (pixels(self.height), pixels(self.width))
+    }
+
+    // A pixel takes the colour of the last shape covering its centre, lines are drawn one pixel wide.
+    pub fn rasterize(&self) -> Grid<Color> {
+        let (height, width) = self.pixel_size();
+        let mut canvas = Grid::new(height, width, self.background);
+        let pixel = |(x, y): Point| {
+            (
+                (x * self.scale).floor() as isize,
+                (y * self.scale).floor() as isize,
+            )
+        };
+
+        for shape in &self.shapes {
+            match shape {
+                Shape::Rectangle {
+                    corner: (x, y),
+                    size: (dx, dy),
+                    color,
+                } => {
+                    let points = [(*x, *y), (x + dx, *y), (x + dx, y + dy), (*x, y + dy)];
+                    fill_polygon(&mut canvas, &points, self.scale, *color);
+                }
+                Shape::Polygon { points, color } => {
+                    fill_polygon(&mut canvas, points, self.scale, *color)
+                }
+                Shape::Polyline { points, color } => {
+                    for segment in points.windows(2) {
+                        draw_line(&mut canvas, pixel(segment[0]), pixel(segment[1]), *color);
+                    }
+                }
+            }
+        }
+
+        canvas
+    }
+
+    // Coordinates stay in scene units through the view box, so lines keep a one pixel stroke.
+    pub fn to_svg(&self) -> String {
+        let (height, width) = self.pixel_size();
+        let points = |points: &[Point]| {
+            points
+                .iter()
+                .map(|(x, y)| format!("{x},{y}"))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {} {}\">\n",
+            self.width, self.height
+        );
+        let _ = writeln!(
+            svg,
+            "<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>",
+            self.background.hex()
+        );
+
+        for shape in &self.shapes {
+            let _ = match shape {
+                Shape::Rectangle {
+                    corner: (x, y),
+                    size: (dx, dy),
+                    color,
+                } => writeln!(
+                    svg,
+                    "<rect x=\"{x}\" y=\"{y}\" width=\"{dx}\" height=\"{dy}\" fill=\"{}\"/>",
+                    color.hex()
+                ),
+                Shape::Polygon { points: corners, color } => writeln!(
+                    svg,
+                    "<polygon points=\"{}\" fill=\"{}\"/>",
+                    points(corners),
+                    color.hex()
+                ),
+                Shape::Polyline { points: line, color } => writeln!(
+                    svg,
+                    "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"1\" vector-effect=\"non-scaling-stroke\"/>",
+                    points(line),
+                    color.hex()
+                ),
+            };
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+// Even-odd rule, tested at the centre of each pixel inside the bounding box.
+fn fill_polygon(canvas: &mut Grid<Color>, points: &[Point], scale: f64, color: Color) {
+    let points: Vec<Point> = points.iter().map(|(x, y)| (x * scale, y * scale)).collect();
+    let bound = |coordinate: fn(&Point) -> f64, limit: usize| {
+        let values = points.iter().map(coordinate);
+        let low = values
+            .clone()
+            .fold(f64::INFINITY, f64::min)
+            .floor()
+            .max(0.0) as usize;
+        let high = values.fold(f64::NEG_INFINITY, f64::max).ceil().max(0.0) as usize;
+        low..high.min(limit)
+    };
+
+    for row in bound(|point| point.1, canvas.height()) {
+        for column in bound(|point| point.0, canvas.width()) {
+            let (x, y) = (column as f64 + 0.5, row as f64 + 0.5);
+            let mut inside = false;
+
+            for (index, (x1, y1)) in points.iter().enumerate() {
+                let (x2, y2) = points[(index + 1) % points.len()];
+                if (*y1 > y) != (y2 > y) && x < x1 + (y - y1) * (x2 - x1) / (y2 - y1) {
+                    inside = !inside;
+                }
+            }
+
+            if inside {
+                canvas[(row, column)] = color;
+            }
+        }
+    }
+}
+
+// Bresenham's line, clipped to the canvas.
+fn draw_line(
+    canvas: &mut Grid<Color>,
+    (mut x, mut y): (isize, isize),
+    (x_end, y_end): (isize, isize),
+    color: Color,
+) {
+    let (dx, dy) = ((x_end - x).abs(), -(y_end - y).abs());
+    let (step_x, step_y) = ((x_end - x).signum(), (y_end - y).signum());
+    let mut error = dx + dy;
+
+    loop {
+        if canvas.get(y, x).is_some() {
+            canvas[(y as usize, x as usize)] = color;
+        }
+        if (x, y) == (x_end, y_end) {
+            break;
+        }
+
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += step_y;
+        }
+    }
+}
+
+// Binary P6, full colour.
+pub fn ppm(canvas: &Grid<Color>) -> Vec<u8> {
+    let mut bytes = format!("P6\n{} {}\n255\n", canvas.width(), canvas.height()).into_bytes();
+    bytes.extend(
+        canvas
+            .cells()
+            .iter()
+            .flat_map(|Color(red, green, blue)| [*red, *green, *blue]),
+    );
+    bytes
+}
+
+// Binary P4, one bit per pixel with rows padded to whole bytes. Set bits are black.
+pub fn pbm(canvas: &Grid<Color>) -> Vec<u8> {
+    let mut bytes = format!("P4\n{} {}\n", canvas.width(), canvas.height()).into_bytes();
+
+    for row in 0..canvas.height() {
+        bytes.extend(canvas.row(row).chunks(8).map(|pixels| {
+            pixels
+                .iter()
+                .enumerate()
+                .filter(|(_, color)| color.is_dark())
+                .fold(0u8, |byte, (bit, _)| byte | (0x80 >> bit))
+        }));
+    }
+
+    bytes
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ImageFormat {
+    Ppm,
+    Pbm,
+    Svg,
+}
+
+impl FromStr for ImageFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "ppm" => Ok(ImageFormat::Ppm),
+            "pbm" => Ok(ImageFormat::Pbm),
+            "svg" => Ok(ImageFormat::Svg),
+            _ => Err(format!(
+                "Unknown image format {format}, expected ppm, pbm or svg"
+            )),
+        }
+    }
+}
+
+impl ImageFormat {
+    pub fn encode(&self, scene: &Scene) -> Vec<u8> {
+        match self {
+            ImageFormat::Ppm => ppm(&scene.rasterize()),
+            ImageFormat::Pbm => pbm(&scene.rasterize()),
+            ImageFormat::Svg => scene.to_svg().into_bytes(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    const RED: Color = Color(255, 0, 0);
+
+    fn render(canvas: &Grid<Color>) -> String {
+        canvas.render(|color| match *color {
+            Color::WHITE => '.',
+            Color::BLACK => '#',
+            _ => 'r',
+        })
+    }
+
+    #[test]
+    fn test_rasterize_shapes() {
+        let mut scene = Scene::new(3.0, 2.0, Color::WHITE);
+        scene.scale = 2.0;
+        scene.cell(0, 1, Color::BLACK);
+        scene.push(Shape::Polygon {
+            points: vec![(0.0, 1.0), (1.0, 2.0), (0.0, 2.0)],
+            color: RED,
+        });
+        scene.push(Shape::Polyline {
+            points: vec![(2.0, 1.0), (2.75, 1.75)],
+            color: Color::BLACK,
+        });
+
+        assert_eq!(
+            "..##..\n..##..\n....#.\nr....#\n",
+            render(&scene.rasterize())
+        );
+    }
+
+    #[rstest]
+    #[case((0.0, 0.0), (4.0, 1.0), "##...\n..###\n")]
+    #[case((0.0, 1.0), (4.0, 0.0), "..###\n##...\n")]
+    #[case((1.0, 0.0), (2.0, 1.0), ".#...\n..#..\n")]
+    fn test_rasterize_shallow_lines(
+        #[case] from: Point,
+        #[case] to: Point,
+        #[case] expected: &str,
+    ) {
+        let mut scene = Scene::new(5.0, 2.0, Color::WHITE);
+        scene.scale = 1.0;
+        scene.push(Shape::Polyline {
+            points: vec![from, to],
+            color: Color::BLACK,
+        });
+
+        assert_eq!(expected, render(&scene.rasterize()));
+    }
+
+    #[test]
+    fn test_ppm() {
+        let mut scene = Scene::new(2.0, 1.0, Color::WHITE);
+        scene.scale = 1.0;
+        scene.cell(0, 1, RED);
+
+        assert_eq!(
+            b"P6\n2 1\n255\n\xff\xff\xff\xff\x00\x00".to_vec(),
+            ImageFormat::Ppm.encode(&scene)
+        );
+    }
+
+    #[test]
+    fn test_pbm_pads_rows() {
+        let mut scene = Scene::new(10.0, 1.0, Color::WHITE);
+        scene.scale = 1.0;
+        scene.cell(0, 0, Color::BLACK);
+        scene.cell(0, 1, RED);
+        scene.cell(0, 9, Color::BLACK);
+
+        assert_eq!(
+            b"P4\n10 1\n\xc0\x40".to_vec(),
+            ImageFormat::Pbm.encode(&scene)
+        );
+    }
+
+    #[test]
+    fn test_svg() {
+        let mut scene = Scene::new(2.0, 1.0, Color::WHITE);
+        scene.cell(0, 1, RED);
+        scene.push(Shape::Polyline {
+            points: vec![(0.0, 0.0), (1.5, 1.0)],
+            color: Color::BLACK,
+        });
+
+        assert_eq!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"16\" height=\"8\" viewBox=\"0 0 2 1\">\n\
+             <rect width=\"100%\" height=\"100%\" fill=\"#ffffff\"/>\n\
+             <rect x=\"1\" y=\"0\" width=\"1\" height=\"1\" fill=\"#ff0000\"/>\n\
+             <polyline points=\"0,0 1.5,1\" fill=\"none\" stroke=\"#000000\" stroke-width=\"1\" vector-effect=\"non-scaling-stroke\"/>\n\
+             </svg>\n",
+            scene.to_svg()
+        );
+    }
+
+    #[rstest]
+    #[case("svg", Ok(ImageFormat::Svg))]
+    #[case(
+        "png",
+        Err(String::from("Unknown image format png, expected ppm, pbm or svg"))
+    )]
+    fn test_parse_format(#[case] format: &str, #[case] expected: Result<ImageFormat, String>) {
+        assert_eq!(expected, format.parse());
+    }
+}
@@ -0,0 +1,159 @@
+use std::io::{self, Read};
+
+use crate::error::SolveError;
+use crate::utilities::file_utilities::{get_file_path, read_input};
+
+macro_rules! examples {
+    ($($name:literal),* $(,)?) => {
+        [$(($name, include_str!(concat!("../../inputs/examples/", $name)))),*]
+    };
+}
+
+// Real puzzle inputs are personal to each account, so only the published examples are embedded.
+const EXAMPLES: [(&str, &str); 26] = examples![
+    "day1.txt",
+    "day2.txt",
+    "day3.txt",
+    "day4.txt",
+    "day5.txt",
+    "day6.txt",
+    "day7.txt",
+    "day8.txt",
+    "day9.txt",
+    "day10.txt",
+    "day11.txt",
+    "day12.txt",
+    "day13.txt",
+    "day14.txt",
+    "day15.txt",
+    "day16-a.txt",
+    "day16-b.txt",
+    "day17.txt",
+    "day18.txt",
+    "day19.txt",
+    "day20.txt",
+    "day21.txt",
+    "day22.txt",
+    "day23.txt",
+    "day24.txt",
+    "day25.txt",
+];
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum InputSource {
+    Path(String),
+    Stdin,
+    Text(String),
+    Embedded {
+        name: &'static str,
+        text: &'static str,
+    },
+}
+
+impl InputSource {
+    // Without a suffix, days with several examples fall back to their first one.
+    pub fn example(day: u32, suffix: Option<&str>) -> Option<Self> {
+        let file_name = format!("day{day}{}.txt", suffix.unwrap_or(""));
+        let first_of_day = format!("day{day}-");
+
+        EXAMPLES
+            .iter()
+            .find(|(name, _)| *name == file_name)
+            .or_else(|| match suffix {
+                Some(_) => None,
+                None => EXAMPLES
+                    .iter()
+                    .find(|(name, _)| name.starts_with(&first_of_day)),
+            })
+            .map(|(name, text)| InputSource::Embedded { name, text })
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            InputSource::Path(path) => path.clone(),
+            InputSource::Stdin => String::from("<stdin>"),
+            InputSource::Text(_) => String::from("<input>"),
+            InputSource::Embedded { name, .. } => String::from(*name),
+        }
+    }
+
+    pub fn read(&self) -> Result<String, SolveError> {
+        match self {
+            InputSource::Path(path) => read_input(path.clone()),
+            InputSource::Stdin => {
+                let mut input = String::new();
+                io::stdin()
+                    .read_to_string(&mut input)
+                    .map_err(|error| SolveError::Io {
+                        file: self.name(),
+                        message: error.to_string(),
+                    })?;
+                Ok(input)
+            }
+            InputSource::Text(text) => Ok(text.clone()),
+            InputSource::Embedded { text, .. } => Ok(String::from(*text)),
+        }
+    }
+}
+
+impl From<&str> for InputSource {
+    fn from(text: &str) -> Self {
+        InputSource::Text(String::from(text))
+    }
+}
+
+// The example comes from the embedded inputs, the real input from the data directory.
+pub fn puzzle_input(is_test: bool, day: u32, suffix: Option<&str>) -> InputSource {
+    let embedded = if is_test {
+        InputSource::example(day, suffix)
+    } else {
+        None
+    };
+
+    embedded.unwrap_or_else(|| InputSource::Path(get_file_path(is_test, day, suffix)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(1, None, Some("day1.txt"))]
+    #[case(16, Some("-b"), Some("day16-b.txt"))]
+    #[case(16, None, Some("day16-a.txt"))]
+    #[case(16, Some("-c"), None)]
+    #[case(26, None, None)]
+    fn test_example(
+        #[case] day: u32,
+        #[case] suffix: Option<&str>,
+        #[case] expected: Option<&str>,
+    ) {
+        assert_eq!(
+            expected.map(String::from),
+            InputSource::example(day, suffix).map(|source| source.name())
+        );
+    }
+
+    #[test]
+    fn test_every_day_has_an_example() {
+        for day in 1..=25 {
+            let suffix = if day == 16 { Some("-a") } else { None };
+            let example = InputSource::example(day, suffix).unwrap();
+            assert!(!example.read().unwrap().is_empty(), "day {day}");
+        }
+    }
+
+    #[test]
+    fn test_read_text() {
+        assert_eq!("0,3,6", InputSource::from("0,3,6").read().unwrap());
+    }
+
+    #[test]
+    fn test_read_missing_path() {
+        let error = InputSource::Path(String::from("./missing/day1.txt"))
+            .read()
+            .unwrap_err();
+        assert!(matches!(error, SolveError::Io { file, .. } if file == "./missing/day1.txt"));
+    }
+}
@@ -0,0 +1,218 @@
+use std::env;
+use std::fmt::Debug;
+
+use crate::utilities::random::{Rng, DEFAULT_SEED};
+
+pub const DEFAULT_CASES: usize = 200;
+// Shrinking stops here even if smaller counterexamples might remain.
+const MAX_SHRINKS: usize = 1000;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Counterexample<T> {
+    pub seed: u64,
+    pub input: T,
+    pub message: String,
+    pub shrinks: usize,
+}
+
+// Random cases come from the seeded `Rng`, so a failure replays with `PROPERTY_SEED=<seed>`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Property {
+    seed: u64,
+    cases: usize,
+}
+
+impl Default for Property {
+    fn default() -> Self {
+        let seed = env::var("PROPERTY_SEED")
+            .ok()
+            .and_then(|seed| seed.parse().ok())
+            .unwrap_or(DEFAULT_SEED);
+
+        Self {
+            seed,
+            cases: DEFAULT_CASES,
+        }
+    }
+}
+
+impl Property {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn seed(self, seed: u64) -> Self {
+        Self { seed, ..self }
+    }
+
+    pub fn cases(self, cases: usize) -> Self {
+        Self { cases, ..self }
+    }
+
+    // The first failing case, shrunk by repeatedly taking the first smaller input that still fails.
+    pub fn find_counterexample<T, G, S, P>(
+        &self,
+        generate: G,
+        shrink: S,
+        property: P,
+    ) -> Option<Counterexample<T>>
+    where
+        T: Clone + Debug,
+        G: Fn(&mut Rng) -> T,
+        S: Fn(&T) -> Vec<T>,
+        P: Fn(&T) -> Result<(), String>,
+    {
+        let (seed, input, message) = (0..self.cases).find_map(|case| {
+            let seed = self.seed.wrapping_add(case as u64);
+            let input = generate(&mut Rng::new(seed));
+            property(&input).err().map(|message| (seed, input, message))
+        })?;
+
+        let mut counterexample = Counterexample {
+            seed,
+            input,
+            message,
+            shrinks: 0,
+        };
+
+        while counterexample.shrinks < MAX_SHRINKS {
+            let Some((input, message)) = shrink(&counterexample.input)
+                .into_iter()
+                .find_map(|smaller| property(&smaller).err().map(|message| (smaller, message)))
+            else {
+                break;
+            };
+
+            counterexample.input = input;
+            counterexample.message = message;
+            counterexample.shrinks += 1;
+        }
+
+        Some(counterexample)
+    }
+
+    pub fn check<T, G, S, P>(&self, generate: G, shrink: S, property: P)
+    where
+        T: Clone + Debug,
+        G: Fn(&mut Rng) -> T,
+        S: Fn(&T) -> Vec<T>,
+        P: Fn(&T) -> Result<(), String>,
+    {
+        if let Some(counterexample) = self.find_counterexample(generate, shrink, property) {
+            panic!(
+                "Property failed with seed {} after {} shrinks: {}\nMinimal input: {:?}",
+                counterexample.seed,
+                counterexample.shrinks,
+                counterexample.message,
+                counterexample.input
+            );
+        }
+    }
+}
+
+pub fn agree<T: Debug + PartialEq>(fast: T, naive: T) -> Result<(), String> {
+    if fast == naive {
+        Ok(())
+    } else {
+        Err(format!("fast gave {fast:?} but naive gave {naive:?}"))
+    }
+}
+
+// Halves first, then every single removal, so large inputs shrink quickly.
+pub fn shrink_vec<T: Clone>(items: &[T]) -> Vec<Vec<T>> {
+    let mut smaller = vec![];
+
+    if items.len() > 1 {
+        let half = items.len() / 2;
+        smaller.push(items[..half].to_vec());
+        smaller.push(items[half..].to_vec());
+    }
+
+    smaller.extend((0..items.len()).map(|index| {
+        let mut fewer = items.to_vec();
+        fewer.remove(index);
+        fewer
+    }));
+
+    smaller
+}
+
+pub fn shrink_elements<T: Clone>(items: &[T], shrink: impl Fn(&T) -> Vec<T>) -> Vec<Vec<T>> {
+    (0..items.len())
+        .flat_map(|index| {
+            shrink(&items[index]).into_iter().map(move |item| {
+                let mut simpler = items.to_vec();
+                simpler[index] = item;
+                simpler
+            })
+        })
+        .collect()
+}
+
+pub fn shrink_number(number: usize) -> Vec<usize> {
+    let mut smaller: Vec<usize> = [0, number / 2, number.saturating_sub(1)]
+        .into_iter()
+        .filter(|smaller| *smaller < number)
+        .collect();
+    smaller.dedup();
+    smaller
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn random_numbers(rng: &mut Rng) -> Vec<usize> {
+        (0..rng.range(0..=20))
+            .map(|_| rng.range(0..=100) as usize)
+            .collect()
+    }
+
+    fn shrink_numbers(numbers: &[usize]) -> Vec<Vec<usize>> {
+        let mut smaller = shrink_vec(numbers);
+        smaller.extend(shrink_elements(numbers, |number| shrink_number(*number)));
+        smaller
+    }
+
+    #[test]
+    fn test_shrinks_to_minimal_counterexample() {
+        let counterexample = Property::new()
+            .seed(1)
+            .find_counterexample(
+                random_numbers,
+                |numbers| shrink_numbers(numbers),
+                |numbers| {
+                    if numbers.iter().all(|number| *number < 10) {
+                        Ok(())
+                    } else {
+                        Err(String::from("too large"))
+                    }
+                },
+            )
+            .unwrap();
+
+        assert_eq!(vec![10], counterexample.input);
+        assert!(counterexample.shrinks > 0);
+    }
+
+    #[test]
+    fn test_passing_property_has_no_counterexample() {
+        let counterexample = Property::new().find_counterexample(
+            random_numbers,
+            |numbers| shrink_numbers(numbers),
+            |numbers| agree(numbers.iter().sum::<usize>(), numbers.iter().rev().sum()),
+        );
+
+        assert_eq!(None, counterexample);
+    }
+
+    #[rstest]
+    #[case(0, vec![])]
+    #[case(1, vec![0])]
+    #[case(2, vec![0, 1])]
+    #[case(10, vec![0, 5, 9])]
+    fn test_shrink_number(#[case] number: usize, #[case] expected: Vec<usize>) {
+        assert_eq!(expected, shrink_number(number));
+    }
+}
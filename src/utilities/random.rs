@@ -0,0 +1,137 @@
+use std::ops::RangeInclusive;
+
+use rustc_hash::FxHashSet;
+
+pub const DEFAULT_SEED: u64 = 2020;
+
+// SplitMix64: small, fast and the same on every platform, so a seed always gives the same input.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in `0..bound`, without the bias of a plain modulo.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0, "Cannot pick a number below 0");
+
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let value = self.next_u64();
+            if value < zone {
+                return value % bound;
+            }
+        }
+    }
+
+    pub fn range(&mut self, range: RangeInclusive<i64>) -> i64 {
+        let (start, end) = range.into_inner();
+        assert!(
+            start <= end,
+            "Cannot pick from the empty range {start}..={end}"
+        );
+
+        let span = end.abs_diff(start);
+        match span.checked_add(1) {
+            Some(bound) => start.wrapping_add(self.below(bound) as i64),
+            None => self.next_u64() as i64,
+        }
+    }
+
+    pub fn index(&mut self, length: usize) -> usize {
+        self.below(length as u64) as usize
+    }
+
+    pub fn chance(&mut self, numerator: u64, denominator: u64) -> bool {
+        self.below(denominator) < numerator
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.index(items.len())]
+    }
+
+    // Fisher-Yates.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for index in (1..items.len()).rev() {
+            items.swap(index, self.index(index + 1));
+        }
+    }
+
+    // `count` distinct values from the range, in random order.
+    pub fn distinct(&mut self, range: RangeInclusive<i64>, count: usize) -> Vec<i64> {
+        let available = range.end().abs_diff(*range.start()).saturating_add(1);
+        assert!(
+            count as u64 <= available,
+            "Cannot pick {count} distinct values from {range:?}"
+        );
+
+        let mut picked = FxHashSet::default();
+        let mut values = Vec::with_capacity(count);
+
+        while values.len() < count {
+            let value = self.range(range.clone());
+            if picked.insert(value) {
+                values.push(value);
+            }
+        }
+
+        values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[test]
+    fn test_seed_is_reproducible() {
+        let mut rng_1 = Rng::new(42);
+        let mut rng_2 = Rng::new(42);
+        let mut rng_3 = Rng::new(43);
+
+        let sequence_1: Vec<_> = (0..5).map(|_| rng_1.next_u64()).collect();
+        let sequence_2: Vec<_> = (0..5).map(|_| rng_2.next_u64()).collect();
+        let sequence_3: Vec<_> = (0..5).map(|_| rng_3.next_u64()).collect();
+
+        assert_eq!(sequence_1, sequence_2);
+        assert_ne!(sequence_1, sequence_3);
+    }
+
+    #[rstest]
+    #[case(-3..=3)]
+    #[case(5..=5)]
+    #[case(i64::MIN..=i64::MAX)]
+    fn test_range(#[case] range: RangeInclusive<i64>) {
+        let mut rng = Rng::new(7);
+
+        assert!((0..100).all(|_| range.contains(&rng.range(range.clone()))));
+    }
+
+    #[test]
+    fn test_shuffle_and_distinct() {
+        let mut rng = Rng::new(1);
+        let mut items: Vec<i64> = (0..20).collect();
+        rng.shuffle(&mut items);
+        items.sort();
+
+        assert_eq!((0..20).collect::<Vec<_>>(), items);
+
+        let mut values = rng.distinct(1..=10, 10);
+        values.sort();
+        assert_eq!((1..=10).collect::<Vec<_>>(), values);
+    }
+}
@@ -0,0 +1,247 @@
+use std::fmt;
+
+use num::BigInt;
+
+use crate::error::ParseError;
+use crate::utilities::file_utilities::lines;
+
+// Only the subset of TOML the answer ledger needs: tables, integers and basic strings.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Value {
+    Integer(BigInt),
+    String(String),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Integer(integer) => write!(f, "{integer}"),
+            Value::String(string) => write!(f, "{string}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Entry<'a> {
+    pub table: Vec<String>,
+    pub key: String,
+    pub value: Value,
+    pub line: usize,
+    pub text: &'a str,
+}
+
+// A basic string, with `\"` and `\\` escaped so the reader gets it back unchanged.
+pub fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (index, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..index],
+            _ => {}
+        }
+    }
+
+    line
+}
+
+// The string opening `text`, and how many bytes it takes up with its quotes.
+fn parse_string(line: &str, text: &str) -> Option<Result<(String, usize), ParseError>> {
+    let mut string = String::new();
+    let mut chars = text.char_indices().skip(1);
+
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' => return Some(Ok((string, index + 1))),
+            '\\' => match chars.next() {
+                Some((_, '"')) => string.push('"'),
+                Some((_, '\\')) => string.push('\\'),
+                _ => {
+                    return Some(Err(ParseError::at(
+                        line,
+                        &text[index..],
+                        "Expected `\\\"` or `\\\\`",
+                    )))
+                }
+            },
+            c => string.push(c),
+        }
+    }
+
+    None
+}
+
+fn parse_key(line: &str, key: &str) -> Result<(String, usize), ParseError> {
+    if key.starts_with('"') {
+        return parse_string(line, key)
+            .unwrap_or_else(|| Err(ParseError::at(line, key, "Unterminated quoted key")));
+    }
+
+    let end = key
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+        .unwrap_or(key.len());
+
+    if end == 0 {
+        return Err(ParseError::at(line, key, "Expected a key"));
+    }
+
+    Ok((String::from(&key[..end]), end))
+}
+
+fn parse_key_path(line: &str, path: &str) -> Result<Vec<String>, ParseError> {
+    let mut keys = vec![];
+    let mut rest = path.trim();
+
+    loop {
+        let (key, length) = parse_key(line, rest)?;
+        keys.push(key);
+
+        let remainder = rest[length..].trim_start();
+        if remainder.is_empty() {
+            return Ok(keys);
+        }
+
+        rest = remainder
+            .strip_prefix('.')
+            .ok_or_else(|| ParseError::at(line, remainder, "Expected `.` between keys"))?
+            .trim_start();
+    }
+}
+
+fn parse_value(line: &str, value: &str) -> Result<Value, ParseError> {
+    if value.starts_with('"') {
+        return match parse_string(line, value) {
+            Some(Ok((string, length))) if length == value.len() => Ok(Value::String(string)),
+            Some(Err(error)) => Err(error),
+            _ => Err(ParseError::at(
+                line,
+                value,
+                "Expected a single quoted string",
+            )),
+        };
+    }
+
+    value
+        .replace('_', "")
+        .parse::<BigInt>()
+        .map(Value::Integer)
+        .map_err(|_| {
+            ParseError::at(
+                line,
+                value,
+                format!("Expected a number or a string, found `{value}`"),
+            )
+        })
+}
+
+pub fn parse(input: &str) -> Result<Vec<Entry<'_>>, ParseError> {
+    let mut table = vec![];
+    let mut entries: Vec<Entry> = vec![];
+
+    for (number, line) in lines(input) {
+        let content = strip_comment(line).trim();
+
+        if content.is_empty() {
+            continue;
+        }
+
+        if let Some(header) = content.strip_prefix('[') {
+            let header = header
+                .strip_suffix(']')
+                .ok_or_else(|| ParseError::at(line, content, "Expected `]`").with_line(number))?;
+            table = parse_key_path(line, header).map_err(|error| error.with_line(number))?;
+            continue;
+        }
+
+        let (key, value) = content.split_once('=').ok_or_else(|| {
+            ParseError::at(line, content, "Expected `key = value`").with_line(number)
+        })?;
+        let key_text = key.trim();
+        let (key, length) = parse_key(line, key_text).map_err(|error| error.with_line(number))?;
+
+        if length != key_text.len() {
+            return Err(ParseError::at(line, &key_text[length..], "Expected `=`").with_line(number));
+        }
+
+        let value = parse_value(line, value.trim()).map_err(|error| error.with_line(number))?;
+
+        if entries
+            .iter()
+            .any(|entry| entry.table == table && entry.key == key)
+        {
+            return Err(
+                ParseError::at(line, content, format!("Duplicate key `{key}`")).with_line(number),
+            );
+        }
+
+        entries.push(Entry {
+            table: table.clone(),
+            key,
+            value,
+            line: number,
+            text: line,
+        });
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[test]
+    fn test_parse() {
+        let input = "# Answers\n[day1.example]\npart_1 = 514_579 # comment\n\n[day21.\"inputs/a#b.txt\"]\npart_2 = \"mxmxvkd,sqjhc\"\n";
+        let entries = parse(input).unwrap();
+
+        assert_eq!(2, entries.len());
+        assert_eq!(vec!["day1", "example"], entries[0].table);
+        assert_eq!("part_1", entries[0].key);
+        assert_eq!(Value::Integer(BigInt::from(514579)), entries[0].value);
+        assert_eq!(3, entries[0].line);
+        assert_eq!(vec!["day21", "inputs/a#b.txt"], entries[1].table);
+        assert_eq!(
+            Value::String(String::from("mxmxvkd,sqjhc")),
+            entries[1].value
+        );
+    }
+
+    #[rstest]
+    #[case("plain", "\"plain\"")]
+    #[case("say \"hi\"", "\"say \\\"hi\\\"\"")]
+    #[case("C:\\day1.txt", "\"C:\\\\day1.txt\"")]
+    fn test_quote_round_trips(#[case] text: &str, #[case] quoted: &str) {
+        let line = format!("[{}]\nkey = {quoted} # comment", quote(&format!("#{text}")));
+        let entries = parse(&line).unwrap();
+
+        assert_eq!(quoted, quote(text));
+        assert_eq!(vec![format!("#{text}")], entries[0].table);
+        assert_eq!(Value::String(String::from(text)), entries[0].value);
+    }
+
+    #[rstest]
+    #[case("[day1\npart_1 = 1", 1, 1)]
+    #[case("[day1]\npart_1 1", 2, 1)]
+    #[case("[day1]\npart_1 = abc", 2, 10)]
+    #[case("[day1]\npart_1 = \"abc", 2, 10)]
+    #[case("[day1]\npart_1 = 1\npart_1 = 2", 3, 1)]
+    #[case("[day1 example]", 1, 7)]
+    #[case("[day1]\npart 1 = 2", 2, 5)]
+    #[case("[day1]\npart_1 = \"a\\qb\"", 2, 12)]
+    #[case("[day1]\npart_1 = \"a\" \"b\"", 2, 10)]
+    fn test_parse_errors(#[case] input: &str, #[case] line: usize, #[case] column: usize) {
+        let error = parse(input).unwrap_err();
+
+        assert_eq!(Some(line), error.line);
+        assert_eq!(column, error.column);
+    }
+}